@@ -0,0 +1,175 @@
+//! Counts the allocations made while parsing a large definition.
+//!
+//! Borrowing parsers only allocate for the AST's structure and for strings that had
+//! to be unescaped. The JSON baseline reads the same JSON through `std::io::Read`, from
+//! which serde cannot borrow, so it copies every string as parsing did before definitions
+//! borrowed from their input. The RRDL baseline only lexes the input into a token list,
+//! which is about the memory the parser needs to buffer the tokens it reads.
+//! Run with `cargo bench --bench allocations`.
+//!
+//! For 2,000 HTTP statements, per iteration:
+//!
+//! | parse                      | allocations | bytes  |
+//! |----------------------------|-------------|--------|
+//! | json copied (baseline)     | 72,017      | 7.2 MB |
+//! | json borrowed              | 34,012      | 6.9 MB |
+//! | rrdl lexed (baseline)      | 17          | 21 MB  |
+//! | rrdl borrowed              | 52,171      | 29 MB  |
+//!
+//! RRDL makes about 9 more allocations per statement than borrowed JSON, for the clauses
+//! and actions the parser collects before building each statement, and most of its bytes
+//! are the buffered tokens. Before well-formed input was parsed with `Cheap` errors, every
+//! alternative that failed along the way built a set of expected tokens, and the same parse
+//! made about 154,000 allocations of 66 MB.
+
+use recast_lang::ast::action::{
+    AuthBasedAction, RapidRecastAction, RapidRecastRbacAction, RapidRecastRbacObject,
+    RapidRecastRbacPolicy, RapidRecastRbacSubject, TopicObject, UserIdentifier,
+};
+use recast_lang::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastHttpMethod};
+use recast_lang::ast::{RapidAstStatement, RapidRecastDefinition};
+use recast_lang::json::JsonRRDL;
+use recast_lang::rrdl::{lex, RrdlParser, RrdlPrinter};
+use recast_lang::{ParseRRDL, SaveRRDL};
+use serde::Deserialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const STATEMENTS: usize = 2_000;
+const ITERATIONS: u32 = 20;
+
+fn user(index: usize) -> UserIdentifier<'static> {
+    UserIdentifier {
+        namespace: Cow::Owned(format!("namespace-{}", index % 10)),
+        username: Cow::Owned(format!("user-{}", index)),
+    }
+}
+
+fn large_definition() -> RapidRecastDefinition<'static> {
+    let ast = (0..STATEMENTS)
+        .map(|i| {
+            let metadata = (0..4)
+                .map(|k| {
+                    (
+                        Cow::Owned(format!("key-{}", k)),
+                        Cow::Owned(format!("value-{}", i)),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+            RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+                HttpStatement {
+                    sequence: (i % 4) as u8,
+                    paths: vec![
+                        Cow::Owned(format!("/users/{}", i)),
+                        Cow::Owned(format!("/users/{}/profile", i)),
+                    ],
+                    methods: vec![RapidRecastHttpMethod::GET, RapidRecastHttpMethod::POST],
+                    min_body_bytes: None,
+                    max_body_bytes: None,
+                    host: None,
+                    content_type: None,
+                    headers: vec![],
+                    query: vec![],
+                    actions: vec![
+                        RapidRecastAction::AuthBasedAction(AuthBasedAction::CreateUser {
+                            subject: user(i),
+                            password: Some(Cow::Owned(format!("password-{}", i))),
+                        }),
+                        RapidRecastAction::AuthBasedAction(AuthBasedAction::AddMetadataToUser {
+                            subject: user(i),
+                            metadata,
+                        }),
+                        RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+                            subject: user(i),
+                            policy: vec![RapidRecastRbacPolicy {
+                                subject: RapidRecastRbacSubject::Anon,
+                                object: RapidRecastRbacObject::Topic(TopicObject::NamespaceTopic(
+                                    Cow::Owned(format!("namespace-{}", i % 10)),
+                                    Cow::Owned(format!("topic-{}", i)),
+                                )),
+                                action: RapidRecastRbacAction::Read,
+                            }],
+                        }),
+                    ],
+                },
+            ))
+        })
+        .collect();
+    RapidRecastDefinition {
+        id: Cow::Borrowed("large-definition"),
+        language_version: "1.0.0".parse().unwrap(),
+        file_version: "1.0.0".parse().unwrap(),
+        name: Some(Cow::Borrowed("Large definition")),
+        description: None,
+        ast,
+    }
+}
+
+fn measure<T>(name: &str, parse: impl Fn() -> T) {
+    black_box(parse());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(parse());
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS as usize;
+    let bytes = (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) / ITERATIONS as usize;
+    println!(
+        "{:<28} {:>10} allocations {:>12} bytes {:>10.2?}",
+        name, allocations, bytes, elapsed
+    );
+}
+
+fn main() {
+    let definition = large_definition();
+    let json = String::from_utf8(JsonRRDL {}.save_rrdl(&definition).unwrap()).unwrap();
+    let rrdl = String::from_utf8(RrdlPrinter {}.save_rrdl(&definition).unwrap()).unwrap();
+    println!(
+        "{} HTTP statements, {} bytes of JSON, {} bytes of RRDL",
+        STATEMENTS,
+        json.len(),
+        rrdl.len()
+    );
+
+    measure("json copied (baseline)", || {
+        let mut reader = serde_json::Deserializer::from_reader(json.as_bytes());
+        let definition = RapidRecastDefinition::deserialize(&mut reader).unwrap();
+        definition.validate().unwrap();
+        definition
+    });
+    measure("json borrowed", || JsonRRDL {}.parse_rrdl(&json).unwrap());
+    measure("json borrowed + into_owned", || {
+        JsonRRDL {}.parse_rrdl(&json).unwrap().into_owned()
+    });
+    measure("rrdl lexed (baseline)", || lex(&rrdl).unwrap());
+    measure("rrdl borrowed", || RrdlParser {}.parse_rrdl(&rrdl).unwrap());
+    measure("rrdl borrowed + into_owned", || {
+        RrdlParser {}.parse_rrdl(&rrdl).unwrap().into_owned()
+    });
+}
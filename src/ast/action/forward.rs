@@ -0,0 +1,202 @@
+//! Actions forwarding a request to upstream services.
+
+use crate::ast::action::{render_template, template_params, RecastValue};
+use crate::ast::{borrow, owned};
+use crate::error::RrdlError;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// Forwards the request to one of several upstream services and relays its response
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ForwardAction<'a> {
+    /// The base URLs of the upstreams, such as `http://users:8080`; at least one
+    #[serde(borrow, deserialize_with = "borrow::vec")]
+    pub upstreams: Vec<Cow<'a, str>>,
+    /// How requests are spread across the upstreams
+    #[serde(default)]
+    pub load_balancing: LoadBalancingStrategy,
+    /// A template for the upstream path, such as `/v2/users/{id}`, or the request path if not set
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
+    pub rewrite_path: Option<Cow<'a, str>>,
+    /// How long each attempt may take, in milliseconds, or no limit if not set
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Headers removed from the request before it is forwarded, by lowercase name
+    #[serde(borrow, default, deserialize_with = "removed_headers")]
+    pub remove_headers: Vec<Cow<'a, str>>,
+    /// Headers added to the request by lowercase name, replacing any the client sent
+    #[serde(borrow, default, deserialize_with = "added_headers")]
+    pub add_headers: BTreeMap<Cow<'a, str>, Cow<'a, str>>,
+    /// When failed attempts are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl ForwardAction<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ForwardAction<'static> {
+        ForwardAction {
+            upstreams: self.upstreams.into_iter().map(owned).collect(),
+            load_balancing: self.load_balancing,
+            rewrite_path: self.rewrite_path.map(owned),
+            timeout_ms: self.timeout_ms,
+            remove_headers: self.remove_headers.into_iter().map(owned).collect(),
+            add_headers: self
+                .add_headers
+                .into_iter()
+                .map(|(name, value)| (owned(name), owned(value)))
+                .collect(),
+            retry: self.retry,
+        }
+    }
+
+    /// Check the upstream URLs, the header names, the path rewrite and the retried statuses.
+    pub fn check(&self) -> Result<(), RrdlError> {
+        if self.upstreams.is_empty() {
+            return Err(RrdlError::new(
+                "A forward action needs at least one upstream",
+            ));
+        }
+        for upstream in &self.upstreams {
+            check_upstream(upstream)?;
+        }
+        let mut names = self.remove_headers.iter().chain(self.add_headers.keys());
+        if let Some(name) = names.find(|name| name.bytes().any(|b| b.is_ascii_uppercase())) {
+            return Err(RrdlError::new(format!(
+                "Invalid header name '{}', it must be lowercase",
+                name
+            )));
+        }
+        if let Some(rewrite) = &self.rewrite_path {
+            if !rewrite.starts_with('/') {
+                return Err(RrdlError::new(format!(
+                    "Invalid path rewrite '{}', it must start with '/'",
+                    rewrite
+                )));
+            }
+            template_params(rewrite)?;
+        }
+        if self.timeout_ms == Some(0) {
+            return Err(RrdlError::new("A forward timeout must be longer than 0ms"));
+        }
+        match self
+            .retry
+            .statuses
+            .iter()
+            .find(|status| !(100..=599).contains(*status))
+        {
+            Some(status) => Err(RrdlError::new(format!(
+                "Invalid retry status {}, expected 100 to 599",
+                status
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The path to request from the upstream: the rendered rewrite, or the request path.
+    pub fn upstream_path(
+        &self,
+        request_path: &str,
+        params: &[(&str, RecastValue)],
+    ) -> Result<String, RrdlError> {
+        match &self.rewrite_path {
+            Some(rewrite) => render_template(rewrite, params),
+            None => Ok(request_path.to_string()),
+        }
+    }
+}
+
+/// Header names are case-insensitive, so they are kept in lowercase.
+pub(crate) fn header_name(name: Cow<str>) -> Cow<str> {
+    match name.bytes().any(|b| b.is_ascii_uppercase()) {
+        true => Cow::Owned(name.to_ascii_lowercase()),
+        false => name,
+    }
+}
+
+/// Add a header by its lowercase name, refusing one that is already added.
+pub(crate) fn add_header<'a>(
+    headers: &mut BTreeMap<Cow<'a, str>, Cow<'a, str>>,
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+) -> Result<(), RrdlError> {
+    let name = header_name(name);
+    if headers.contains_key(&name) {
+        return Err(RrdlError::new(format!(
+            "Header '{}' is added more than once",
+            name
+        )));
+    }
+    headers.insert(name, value);
+    Ok(())
+}
+
+fn added_headers<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<BTreeMap<Cow<'a, str>, Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut headers = BTreeMap::new();
+    for (name, value) in borrow::entries(deserializer)? {
+        add_header(&mut headers, name, value).map_err(|e| de::Error::custom(e.message))?;
+    }
+    Ok(headers)
+}
+
+fn removed_headers<'de: 'a, 'a, D>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(borrow::vec(deserializer)?
+        .into_iter()
+        .map(header_name)
+        .collect())
+}
+
+/// Check an upstream is an `http://` or `https://` URL with a host.
+fn check_upstream(upstream: &str) -> Result<(), RrdlError> {
+    let authority = upstream
+        .strip_prefix("http://")
+        .or_else(|| upstream.strip_prefix("https://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or(""));
+    match authority {
+        Some(authority) if !authority.is_empty() && !authority.contains(char::is_whitespace) => {
+            Ok(())
+        }
+        _ => Err(RrdlError::new(format!(
+            "Invalid upstream '{}', expected an http:// or https:// URL with a host",
+            upstream
+        ))),
+    }
+}
+
+/// How requests are spread across upstreams
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum LoadBalancingStrategy {
+    /// Each upstream in turn
+    #[default]
+    RoundRobin,
+    /// An upstream picked at random
+    Random,
+    /// The upstream with the fewest requests in flight
+    LeastConnections,
+    /// The first upstream, moving to the next only when one fails
+    Failover,
+}
+
+/// When failed attempts are retried.
+/// Connection failures and timeouts are always retried, while attempts remain.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RetryPolicy {
+    /// How many times to retry after the first attempt; none by default
+    #[serde(default)]
+    pub attempts: u32,
+    /// How long to wait before each retry, in milliseconds
+    #[serde(default)]
+    pub backoff_ms: u64,
+    /// Upstream response statuses that are retried as well, such as 502 and 503
+    #[serde(default)]
+    pub statuses: Vec<u16>,
+}
@@ -1,6 +1,19 @@
 //! Actions available in the AST.
+#[cfg(test)]
+mod test;
+
+mod forward;
+mod response;
+
+pub(crate) use forward::{add_header, header_name};
+pub use forward::{ForwardAction, LoadBalancingStrategy, RetryPolicy};
+pub use response::{
+    render_template, served_file, template_params, ResponseBasedAction, ResponseBody,
+    ResponseHeader,
+};
 
 use crate::ast::protocol::RapidRecastProtocolType;
+use crate::ast::{borrow, owned};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
@@ -9,9 +22,37 @@ use std::collections::BTreeMap;
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum RapidRecastAction<'a> {
     /// An action that is related to authentication
+    #[serde(borrow)]
     AuthBasedAction(AuthBasedAction<'a>),
     /// An action that is based on logic
+    #[serde(borrow)]
     LogicBasedAction(LogicBasedAction<'a>),
+    /// An action that decides the response to a request
+    #[serde(borrow)]
+    ResponseBasedAction(ResponseBasedAction<'a>),
+    /// An action that forwards the request to upstream services
+    #[serde(borrow)]
+    ForwardAction(ForwardAction<'a>),
+}
+
+impl RapidRecastAction<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidRecastAction<'static> {
+        match self {
+            RapidRecastAction::AuthBasedAction(auth) => {
+                RapidRecastAction::AuthBasedAction(auth.into_owned())
+            }
+            RapidRecastAction::LogicBasedAction(logic) => {
+                RapidRecastAction::LogicBasedAction(logic.into_owned())
+            }
+            RapidRecastAction::ResponseBasedAction(response) => {
+                RapidRecastAction::ResponseBasedAction(response.into_owned())
+            }
+            RapidRecastAction::ForwardAction(forward) => {
+                RapidRecastAction::ForwardAction(forward.into_owned())
+            }
+        }
+    }
 }
 
 /// Actions that resolve to a logic related change
@@ -20,94 +61,246 @@ pub enum LogicBasedAction<'a> {
     /// If-condition-style blocks
     ConditionBlock {
         /// The condition type associated with the if block
+        #[serde(borrow)]
         condition: ConditionStatement<'a>,
         /// The code that is executed if the statement is true
+        #[serde(borrow)]
         if_true: Box<RapidRecastAction<'a>>,
         /// THe code that is executed if the statement is false
+        #[serde(borrow)]
         if_false: Option<Box<RapidRecastAction<'a>>>,
     },
 }
 
+impl LogicBasedAction<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> LogicBasedAction<'static> {
+        match self {
+            LogicBasedAction::ConditionBlock {
+                condition,
+                if_true,
+                if_false,
+            } => LogicBasedAction::ConditionBlock {
+                condition: condition.into_owned(),
+                if_true: Box::new(if_true.into_owned()),
+                if_false: if_false.map(|action| Box::new(action.into_owned())),
+            },
+        }
+    }
+}
+
 /// A value that can be used in statements
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum RecastValue<'a> {
     /// A pre-declared variable reference
+    #[serde(borrow)]
     Param(Cow<'a, str>),
     /// A string literal
+    #[serde(borrow)]
     String(Cow<'a, str>),
     /// A number literal
     Number(f64),
 }
 
+impl RecastValue<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RecastValue<'static> {
+        match self {
+            RecastValue::Param(param) => RecastValue::Param(owned(param)),
+            RecastValue::String(s) => RecastValue::String(owned(s)),
+            RecastValue::Number(n) => RecastValue::Number(n),
+        }
+    }
+}
+
 /// A condition statement
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum ConditionStatement<'a> {
     /// Left == Right
-    Equals(RecastValue<'a>, RecastValue<'a>),
+    Equals(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left != Right
-    NotEquals(RecastValue<'a>, RecastValue<'a>),
+    NotEquals(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left > Right
-    GreaterThan(RecastValue<'a>, RecastValue<'a>),
+    GreaterThan(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left < Right
-    LessThan(RecastValue<'a>, RecastValue<'a>),
+    LessThan(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left >= Right
-    GreaterThanOrEqual(RecastValue<'a>, RecastValue<'a>),
+    GreaterThanOrEqual(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left <= Right
-    LessThanOrEqual(RecastValue<'a>, RecastValue<'a>),
+    LessThanOrEqual(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left && Right
-    And(RecastValue<'a>, RecastValue<'a>),
+    And(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// Left || Right
-    Or(RecastValue<'a>, RecastValue<'a>),
+    Or(
+        #[serde(borrow)] RecastValue<'a>,
+        #[serde(borrow)] RecastValue<'a>,
+    ),
     /// !Value
+    #[serde(borrow)]
     Not(RecastValue<'a>),
 }
 
+impl ConditionStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ConditionStatement<'static> {
+        match self {
+            ConditionStatement::Equals(left, right) => {
+                ConditionStatement::Equals(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::NotEquals(left, right) => {
+                ConditionStatement::NotEquals(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::GreaterThan(left, right) => {
+                ConditionStatement::GreaterThan(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::LessThan(left, right) => {
+                ConditionStatement::LessThan(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::GreaterThanOrEqual(left, right) => {
+                ConditionStatement::GreaterThanOrEqual(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::LessThanOrEqual(left, right) => {
+                ConditionStatement::LessThanOrEqual(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::And(left, right) => {
+                ConditionStatement::And(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::Or(left, right) => {
+                ConditionStatement::Or(left.into_owned(), right.into_owned())
+            }
+            ConditionStatement::Not(value) => ConditionStatement::Not(value.into_owned()),
+        }
+    }
+}
+
 /// Actions that resolve to an authentication related change
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum AuthBasedAction<'a> {
     /// Creates a new user with the specified password
     CreateUser {
         /// The user or role
+        #[serde(borrow)]
         subject: UserIdentifier<'a>,
         /// The password for the user
+        #[serde(borrow, default, deserialize_with = "borrow::option")]
         password: Option<Cow<'a, str>>,
     },
     /// Adds non-system metadata that will be accessible to the system for the user
     AddMetadataToUser {
         /// The user or role
+        #[serde(borrow)]
         subject: UserIdentifier<'a>,
         /// Metadata to be added to a subject (user or role)
+        #[serde(borrow, deserialize_with = "borrow::map")]
         metadata: BTreeMap<Cow<'a, str>, Cow<'a, str>>,
     },
     /// Adds a policy to the system
     GrantPermissions {
         /// The user or role
+        #[serde(borrow)]
         subject: UserIdentifier<'a>,
         /// Policies to be added to a subject (user or role)
+        #[serde(borrow)]
         policy: Vec<RapidRecastRbacPolicy<'a>>,
     },
 }
 
+impl AuthBasedAction<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AuthBasedAction<'static> {
+        match self {
+            AuthBasedAction::CreateUser { subject, password } => AuthBasedAction::CreateUser {
+                subject: subject.into_owned(),
+                password: password.map(owned),
+            },
+            AuthBasedAction::AddMetadataToUser { subject, metadata } => {
+                AuthBasedAction::AddMetadataToUser {
+                    subject: subject.into_owned(),
+                    metadata: metadata
+                        .into_iter()
+                        .map(|(key, value)| (owned(key), owned(value)))
+                        .collect(),
+                }
+            }
+            AuthBasedAction::GrantPermissions { subject, policy } => {
+                AuthBasedAction::GrantPermissions {
+                    subject: subject.into_owned(),
+                    policy: policy
+                        .into_iter()
+                        .map(RapidRecastRbacPolicy::into_owned)
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
 /// Convenience struct user data for actions
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct UserIdentifier<'a> {
     /// The namespace the user belongs to
+    #[serde(borrow)]
     pub namespace: Cow<'a, str>,
     /// The username within that namespace
+    #[serde(borrow)]
     pub username: Cow<'a, str>,
 }
 
+impl UserIdentifier<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> UserIdentifier<'static> {
+        UserIdentifier {
+            namespace: owned(self.namespace),
+            username: owned(self.username),
+        }
+    }
+}
+
 /// A way of declaring a RapidRecast RBAC policy
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct RapidRecastRbacPolicy<'a> {
     /// The subject of the policy
+    #[serde(borrow)]
     pub subject: RapidRecastRbacSubject<'a>,
     /// The object of the policy
+    #[serde(borrow)]
     pub object: RapidRecastRbacObject<'a>,
     /// The action of the policy
     pub action: RapidRecastRbacAction,
 }
 
+impl RapidRecastRbacPolicy<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidRecastRbacPolicy<'static> {
+        RapidRecastRbacPolicy {
+            subject: self.subject.into_owned(),
+            object: self.object.into_owned(),
+            action: self.action,
+        }
+    }
+}
+
 /// The subjects available in RapidRecast RBAC
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum RapidRecastRbacSubject<'a> {
@@ -117,40 +310,105 @@ pub enum RapidRecastRbacSubject<'a> {
     Anon,
     /// A specific user on the given namespace
     /// (namespace, user)
-    UserOrRole(Cow<'a, str>, Cow<'a, str>),
+    UserOrRole(#[serde(borrow)] Cow<'a, str>, #[serde(borrow)] Cow<'a, str>),
+}
+
+impl RapidRecastRbacSubject<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidRecastRbacSubject<'static> {
+        match self {
+            RapidRecastRbacSubject::Admin => RapidRecastRbacSubject::Admin,
+            RapidRecastRbacSubject::Anon => RapidRecastRbacSubject::Anon,
+            RapidRecastRbacSubject::UserOrRole(namespace, username) => {
+                RapidRecastRbacSubject::UserOrRole(owned(namespace), owned(username))
+            }
+        }
+    }
 }
 
 /// The objects available in RapidRecast RBAC
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum RapidRecastRbacObject<'a> {
     /// A Namespace within RapidRecast
+    #[serde(borrow)]
     Namespace(NamespaceObject<'a>),
     /// A Protocol provided by within RapidRecast
     Protocol(RapidRecastProtocolType),
     /// A Topic within RapidRecast
+    #[serde(borrow)]
     Topic(TopicObject<'a>),
     /// A client available to RapidRecast
     Client(ClientObject),
+    /// The MQTT topics matching a topic filter
+    #[serde(borrow)]
+    MqttTopic(Cow<'a, str>),
     /// A RapidRecast Definition Language object
     Model(ModelObject),
 }
 
+impl RapidRecastRbacObject<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidRecastRbacObject<'static> {
+        match self {
+            RapidRecastRbacObject::Namespace(namespace) => {
+                RapidRecastRbacObject::Namespace(namespace.into_owned())
+            }
+            RapidRecastRbacObject::Protocol(protocol) => RapidRecastRbacObject::Protocol(protocol),
+            RapidRecastRbacObject::Topic(topic) => RapidRecastRbacObject::Topic(topic.into_owned()),
+            RapidRecastRbacObject::Client(client) => RapidRecastRbacObject::Client(client),
+            RapidRecastRbacObject::MqttTopic(filter) => {
+                RapidRecastRbacObject::MqttTopic(owned(filter))
+            }
+            RapidRecastRbacObject::Model(model) => RapidRecastRbacObject::Model(model),
+        }
+    }
+}
+
 /// A Namespace within RapidRecast
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum NamespaceObject<'a> {
     /// A namespace that exists
+    #[serde(borrow)]
     ExistingNamespace(Cow<'a, str>),
     /// A namespace that does not exist
+    #[serde(borrow)]
     NonExistingNamespace(Cow<'a, str>),
 }
 
+impl NamespaceObject<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> NamespaceObject<'static> {
+        match self {
+            NamespaceObject::ExistingNamespace(namespace) => {
+                NamespaceObject::ExistingNamespace(owned(namespace))
+            }
+            NamespaceObject::NonExistingNamespace(namespace) => {
+                NamespaceObject::NonExistingNamespace(owned(namespace))
+            }
+        }
+    }
+}
+
 /// A topic within RapidRecast
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum TopicObject<'a> {
     /// A topic that does not exist
+    #[serde(borrow)]
     NonExistingTopic(Cow<'a, str>),
     /// A topic that exists
-    NamespaceTopic(Cow<'a, str>, Cow<'a, str>),
+    NamespaceTopic(#[serde(borrow)] Cow<'a, str>, #[serde(borrow)] Cow<'a, str>),
+}
+
+impl TopicObject<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> TopicObject<'static> {
+        match self {
+            TopicObject::NonExistingTopic(topic) => TopicObject::NonExistingTopic(owned(topic)),
+            TopicObject::NamespaceTopic(namespace, topic) => {
+                TopicObject::NamespaceTopic(owned(namespace), owned(topic))
+            }
+        }
+    }
 }
 
 /// The clients available to the system. Cached or otherwise to-be created.
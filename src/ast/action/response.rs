@@ -0,0 +1,275 @@
+//! Actions deciding the response to an HTTP request.
+
+use crate::ast::action::RecastValue;
+use crate::ast::protocol::is_param_name;
+use crate::ast::{borrow, owned};
+use crate::error::RrdlError;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+/// Actions that decide the response to a request
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ResponseBasedAction<'a> {
+    /// Responds with a fixed status, headers and body
+    Respond {
+        /// The status code, from 100 to 599
+        status: u16,
+        /// Headers to send, in order; a name may repeat
+        #[serde(borrow)]
+        headers: Vec<ResponseHeader<'a>>,
+        /// The body to send, or none
+        #[serde(borrow)]
+        body: Option<ResponseBody<'a>>,
+    },
+    /// Redirects the client to another location
+    Redirect {
+        /// The status code, one of 301, 302, 303, 307 or 308
+        status: u16,
+        /// A template for the location to redirect to, such as `/users/{id}`
+        #[serde(borrow)]
+        location: Cow<'a, str>,
+    },
+    /// Responds with the contents of a file
+    ServeFile {
+        /// The directory files are served from
+        #[serde(borrow)]
+        root: Cow<'a, str>,
+        /// A template for the file's path within the root, such as `{rest}`
+        #[serde(borrow)]
+        path: Cow<'a, str>,
+        /// The content type to send, or one guessed from the file if not set
+        #[serde(borrow, default, deserialize_with = "borrow::option")]
+        content_type: Option<Cow<'a, str>>,
+    },
+}
+
+impl ResponseBasedAction<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ResponseBasedAction<'static> {
+        match self {
+            ResponseBasedAction::Respond {
+                status,
+                headers,
+                body,
+            } => ResponseBasedAction::Respond {
+                status,
+                headers: headers
+                    .into_iter()
+                    .map(ResponseHeader::into_owned)
+                    .collect(),
+                body: body.map(ResponseBody::into_owned),
+            },
+            ResponseBasedAction::Redirect { status, location } => ResponseBasedAction::Redirect {
+                status,
+                location: owned(location),
+            },
+            ResponseBasedAction::ServeFile {
+                root,
+                path,
+                content_type,
+            } => ResponseBasedAction::ServeFile {
+                root: owned(root),
+                path: owned(path),
+                content_type: content_type.map(owned),
+            },
+        }
+    }
+
+    /// Check the status codes and templates.
+    pub fn check(&self) -> Result<(), RrdlError> {
+        match self {
+            ResponseBasedAction::Respond { status, body, .. } => {
+                if !(100..=599).contains(status) {
+                    return Err(RrdlError::new(format!(
+                        "Invalid response status {}, expected 100 to 599",
+                        status
+                    )));
+                }
+                match body {
+                    Some(ResponseBody::Template(template)) => template_params(template).map(|_| ()),
+                    _ => Ok(()),
+                }
+            }
+            ResponseBasedAction::Redirect { status, location } => {
+                if !REDIRECT_STATUSES.contains(status) {
+                    return Err(RrdlError::new(format!(
+                        "Invalid redirect status {}, expected one of 301, 302, 303, 307, 308",
+                        status
+                    )));
+                }
+                template_params(location).map(|_| ())
+            }
+            ResponseBasedAction::ServeFile { path, .. } => template_params(path).map(|_| ()),
+        }
+    }
+}
+
+/// The status codes a redirect may use
+const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+
+/// A response header
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseHeader<'a> {
+    /// The header name
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The header value, sent as written
+    #[serde(borrow)]
+    pub value: Cow<'a, str>,
+}
+
+impl ResponseHeader<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ResponseHeader<'static> {
+        ResponseHeader {
+            name: owned(self.name),
+            value: owned(self.value),
+        }
+    }
+}
+
+/// A response body
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ResponseBody<'a> {
+    /// Sent as written
+    #[serde(borrow)]
+    Text(Cow<'a, str>),
+    /// Sent with each `{name}` placeholder replaced by the param's value
+    #[serde(borrow)]
+    Template(Cow<'a, str>),
+}
+
+impl ResponseBody<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ResponseBody<'static> {
+        match self {
+            ResponseBody::Text(text) => ResponseBody::Text(owned(text)),
+            ResponseBody::Template(template) => ResponseBody::Template(owned(template)),
+        }
+    }
+}
+
+enum Piece<'t> {
+    Text(&'t str),
+    Param(&'t str),
+}
+
+/// Split a template into text and placeholders, where `{{` and `}}` stand for literal braces.
+fn pieces(template: &str) -> Result<Vec<Piece<'_>>, RrdlError> {
+    let invalid = |reason: String| {
+        Err(RrdlError::new(format!(
+            "Invalid template '{}', {}",
+            template, reason
+        )))
+    };
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        pieces.push(Piece::Text(&rest[..index]));
+        let brace = &rest[index..index + 1];
+        let after = &rest[index + 1..];
+        if after.starts_with(brace) {
+            pieces.push(Piece::Text(brace));
+            rest = &after[1..];
+        } else if brace == "}" {
+            return invalid("'}' is never opened, write '}}' for a literal brace".to_string());
+        } else {
+            let Some(end) = after.find('}') else {
+                return invalid("'{' is never closed, write '{{' for a literal brace".to_string());
+            };
+            let name = &after[..end];
+            if !is_param_name(name) {
+                return invalid(format!("'{}' is not a valid param name", name));
+            }
+            pieces.push(Piece::Param(name));
+            rest = &after[end + 1..];
+        }
+    }
+    pieces.push(Piece::Text(rest));
+    Ok(pieces)
+}
+
+/// Check a template, returning the names of the params it uses, in order.
+pub fn template_params(template: &str) -> Result<Vec<&str>, RrdlError> {
+    Ok(pieces(template)?
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Param(name) => Some(name),
+            Piece::Text(_) => None,
+        })
+        .collect())
+}
+
+/// Fill a template's placeholders from params, such as those captured by a path template.
+/// Whole numbers are written without a fraction.
+pub fn render_template(
+    template: &str,
+    params: &[(&str, RecastValue)],
+) -> Result<String, RrdlError> {
+    let mut rendered = String::with_capacity(template.len());
+    for piece in pieces(template)? {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Param(name) => {
+                let value = params
+                    .iter()
+                    .find(|(param, _)| *param == name)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| RrdlError::new(format!("Param '{}' has no value", name)))?;
+                match value {
+                    RecastValue::Param(text) | RecastValue::String(text) => rendered.push_str(text),
+                    RecastValue::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => {
+                        rendered.push_str(&(*n as i64).to_string())
+                    }
+                    RecastValue::Number(n) => rendered.push_str(&n.to_string()),
+                }
+            }
+        }
+    }
+    Ok(rendered)
+}
+
+/// The file a `ServeFile` action serves: its rendered path, inside the root.
+/// Paths that would leave the root through `..` are refused.
+pub fn served_file(
+    root: &str,
+    path: &str,
+    params: &[(&str, RecastValue)],
+) -> Result<PathBuf, RrdlError> {
+    let rendered = render_template(path, params)?;
+    let mut file = PathBuf::from(root);
+    for segment in rendered.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                return Err(RrdlError::new(format!(
+                    "Refusing to serve '{}', which is outside the root",
+                    rendered
+                )))
+            }
+            segment if segment.contains('\\') => {
+                return Err(RrdlError::new(format!(
+                    "Refusing to serve '{}', which contains a backslash",
+                    rendered
+                )))
+            }
+            segment if segment.contains(':') => {
+                return Err(RrdlError::new(format!(
+                    "Refusing to serve '{}', which contains a colon",
+                    rendered
+                )))
+            }
+            segment => match Path::new(segment).components().collect::<Vec<_>>()[..] {
+                [Component::Normal(name)] => file.push(name),
+                _ => {
+                    return Err(RrdlError::new(format!(
+                        "Refusing to serve '{}', '{}' is not a file name",
+                        rendered, segment
+                    )))
+                }
+            },
+        }
+    }
+    Ok(file)
+}
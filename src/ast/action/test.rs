@@ -0,0 +1,150 @@
+use crate::ast::action::{
+    render_template, served_file, template_params, ForwardAction, LoadBalancingStrategy,
+    RecastValue, ResponseBasedAction, RetryPolicy,
+};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+fn params() -> Vec<(&'static str, RecastValue<'static>)> {
+    vec![
+        ("id", RecastValue::Number(42.0)),
+        ("ratio", RecastValue::Number(0.5)),
+        ("rest", RecastValue::String(Cow::Borrowed("css/site.css"))),
+    ]
+}
+
+#[test]
+pub fn templates_render_params() {
+    assert_eq!(
+        render_template("{{\"id\": {id}, \"ratio\": {ratio}}}", &params()).unwrap(),
+        "{\"id\": 42, \"ratio\": 0.5}"
+    );
+    assert_eq!(
+        template_params("/users/{id}/files/{rest}").unwrap(),
+        vec!["id", "rest"]
+    );
+    assert_eq!(
+        render_template("/users/{name}", &params())
+            .unwrap_err()
+            .message,
+        "Param 'name' has no value"
+    );
+}
+
+#[test]
+pub fn invalid_templates() {
+    let error = |template| template_params(template).unwrap_err().message;
+    assert_eq!(
+        error("{id"),
+        "Invalid template '{id', '{' is never closed, write '{{' for a literal brace"
+    );
+    assert_eq!(
+        error("id}"),
+        "Invalid template 'id}', '}' is never opened, write '}}' for a literal brace"
+    );
+    assert_eq!(
+        error("{}"),
+        "Invalid template '{}', '' is not a valid param name"
+    );
+}
+
+#[test]
+pub fn served_files_stay_inside_the_root() {
+    assert_eq!(
+        served_file("/var/www", "static/{rest}", &params()).unwrap(),
+        PathBuf::from("/var/www/static/css/site.css")
+    );
+    let params = vec![("rest", RecastValue::String(Cow::Borrowed("../etc/passwd")))];
+    assert_eq!(
+        served_file("/var/www", "{rest}", &params)
+            .unwrap_err()
+            .message,
+        "Refusing to serve '../etc/passwd', which is outside the root"
+    );
+    let params = vec![(
+        "rest",
+        RecastValue::String(Cow::Borrowed("C:/Windows/win.ini")),
+    )];
+    assert_eq!(
+        served_file("/var/www", "{rest}", &params)
+            .unwrap_err()
+            .message,
+        "Refusing to serve 'C:/Windows/win.ini', which contains a colon"
+    );
+}
+
+#[test]
+pub fn response_statuses_are_checked() {
+    let redirect = |status| ResponseBasedAction::Redirect {
+        status,
+        location: Cow::Borrowed("/login"),
+    };
+    assert!(redirect(307).check().is_ok());
+    assert_eq!(
+        redirect(304).check().unwrap_err().message,
+        "Invalid redirect status 304, expected one of 301, 302, 303, 307, 308"
+    );
+}
+
+fn forward(
+    upstreams: Vec<&'static str>,
+    rewrite_path: Option<&'static str>,
+) -> ForwardAction<'static> {
+    ForwardAction {
+        upstreams: upstreams.into_iter().map(Cow::Borrowed).collect(),
+        load_balancing: LoadBalancingStrategy::RoundRobin,
+        rewrite_path: rewrite_path.map(Cow::Borrowed),
+        timeout_ms: None,
+        remove_headers: vec![],
+        add_headers: BTreeMap::new(),
+        retry: RetryPolicy::default(),
+    }
+}
+
+#[test]
+pub fn forward_paths_are_rewritten() {
+    let plain = forward(vec!["http://users:8080"], None);
+    assert_eq!(
+        plain.upstream_path("/api/users/42", &params()).unwrap(),
+        "/api/users/42"
+    );
+    let rewritten = forward(vec!["http://users:8080"], Some("/v2/users/{id}"));
+    assert_eq!(
+        rewritten.upstream_path("/api/users/42", &params()).unwrap(),
+        "/v2/users/42"
+    );
+}
+
+#[test]
+pub fn forward_upstreams_are_checked() {
+    let error = |upstreams| forward(upstreams, None).check().unwrap_err().message;
+    assert!(forward(vec!["https://users.internal/api?v=2"], None)
+        .check()
+        .is_ok());
+    assert_eq!(
+        error(vec![]),
+        "A forward action needs at least one upstream"
+    );
+    assert_eq!(
+        error(vec!["http://"]),
+        "Invalid upstream 'http://', expected an http:// or https:// URL with a host"
+    );
+    assert_eq!(
+        error(vec!["ftp://files"]),
+        "Invalid upstream 'ftp://files', expected an http:// or https:// URL with a host"
+    );
+    assert_eq!(
+        forward(vec!["http://users"], Some("/users/{id"))
+            .check()
+            .unwrap_err()
+            .message,
+        "Invalid template '/users/{id', '{' is never closed, write '{{' for a literal brace"
+    );
+    let mut uppercase = forward(vec!["http://users"], None);
+    uppercase.remove_headers.push(Cow::Borrowed("Cookie"));
+    assert_eq!(
+        uppercase.check().unwrap_err().message,
+        "Invalid header name 'Cookie', it must be lowercase"
+    );
+}
@@ -0,0 +1,68 @@
+//! Deserializers borrowing strings from the input where serde would otherwise copy them.
+//!
+//! `#[serde(borrow)]` only borrows a `Cow<str>` field itself, so strings held in an
+//! `Option`, a `Vec` or a map go through [`CowStr`], which borrows when the input allows.
+
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+struct CowStr<'a>(#[serde(borrow)] Cow<'a, str>);
+
+pub(crate) fn option<'de: 'a, 'a, D>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<CowStr>::deserialize(deserializer).map(|s| s.map(|s| s.0))
+}
+
+pub(crate) fn vec<'de: 'a, 'a, D>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<CowStr>::deserialize(deserializer).map(|v| v.into_iter().map(|s| s.0).collect())
+}
+
+pub(crate) fn map<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<BTreeMap<Cow<'a, str>, Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BTreeMap::<CowStr, CowStr>::deserialize(deserializer)
+        .map(|m| m.into_iter().map(|(k, v)| (k.0, v.0)).collect())
+}
+
+/// A string key and its string value.
+pub(crate) type Entry<'a> = (Cow<'a, str>, Cow<'a, str>);
+
+/// The entries of a map in the order written, keeping any repeated keys, which a
+/// `BTreeMap` would silently overwrite.
+pub(crate) fn entries<'de: 'a, 'a, D>(deserializer: D) -> Result<Vec<Entry<'a>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct Entries<'a>(PhantomData<&'a ()>);
+
+    impl<'de: 'a, 'a> Visitor<'de> for Entries<'a> {
+        type Value = Vec<Entry<'a>>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("a map of strings")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((key, value)) = map.next_entry::<CowStr, CowStr>()? {
+                entries.push((key.0, value.0));
+            }
+            Ok(entries)
+        }
+    }
+
+    deserializer.deserialize_map(Entries(PhantomData))
+}
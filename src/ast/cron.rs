@@ -1,6 +0,0 @@
-//! Cron Definition related AST
-use serde::{Deserialize, Serialize};
-
-/// A Cron Definition
-#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
-pub struct RapidCronDefinition {}
@@ -0,0 +1,113 @@
+//! Cron Definition related AST
+#[cfg(test)]
+mod test;
+
+mod schedule;
+
+pub use schedule::CronSchedule;
+
+use crate::ast::action::RapidRecastAction;
+use crate::ast::{borrow, owned};
+use crate::error::RrdlError;
+use chrono::{DateTime, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// A Cron Definition, running actions on a schedule
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct RapidCronDefinition<'a> {
+    /// The namespace the cron belongs to
+    #[serde(borrow)]
+    pub namespace: Cow<'a, str>,
+    /// The name of the cron within its namespace
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The cron expression deciding when the actions run, as written
+    #[serde(borrow)]
+    pub schedule: Cow<'a, str>,
+    /// The IANA timezone the schedule is evaluated in, or UTC if not set
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
+    pub timezone: Option<Cow<'a, str>>,
+    /// The longest random delay added to each run, in milliseconds, to spread load
+    #[serde(default)]
+    pub jitter_ms: u64,
+    /// What happens when a run is due while the previous one is still going
+    #[serde(default)]
+    pub overlap: CronOverlapPolicy,
+    /// Actions that take effect each time the schedule fires
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl RapidCronDefinition<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidCronDefinition<'static> {
+        RapidCronDefinition {
+            namespace: owned(self.namespace),
+            name: owned(self.name),
+            schedule: owned(self.schedule),
+            timezone: self.timezone.map(owned),
+            jitter_ms: self.jitter_ms,
+            overlap: self.overlap,
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Parse the schedule expression.
+    pub fn parsed_schedule(&self) -> Result<CronSchedule, RrdlError> {
+        CronSchedule::parse(&self.schedule)
+    }
+
+    /// The timezone the schedule is evaluated in.
+    pub fn time_zone(&self) -> Result<Tz, RrdlError> {
+        match &self.timezone {
+            Some(name) => parse_time_zone(name),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    /// The next `count` times the schedule fires strictly after `after`, in the cron's timezone.
+    /// Jitter is not included, as it is chosen at random for each run.
+    pub fn next_fire_times<Z: TimeZone>(
+        &self,
+        after: &DateTime<Z>,
+        count: usize,
+    ) -> Result<Vec<DateTime<Tz>>, RrdlError> {
+        let schedule = self.parsed_schedule()?;
+        let mut time = after.with_timezone(&self.time_zone()?);
+        let mut times = Vec::with_capacity(count);
+        while times.len() < count {
+            match schedule.next_after(&time) {
+                Some(next) => {
+                    times.push(next);
+                    time = next;
+                }
+                None => break,
+            }
+        }
+        Ok(times)
+    }
+}
+
+/// Look up an IANA timezone such as `Europe/London`.
+pub(crate) fn parse_time_zone(name: &str) -> Result<Tz, RrdlError> {
+    name.parse()
+        .map_err(|_| RrdlError::new(format!("Unknown timezone '{}'", name)))
+}
+
+/// What to do when a run is due while the previous one is still going
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Default)]
+pub enum CronOverlapPolicy {
+    /// Start the new run alongside the previous one
+    #[default]
+    Allow,
+    /// Drop the new run
+    Skip,
+    /// Start the new run once the previous one finishes
+    Queue,
+}
@@ -0,0 +1,251 @@
+//! Evaluation of cron schedule expressions.
+
+use crate::error::RrdlError;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// A parsed cron schedule, following the semantics of the standard cron daemon.
+///
+/// Expressions have five fields (minute, hour, day of month, month, day of week), or six with
+/// seconds first. Each field is `*`, a value, a range `a-b`, a step `*/n`, `a/n` or `a-b/n`,
+/// or a comma separated list of those. Months and days of the week also accept names such as
+/// `jan` and `mon`, and Sunday is both `0` and `7`. The macros `@yearly`, `@annually`,
+/// `@monthly`, `@weekly`, `@daily`, `@midnight` and `@hourly` stand for their usual expressions.
+///
+/// When both the day of month and the day of week are restricted, a day matching either fires.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CronSchedule {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    either_day: bool,
+}
+
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    /// Names accepted for the values from `min` upwards
+    names: &'static [&'static str],
+}
+
+const SECOND: Field = Field {
+    name: "second",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const MINUTE: Field = Field {
+    name: "minute",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const HOUR: Field = Field {
+    name: "hour",
+    min: 0,
+    max: 23,
+    names: &[],
+};
+const DAY_OF_MONTH: Field = Field {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+};
+const MONTH: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: &[
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ],
+};
+const DAY_OF_WEEK: Field = Field {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: &["sun", "mon", "tue", "wed", "thu", "fri", "sat"],
+};
+
+const MACROS: &[(&str, &str)] = &[
+    ("@yearly", "0 0 1 1 *"),
+    ("@annually", "0 0 1 1 *"),
+    ("@monthly", "0 0 1 * *"),
+    ("@weekly", "0 0 * * 0"),
+    ("@daily", "0 0 * * *"),
+    ("@midnight", "0 0 * * *"),
+    ("@hourly", "0 * * * *"),
+];
+
+/// The longest each month can be, so schedules that can never fire are rejected
+const MONTH_DAYS: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+/// How far ahead to search; the rarest valid schedule, the 29th of February, recurs within 8 years
+const SEARCH_YEARS: i32 = 10;
+
+impl CronSchedule {
+    /// Parse a cron expression, rejecting out of range values and schedules that never fire.
+    pub fn parse(expression: &str) -> Result<Self, RrdlError> {
+        let expression = expression.trim();
+        let expanded = match expression.strip_prefix('@') {
+            Some(_) => MACROS
+                .iter()
+                .find(|(name, _)| *name == expression)
+                .map(|(_, expanded)| *expanded)
+                .ok_or_else(|| {
+                    let names = MACROS.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                    RrdlError::new(format!(
+                        "Unknown cron macro '{}', expected one of {}",
+                        expression,
+                        names.join(", ")
+                    ))
+                })?,
+            None => expression,
+        };
+        let fields = expanded.split_whitespace().collect::<Vec<_>>();
+        let (seconds, fields) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => {
+                return Err(RrdlError::new(format!(
+                    "Expected 5 or 6 fields in cron schedule, found {}",
+                    n
+                )))
+            }
+        };
+        let mut days_of_week = parse_field(&DAY_OF_WEEK, fields[4])?;
+        if days_of_week & 1 << 7 != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        let schedule = CronSchedule {
+            seconds: parse_field(&SECOND, seconds)?,
+            minutes: parse_field(&MINUTE, fields[0])?,
+            hours: parse_field(&HOUR, fields[1])?,
+            days_of_month: parse_field(&DAY_OF_MONTH, fields[2])?,
+            months: parse_field(&MONTH, fields[3])?,
+            days_of_week,
+            either_day: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+        };
+        let day_of_month_fits = (1..=12).any(|month| {
+            has(schedule.months, month)
+                && (1..=MONTH_DAYS[month as usize - 1]).any(|day| has(schedule.days_of_month, day))
+        });
+        if !schedule.either_day && fields[4].starts_with('*') && !day_of_month_fits {
+            return Err(RrdlError::new(format!(
+                "Cron schedule '{}' never fires",
+                expression
+            )));
+        }
+        Ok(schedule)
+    }
+
+    /// The first time strictly after `after` that the schedule fires, in the same timezone.
+    ///
+    /// Times skipped by a daylight saving change do not fire, and times repeated by one fire
+    /// only on their first occurrence, as with the standard cron daemon.
+    pub fn next_after<Z: TimeZone>(&self, after: &DateTime<Z>) -> Option<DateTime<Z>> {
+        let zone = after.timezone();
+        let mut from = after.naive_local().with_nanosecond(0)? + Duration::seconds(1);
+        loop {
+            let local = self.next_local(from)?;
+            if let Some(time) = zone.from_local_datetime(&local).earliest() {
+                if time > *after {
+                    return Some(time);
+                }
+            }
+            from = local + Duration::seconds(1);
+        }
+    }
+
+    /// The first local time at or after `from` matching every field.
+    fn next_local(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = from.year() + SEARCH_YEARS;
+        let mut time = from;
+        while time.year() <= limit {
+            let date = time.date();
+            if !has(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.has_day(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, time.hour()) {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time = date.and_hms_opt(time.hour(), time.minute(), 0)? + Duration::minutes(1);
+            } else if !has(self.seconds, time.second()) {
+                time += Duration::seconds(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn has_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        match self.either_day {
+            true => day_of_month || day_of_week,
+            false => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & 1 << value != 0
+}
+
+/// Parse one field into a set of values, one bit per value.
+fn parse_field(field: &Field, text: &str) -> Result<u64, RrdlError> {
+    let mut set = 0;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        let invalid = || {
+            RrdlError::new(format!(
+                "Invalid {} '{}' in cron schedule, expected {} to {}",
+                field.name, item, field.min, field.max
+            ))
+        };
+        let value = |text: &str| {
+            let lower = text.to_ascii_lowercase();
+            field
+                .names
+                .iter()
+                .position(|name| *name == lower)
+                .map(|index| index as u32 + field.min)
+                .or_else(|| text.parse().ok())
+                .filter(|value| (field.min..=field.max).contains(value))
+                .ok_or_else(invalid)
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (field.min, field.max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            None if step.is_some() => (value(range)?, field.max),
+            None => (value(range)?, value(range)?),
+        };
+        let step = match step {
+            Some(step) => step
+                .parse::<usize>()
+                .ok()
+                .filter(|step| *step > 0)
+                .ok_or_else(invalid)?,
+            None => 1,
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
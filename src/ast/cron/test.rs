@@ -0,0 +1,141 @@
+use crate::ast::cron::{CronOverlapPolicy, CronSchedule, RapidCronDefinition};
+use chrono::{DateTime, TimeZone, Utc};
+use std::borrow::Cow;
+
+fn cron(schedule: &'static str, timezone: Option<&'static str>) -> RapidCronDefinition<'static> {
+    RapidCronDefinition {
+        namespace: Cow::Borrowed("ns"),
+        name: Cow::Borrowed("job"),
+        schedule: Cow::Borrowed(schedule),
+        timezone: timezone.map(Cow::Borrowed),
+        jitter_ms: 0,
+        overlap: CronOverlapPolicy::Allow,
+        actions: vec![],
+    }
+}
+
+fn utc(time: &str) -> DateTime<Utc> {
+    time.parse().unwrap()
+}
+
+fn fire_times(schedule: &'static str, after: &str, count: usize) -> Vec<String> {
+    cron(schedule, None)
+        .next_fire_times(&utc(after), count)
+        .unwrap()
+        .iter()
+        .map(|time| time.to_rfc3339())
+        .collect()
+}
+
+#[test]
+pub fn weekday_schedule() {
+    assert_eq!(
+        fire_times("30 2 * * mon-fri", "2024-03-01T12:00:00Z", 3),
+        vec![
+            "2024-03-04T02:30:00+00:00",
+            "2024-03-05T02:30:00+00:00",
+            "2024-03-06T02:30:00+00:00",
+        ]
+    );
+}
+
+#[test]
+pub fn macros_and_seconds() {
+    assert_eq!(
+        fire_times("@hourly", "2024-03-01T10:00:00Z", 2),
+        vec!["2024-03-01T11:00:00+00:00", "2024-03-01T12:00:00+00:00"]
+    );
+    assert_eq!(
+        fire_times("*/20 * * * * *", "2024-03-01T10:00:07.5Z", 3),
+        vec![
+            "2024-03-01T10:00:20+00:00",
+            "2024-03-01T10:00:40+00:00",
+            "2024-03-01T10:01:00+00:00",
+        ]
+    );
+    assert_eq!(
+        CronSchedule::parse("0 0 * * 7").unwrap(),
+        CronSchedule::parse("@weekly").unwrap()
+    );
+}
+
+#[test]
+pub fn day_of_month_or_day_of_week() {
+    assert_eq!(
+        fire_times("0 0 1 * mon", "2024-07-20T00:00:00Z", 3),
+        vec![
+            "2024-07-22T00:00:00+00:00",
+            "2024-07-29T00:00:00+00:00",
+            "2024-08-01T00:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fire_times("0 0 29 2 *", "2025-01-01T00:00:00Z", 2),
+        vec!["2028-02-29T00:00:00+00:00", "2032-02-29T00:00:00+00:00"]
+    );
+}
+
+#[test]
+pub fn daylight_saving_changes() {
+    let times = |after| {
+        cron("30 1 * * *", Some("Europe/London"))
+            .next_fire_times(&utc(after), 2)
+            .unwrap()
+            .iter()
+            .map(|time| time.with_timezone(&Utc))
+            .collect::<Vec<_>>()
+    };
+    // 01:30 does not exist when the clocks go forward
+    assert_eq!(
+        times("2024-03-30T12:00:00Z"),
+        vec![
+            Utc.with_ymd_and_hms(2024, 4, 1, 0, 30, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 4, 2, 0, 30, 0).unwrap(),
+        ]
+    );
+    // 01:30 happens twice when they go back, but only fires once
+    assert_eq!(
+        times("2024-10-26T12:00:00Z"),
+        vec![
+            Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 10, 28, 1, 30, 0).unwrap(),
+        ]
+    );
+}
+
+#[test]
+pub fn invalid_schedules() {
+    let error = |schedule| CronSchedule::parse(schedule).unwrap_err().message;
+    assert_eq!(
+        error("61 * * * *"),
+        "Invalid minute '61' in cron schedule, expected 0 to 59"
+    );
+    assert_eq!(
+        error("0 0 * * */0"),
+        "Invalid day of week '*/0' in cron schedule, expected 0 to 7"
+    );
+    assert_eq!(
+        error("0 5-1 * * *"),
+        "Invalid hour '5-1' in cron schedule, expected 0 to 23"
+    );
+    assert_eq!(
+        error("* * *"),
+        "Expected 5 or 6 fields in cron schedule, found 3"
+    );
+    assert_eq!(
+        error("@reboot"),
+        "Unknown cron macro '@reboot', expected one of @yearly, @annually, @monthly, @weekly, @daily, @midnight, @hourly"
+    );
+    assert_eq!(
+        error("0 0 30 feb *"),
+        "Cron schedule '0 0 30 feb *' never fires"
+    );
+}
+
+#[test]
+pub fn unknown_timezone() {
+    let error = cron("@daily", Some("Mars/Olympus_Mons"))
+        .next_fire_times(&utc("2024-01-01T00:00:00Z"), 1)
+        .unwrap_err();
+    assert_eq!(error.message, "Unknown timezone 'Mars/Olympus_Mons'");
+}
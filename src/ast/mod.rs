@@ -1,10 +1,15 @@
 //! This module contains the AST definitions for the RapidRecast language.
 
+#[cfg(test)]
+mod test;
+
 pub mod action;
+mod borrow;
 pub mod cron;
 pub mod model;
 pub mod protocol;
 pub mod topic;
+mod validate;
 
 use crate::ast::cron::RapidCronDefinition;
 use crate::ast::model::RapidModelDefinition;
@@ -13,6 +18,7 @@ use crate::ast::topic::RapidTopicDefinition;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// RapidModelDefinition represents a single "Cell" of configuration.
 /// You can think of it as a single file, or a single request.
@@ -21,22 +27,50 @@ use std::fmt::{Display, Formatter};
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct RapidRecastDefinition<'a> {
     /// The unique identifier of the RapidRecastDefinition.
+    #[serde(borrow)]
     pub id: Cow<'a, str>,
     /// The version of the language used to define the file.
     pub language_version: Version,
     /// The version of the RapidRecastDefinition.
     pub file_version: Version,
     /// The name of the RapidRecastDefinition.
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
     pub name: Option<Cow<'a, str>>,
     /// The description of the RapidRecastDefinition.
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
     pub description: Option<Cow<'a, str>>,
     /// The AST of the RapidRecastDefinition.
     /// TODO this should be something like a Cow but working for vec
     /// So not Cow::borrow(str) -> ::clone() -> Cow::Owned(String)
     /// But actually Cow::borrow([]Vals) -> ::push() -> Cow::Owned(Vec<Vals>)
+    #[serde(borrow)]
     pub ast: Vec<RapidAstStatement<'a>>,
 }
 
+impl RapidRecastDefinition<'_> {
+    /// Copy any strings borrowed from the input, so the definition can outlive it.
+    /// Useful for caching definitions in long-lived state.
+    pub fn into_owned(self) -> RapidRecastDefinition<'static> {
+        RapidRecastDefinition {
+            id: owned(self.id),
+            language_version: self.language_version,
+            file_version: self.file_version,
+            name: self.name.map(owned),
+            description: self.description.map(owned),
+            ast: self
+                .ast
+                .into_iter()
+                .map(RapidAstStatement::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Detach a string from the buffer it borrows from.
+pub(crate) fn owned(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
 /// A semantic version, referenced across the RapidRecastDefinition.
 #[derive(PartialEq, Debug, Clone)]
 pub struct Version {
@@ -48,29 +82,27 @@ pub struct Version {
     pub patch: u64,
 }
 
-impl<'de> Deserialize<'de> for Version {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split('.');
 
         let major = parts
             .next()
-            .ok_or_else(|| serde::de::Error::custom("Missing major version"))?
+            .ok_or_else(|| "Missing major version".to_string())?
             .parse::<u64>()
-            .map_err(|_| serde::de::Error::custom("Invalid major version"))?;
+            .map_err(|_| "Invalid major version".to_string())?;
         let minor = parts
             .next()
-            .ok_or_else(|| serde::de::Error::custom("Missing minor version"))?
+            .ok_or_else(|| "Missing minor version".to_string())?
             .parse::<u64>()
-            .map_err(|_| serde::de::Error::custom("Invalid minor version"))?;
+            .map_err(|_| "Invalid minor version".to_string())?;
         let patch = parts
             .next()
-            .ok_or_else(|| serde::de::Error::custom("Missing patch version"))?
+            .ok_or_else(|| "Missing patch version".to_string())?
             .parse::<u64>()
-            .map_err(|_| serde::de::Error::custom("Invalid patch version"))?;
+            .map_err(|_| "Invalid patch version".to_string())?;
 
         Ok(Version {
             major,
@@ -80,6 +112,16 @@ impl<'de> Deserialize<'de> for Version {
     }
 }
 
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl Serialize for Version {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -99,11 +141,35 @@ impl Display for Version {
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum RapidAstStatement<'a> {
     /// A Model Definition
-    ModelDefinition(RapidModelDefinition),
+    #[serde(borrow)]
+    ModelDefinition(RapidModelDefinition<'a>),
     /// A Protocol Definition
+    #[serde(borrow)]
     ProtocolDefinition(RapidProtocolDefinition<'a>),
     /// A Topic Definition
-    TopicDefinition(RapidTopicDefinition),
+    #[serde(borrow)]
+    TopicDefinition(RapidTopicDefinition<'a>),
     /// A Cron Definition
-    CronDefinition(RapidCronDefinition),
+    #[serde(borrow)]
+    CronDefinition(RapidCronDefinition<'a>),
+}
+
+impl RapidAstStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`].
+    pub fn into_owned(self) -> RapidAstStatement<'static> {
+        match self {
+            RapidAstStatement::ModelDefinition(model) => {
+                RapidAstStatement::ModelDefinition(model.into_owned())
+            }
+            RapidAstStatement::ProtocolDefinition(protocol) => {
+                RapidAstStatement::ProtocolDefinition(protocol.into_owned())
+            }
+            RapidAstStatement::TopicDefinition(topic) => {
+                RapidAstStatement::TopicDefinition(topic.into_owned())
+            }
+            RapidAstStatement::CronDefinition(cron) => {
+                RapidAstStatement::CronDefinition(cron.into_owned())
+            }
+        }
+    }
 }
@@ -1,6 +1,292 @@
 //! Model Definition related AST
+use crate::ast::owned;
+use crate::ast::protocol::compile_regex;
+use crate::error::RrdlError;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-/// A Model Definition
+/// A Model Definition, describing the shape of data such as topic messages
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
-pub struct RapidModelDefinition {}
+pub struct RapidModelDefinition<'a> {
+    /// The namespace the model belongs to
+    #[serde(borrow)]
+    pub namespace: Cow<'a, str>,
+    /// The name of the model within its namespace
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The fields of the model, in declaration order
+    #[serde(borrow)]
+    pub fields: Vec<ModelField<'a>>,
+}
+
+impl RapidModelDefinition<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidModelDefinition<'static> {
+        RapidModelDefinition {
+            namespace: owned(self.namespace),
+            name: owned(self.name),
+            fields: self
+                .fields
+                .into_iter()
+                .map(ModelField::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// A single field of a model
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ModelField<'a> {
+    /// The name of the field
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The type of the values held by the field
+    #[serde(borrow)]
+    pub field_type: ModelFieldType<'a>,
+    /// Whether the field must be present, which it is unless stated otherwise
+    #[serde(default = "required_by_default")]
+    pub required: bool,
+    /// The value used when the field is absent
+    #[serde(borrow)]
+    pub default: Option<ModelValue<'a>>,
+    /// Constraints that values of the field must satisfy
+    #[serde(borrow, default)]
+    pub constraints: Vec<ModelConstraint<'a>>,
+}
+
+impl ModelField<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ModelField<'static> {
+        ModelField {
+            name: owned(self.name),
+            field_type: self.field_type.into_owned(),
+            required: self.required,
+            default: self.default.map(ModelValue::into_owned),
+            constraints: self
+                .constraints
+                .into_iter()
+                .map(ModelConstraint::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Check that regexes compile and are only set on string fields, that no min is above a max,
+    /// and that the default and allowed values have the field's type.
+    pub fn check(&self) -> Result<(), RrdlError> {
+        for constraint in &self.constraints {
+            match constraint {
+                ModelConstraint::Regex(regex) => {
+                    if self.field_type != ModelFieldType::Scalar(ScalarType::String) {
+                        return Err(RrdlError::new(format!(
+                            "Invalid field '{}', a regex needs a string field but it is {}",
+                            self.name,
+                            self.field_type.name()
+                        )));
+                    }
+                    compile_regex(regex)?;
+                }
+                ModelConstraint::Min(min) => {
+                    let above = self
+                        .constraints
+                        .iter()
+                        .find_map(|constraint| match constraint {
+                            ModelConstraint::Max(max) if max < min => Some(max),
+                            _ => None,
+                        });
+                    if let Some(max) = above {
+                        return Err(RrdlError::new(format!(
+                            "Invalid field '{}', min {} is greater than max {}",
+                            self.name, min, max
+                        )));
+                    }
+                }
+                ModelConstraint::OneOf(values) => {
+                    if !values.iter().all(|value| self.field_type.holds(value)) {
+                        return Err(RrdlError::new(format!(
+                            "Invalid one_of value for field '{}', expected {}",
+                            self.name,
+                            self.field_type.name()
+                        )));
+                    }
+                }
+                ModelConstraint::Max(_) => {}
+            }
+        }
+        match &self.default {
+            Some(default) if !self.field_type.holds(default) => Err(RrdlError::new(format!(
+                "Invalid default for field '{}', expected {}",
+                self.name,
+                self.field_type.name()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+/// The type of a model field
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ModelFieldType<'a> {
+    /// A single scalar value
+    Scalar(ScalarType),
+    /// An ordered list of values of the given type
+    #[serde(borrow)]
+    List(Box<ModelFieldType<'a>>),
+    /// A map from string keys to values of the given type
+    #[serde(borrow)]
+    Map(Box<ModelFieldType<'a>>),
+    /// A value described by another model
+    #[serde(borrow)]
+    Model(ModelReference<'a>),
+}
+
+impl ModelFieldType<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ModelFieldType<'static> {
+        match self {
+            ModelFieldType::Scalar(scalar) => ModelFieldType::Scalar(scalar),
+            ModelFieldType::List(item) => ModelFieldType::List(Box::new(item.into_owned())),
+            ModelFieldType::Map(value) => ModelFieldType::Map(Box::new(value.into_owned())),
+            ModelFieldType::Model(model) => ModelFieldType::Model(model.into_owned()),
+        }
+    }
+
+    /// Whether a literal is a value of this type. Models and maps have no literals.
+    fn holds(&self, value: &ModelValue) -> bool {
+        match (self, value) {
+            (ModelFieldType::Scalar(scalar), value) => match (scalar, value) {
+                (
+                    ScalarType::String | ScalarType::Timestamp | ScalarType::Bytes,
+                    ModelValue::String(_),
+                ) => true,
+                (ScalarType::Integer, ModelValue::Number(n)) => n.fract() == 0.0,
+                (ScalarType::Float, ModelValue::Number(_)) => true,
+                (ScalarType::Bool, ModelValue::Bool(_)) => true,
+                _ => false,
+            },
+            (ModelFieldType::List(item), ModelValue::List(values)) => {
+                values.iter().all(|value| item.holds(value))
+            }
+            _ => false,
+        }
+    }
+
+    /// The type as it is written in RRDL, such as `list<integer>`.
+    fn name(&self) -> String {
+        match self {
+            ModelFieldType::Scalar(scalar) => match scalar {
+                ScalarType::String => "string",
+                ScalarType::Integer => "integer",
+                ScalarType::Float => "float",
+                ScalarType::Bool => "bool",
+                ScalarType::Timestamp => "timestamp",
+                ScalarType::Bytes => "bytes",
+            }
+            .to_string(),
+            ModelFieldType::List(item) => format!("list<{}>", item.name()),
+            ModelFieldType::Map(value) => format!("map<{}>", value.name()),
+            ModelFieldType::Model(model) => {
+                format!("model '{}' '{}'", model.namespace, model.name)
+            }
+        }
+    }
+}
+
+/// The scalar types a field can hold
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ScalarType {
+    /// UTF-8 text
+    String,
+    /// A signed 64-bit integer
+    Integer,
+    /// A 64-bit floating point number
+    Float,
+    /// True or false
+    Bool,
+    /// A point in time, written as an RFC 3339 string
+    Timestamp,
+    /// Raw bytes, written as a base64 string
+    Bytes,
+}
+
+/// A reference to a model by namespace and name
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ModelReference<'a> {
+    /// The namespace of the referenced model
+    #[serde(borrow)]
+    pub namespace: Cow<'a, str>,
+    /// The name of the referenced model
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+}
+
+impl ModelReference<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ModelReference<'static> {
+        ModelReference {
+            namespace: owned(self.namespace),
+            name: owned(self.name),
+        }
+    }
+}
+
+/// A literal value in a model, used for defaults and allowed values
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ModelValue<'a> {
+    /// A string literal, also used for timestamps and bytes
+    #[serde(borrow)]
+    String(Cow<'a, str>),
+    /// A number literal
+    Number(f64),
+    /// A boolean literal
+    Bool(bool),
+    /// A list of values
+    #[serde(borrow)]
+    List(Vec<ModelValue<'a>>),
+}
+
+impl ModelValue<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ModelValue<'static> {
+        match self {
+            ModelValue::String(s) => ModelValue::String(owned(s)),
+            ModelValue::Number(n) => ModelValue::Number(n),
+            ModelValue::Bool(b) => ModelValue::Bool(b),
+            ModelValue::List(values) => {
+                ModelValue::List(values.into_iter().map(ModelValue::into_owned).collect())
+            }
+        }
+    }
+}
+
+/// A constraint on the values of a field
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum ModelConstraint<'a> {
+    /// The smallest allowed number, or the shortest allowed string, list or map
+    Min(f64),
+    /// The largest allowed number, or the longest allowed string, list or map
+    Max(f64),
+    /// A regular expression that string values must match
+    #[serde(borrow)]
+    Regex(Cow<'a, str>),
+    /// The only values the field may hold
+    #[serde(borrow)]
+    OneOf(Vec<ModelValue<'a>>),
+}
+
+impl ModelConstraint<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> ModelConstraint<'static> {
+        match self {
+            ModelConstraint::Min(min) => ModelConstraint::Min(min),
+            ModelConstraint::Max(max) => ModelConstraint::Max(max),
+            ModelConstraint::Regex(regex) => ModelConstraint::Regex(owned(regex)),
+            ModelConstraint::OneOf(values) => {
+                ModelConstraint::OneOf(values.into_iter().map(ModelValue::into_owned).collect())
+            }
+        }
+    }
+}
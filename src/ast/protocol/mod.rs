@@ -0,0 +1,859 @@
+//! Protocol Definition related AST
+#[cfg(test)]
+mod test;
+
+mod path;
+
+pub(crate) use path::is_param_name;
+pub use path::{PathParamType, PathSegment, PathTemplate};
+
+use crate::ast::action::{ConditionStatement, RapidRecastAction, TopicObject};
+use crate::ast::{borrow, owned};
+use crate::error::RrdlError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+/// A Protocol Definition
+/// The type of protocol used for the protocol definition
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum RapidProtocolDefinition<'a> {
+    /// Indicates that we are modifying an HTTP protocol
+    #[serde(borrow)]
+    HttpProtocolDefinition(HttpStatement<'a>),
+    /// Indicates that we are modifying a WebSocket protocol
+    #[serde(borrow)]
+    WebSocketProtocolDefinition(WebSocketStatement<'a>),
+    /// Indicates that we are modifying a Kafka protocol
+    #[serde(borrow)]
+    KafkaProtocolDefinition(KafkaStatement<'a>),
+    /// Indicates that we are modifying a RabbitMQ protocol
+    #[serde(borrow)]
+    RabbitMQProtocolDefinition(AmqpStatement<'a>),
+    /// Indicates that we are modifying a gRPC protocol
+    #[serde(borrow)]
+    GrpcProtocolDefinition(GrpcStatement<'a>),
+    /// Indicates that we are modifying an MQTT protocol
+    #[serde(borrow)]
+    MqttProtocolDefinition(MqttStatement<'a>),
+}
+
+impl RapidProtocolDefinition<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidProtocolDefinition<'static> {
+        match self {
+            RapidProtocolDefinition::HttpProtocolDefinition(http) => {
+                RapidProtocolDefinition::HttpProtocolDefinition(http.into_owned())
+            }
+            RapidProtocolDefinition::WebSocketProtocolDefinition(websocket) => {
+                RapidProtocolDefinition::WebSocketProtocolDefinition(websocket.into_owned())
+            }
+            RapidProtocolDefinition::KafkaProtocolDefinition(kafka) => {
+                RapidProtocolDefinition::KafkaProtocolDefinition(kafka.into_owned())
+            }
+            RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp) => {
+                RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp.into_owned())
+            }
+            RapidProtocolDefinition::GrpcProtocolDefinition(grpc) => {
+                RapidProtocolDefinition::GrpcProtocolDefinition(grpc.into_owned())
+            }
+            RapidProtocolDefinition::MqttProtocolDefinition(mqtt) => {
+                RapidProtocolDefinition::MqttProtocolDefinition(mqtt.into_owned())
+            }
+        }
+    }
+}
+
+/// Allows for specifying parts of an HTTP statement
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct HttpStatement<'a> {
+    /// Protocol sequence, where the order is determined from CLI order
+    /// ex. `rapidrecast --http host1:123,host1:234,host2:345`
+    /// That declares 3 protocols, so `sequence=2` means binding `host2:345`
+    pub sequence: u8,
+    /// The path templates to match on the HTTP protocol, as written
+    #[serde(borrow, deserialize_with = "borrow::vec")]
+    pub paths: Vec<Cow<'a, str>>,
+    /// The methods of access for this rule. GET, POST, DELETE, UPDATE
+    pub methods: Vec<RapidRecastHttpMethod>,
+    /// The smallest request body to match, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_body_bytes: Option<u64>,
+    /// The largest request body to match, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_bytes: Option<u64>,
+    /// The host, or authority, to match, without the port
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<HttpValueMatch<'a>>,
+    /// The content type to match, parameters included
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<HttpValueMatch<'a>>,
+    /// Request headers to match; names are not case sensitive
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<HttpFieldMatch<'a>>,
+    /// Query parameters to match
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<HttpFieldMatch<'a>>,
+    /// Actions that take effect once the protocol is triggered
+    #[serde(borrow)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl HttpStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> HttpStatement<'static> {
+        HttpStatement {
+            sequence: self.sequence,
+            paths: self.paths.into_iter().map(owned).collect(),
+            methods: self.methods,
+            min_body_bytes: self.min_body_bytes,
+            max_body_bytes: self.max_body_bytes,
+            host: self.host.map(HttpValueMatch::into_owned),
+            content_type: self.content_type.map(HttpValueMatch::into_owned),
+            headers: self
+                .headers
+                .into_iter()
+                .map(HttpFieldMatch::into_owned)
+                .collect(),
+            query: self
+                .query
+                .into_iter()
+                .map(HttpFieldMatch::into_owned)
+                .collect(),
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Parse the path templates.
+    pub fn parsed_paths(&self) -> Result<Vec<PathTemplate>, RrdlError> {
+        self.paths
+            .iter()
+            .map(|path| PathTemplate::parse(path))
+            .collect()
+    }
+
+    /// Check the body size min is not larger than the max.
+    pub fn check_body_size(&self) -> Result<(), RrdlError> {
+        check_body_size(self.min_body_bytes, self.max_body_bytes)
+    }
+}
+
+pub(crate) fn check_body_size(min: Option<u64>, max: Option<u64>) -> Result<(), RrdlError> {
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => Err(RrdlError::new(
+            "The body size min must not be larger than the max",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A named request value to match, such as a header or query parameter
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct HttpFieldMatch<'a> {
+    /// The header or query parameter name
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// How its value is matched
+    #[serde(borrow)]
+    pub value: HttpValueMatch<'a>,
+}
+
+impl HttpFieldMatch<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> HttpFieldMatch<'static> {
+        HttpFieldMatch {
+            name: owned(self.name),
+            value: self.value.into_owned(),
+        }
+    }
+}
+
+/// How a request value is matched
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum HttpValueMatch<'a> {
+    /// The value equals the string
+    #[serde(borrow)]
+    Exact(Cow<'a, str>),
+    /// The value starts with the string
+    #[serde(borrow)]
+    Prefix(Cow<'a, str>),
+    /// The value matches the regular expression, anywhere in it unless anchored
+    #[serde(borrow)]
+    Regex(HttpRegex<'a>),
+    /// The value is present, whatever it is
+    Present,
+}
+
+impl HttpValueMatch<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> HttpValueMatch<'static> {
+        match self {
+            HttpValueMatch::Exact(value) => HttpValueMatch::Exact(owned(value)),
+            HttpValueMatch::Prefix(prefix) => HttpValueMatch::Prefix(owned(prefix)),
+            HttpValueMatch::Regex(regex) => HttpValueMatch::Regex(regex.into_owned()),
+            HttpValueMatch::Present => HttpValueMatch::Present,
+        }
+    }
+
+    /// Check that a regular expression compiles, keeping it for matching.
+    pub fn check(&self) -> Result<(), RrdlError> {
+        match self {
+            HttpValueMatch::Regex(regex) => regex.compiled().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a request value, or its absence, matches.
+    pub fn matches(&self, value: Option<&str>) -> Result<bool, RrdlError> {
+        let Some(value) = value else {
+            return Ok(false);
+        };
+        Ok(match self {
+            HttpValueMatch::Exact(expected) => value == expected,
+            HttpValueMatch::Prefix(prefix) => value.starts_with(prefix.as_ref()),
+            HttpValueMatch::Regex(regex) => regex.compiled()?.is_match(value),
+            HttpValueMatch::Present => true,
+        })
+    }
+}
+
+/// A regular expression as written, compiled once when it is first checked or matched.
+/// Every reader checks it while loading, so matching requests never compiles it.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct HttpRegex<'a> {
+    #[serde(borrow)]
+    pattern: Cow<'a, str>,
+    #[serde(skip)]
+    compiled: OnceLock<Regex>,
+}
+
+impl<'a> HttpRegex<'a> {
+    /// A regular expression that is not compiled yet.
+    pub fn new(pattern: Cow<'a, str>) -> Self {
+        HttpRegex {
+            pattern,
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> HttpRegex<'static> {
+        HttpRegex {
+            pattern: owned(self.pattern),
+            compiled: self.compiled,
+        }
+    }
+
+    /// The compiled expression, compiling it the first time.
+    fn compiled(&self) -> Result<&Regex, RrdlError> {
+        if let Some(regex) = self.compiled.get() {
+            return Ok(regex);
+        }
+        let regex = compile_regex(&self.pattern)?;
+        Ok(self.compiled.get_or_init(|| regex))
+    }
+}
+
+impl Deref for HttpRegex<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.pattern
+    }
+}
+
+impl PartialEq for HttpRegex<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Debug for HttpRegex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.pattern, f)
+    }
+}
+
+impl Display for HttpRegex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+pub(crate) fn compile_regex(regex: &str) -> Result<Regex, RrdlError> {
+    Regex::new(regex).map_err(|e| {
+        // Syntax errors draw the expression over several lines, ending with the reason
+        let message = e.to_string();
+        let reason = message.lines().last().unwrap_or_default();
+        RrdlError::new(format!(
+            "Invalid regex '{}', {}",
+            regex,
+            reason.trim_start_matches("error: ")
+        ))
+    })
+}
+
+/// Allows for specifying parts of a WebSocket statement
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct WebSocketStatement<'a> {
+    /// Protocol sequence, selecting the binding as for HTTP statements
+    pub sequence: u8,
+    /// The path the WebSocket is upgraded from
+    #[serde(borrow)]
+    pub path: Cow<'a, str>,
+    /// The subprotocols offered during the handshake, in order of preference
+    #[serde(borrow, default, deserialize_with = "borrow::vec")]
+    pub subprotocols: Vec<Cow<'a, str>>,
+    /// Actions that take effect once a client connects
+    #[serde(borrow, default)]
+    pub on_connect: Vec<RapidRecastAction<'a>>,
+    /// Handlers for incoming messages; every matching handler runs, in order
+    #[serde(borrow, default)]
+    pub on_message: Vec<WebSocketMessageHandler<'a>>,
+    /// Actions that take effect once the connection closes
+    #[serde(borrow, default)]
+    pub on_close: Vec<RapidRecastAction<'a>>,
+}
+
+impl WebSocketStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> WebSocketStatement<'static> {
+        WebSocketStatement {
+            sequence: self.sequence,
+            path: owned(self.path),
+            subprotocols: self.subprotocols.into_iter().map(owned).collect(),
+            on_connect: self
+                .on_connect
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+            on_message: self
+                .on_message
+                .into_iter()
+                .map(WebSocketMessageHandler::into_owned)
+                .collect(),
+            on_close: self
+                .on_close
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Actions to take for WebSocket messages of one kind
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct WebSocketMessageHandler<'a> {
+    /// The kind of frame the handler applies to
+    pub kind: WebSocketMessageKind,
+    /// A condition the message must satisfy, or none to handle every message of the kind
+    #[serde(borrow)]
+    pub condition: Option<ConditionStatement<'a>>,
+    /// Actions that take effect for each handled message
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl WebSocketMessageHandler<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> WebSocketMessageHandler<'static> {
+        WebSocketMessageHandler {
+            kind: self.kind,
+            condition: self.condition.map(ConditionStatement::into_owned),
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// The kinds of WebSocket data frames
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum WebSocketMessageKind {
+    /// UTF-8 text frames
+    Text,
+    /// Binary frames
+    Binary,
+}
+
+/// Allows for specifying parts of a Kafka statement
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct KafkaStatement<'a> {
+    /// Protocol sequence, selecting the binding as for HTTP statements
+    pub sequence: u8,
+    /// The requests to match; empty matches both produce and fetch
+    #[serde(default)]
+    pub operations: Vec<KafkaOperation>,
+    /// The topics to match, where names may use `*` as a wildcard; empty matches every topic
+    #[serde(borrow, default)]
+    pub topics: Vec<TopicObject<'a>>,
+    /// The consumer groups to match, where `*` is a wildcard; empty matches every group.
+    /// Produce requests have no group, so they only match when this is empty.
+    #[serde(borrow, default, deserialize_with = "borrow::vec")]
+    pub consumer_groups: Vec<Cow<'a, str>>,
+    /// Actions that take effect once a request matches
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl KafkaStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> KafkaStatement<'static> {
+        KafkaStatement {
+            sequence: self.sequence,
+            operations: self.operations,
+            topics: self
+                .topics
+                .into_iter()
+                .map(TopicObject::into_owned)
+                .collect(),
+            consumer_groups: self.consumer_groups.into_iter().map(owned).collect(),
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// The Kafka requests a statement can match
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum KafkaOperation {
+    /// Clients writing records to a topic
+    Produce,
+    /// Clients reading records from a topic
+    Fetch,
+}
+
+/// Allows for specifying parts of an AMQP statement, as served to RabbitMQ clients
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct AmqpStatement<'a> {
+    /// Protocol sequence, selecting the binding as for HTTP statements
+    pub sequence: u8,
+    /// Exchanges to declare
+    #[serde(borrow, default)]
+    pub exchanges: Vec<AmqpExchange<'a>>,
+    /// Queues to declare
+    #[serde(borrow, default)]
+    pub queues: Vec<AmqpQueue<'a>>,
+    /// Bindings routing messages from exchanges to queues
+    #[serde(borrow, default)]
+    pub bindings: Vec<AmqpBinding<'a>>,
+    /// Handlers for published messages; every matching handler runs, in order
+    #[serde(borrow, default)]
+    pub on_publish: Vec<AmqpPublishHandler<'a>>,
+}
+
+impl AmqpStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AmqpStatement<'static> {
+        AmqpStatement {
+            sequence: self.sequence,
+            exchanges: self
+                .exchanges
+                .into_iter()
+                .map(AmqpExchange::into_owned)
+                .collect(),
+            queues: self.queues.into_iter().map(AmqpQueue::into_owned).collect(),
+            bindings: self
+                .bindings
+                .into_iter()
+                .map(AmqpBinding::into_owned)
+                .collect(),
+            on_publish: self
+                .on_publish
+                .into_iter()
+                .map(AmqpPublishHandler::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// An AMQP exchange
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct AmqpExchange<'a> {
+    /// The name of the exchange
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// How the exchange routes messages to bound queues
+    pub exchange_type: AmqpExchangeType,
+    /// Whether the exchange survives a broker restart
+    pub durable: bool,
+}
+
+impl AmqpExchange<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AmqpExchange<'static> {
+        AmqpExchange {
+            name: owned(self.name),
+            exchange_type: self.exchange_type,
+            durable: self.durable,
+        }
+    }
+}
+
+/// The routing behaviours of AMQP exchanges
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum AmqpExchangeType {
+    /// Routes to queues bound with exactly the message's routing key
+    Direct,
+    /// Routes to every bound queue, ignoring routing keys
+    Fanout,
+    /// Routes to queues whose binding pattern matches the routing key
+    Topic,
+    /// Routes on message headers instead of the routing key
+    Headers,
+}
+
+/// An AMQP queue
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct AmqpQueue<'a> {
+    /// The name of the queue
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// Whether the queue survives a broker restart
+    pub durable: bool,
+}
+
+impl AmqpQueue<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AmqpQueue<'static> {
+        AmqpQueue {
+            name: owned(self.name),
+            durable: self.durable,
+        }
+    }
+}
+
+/// A binding delivering messages from an exchange to a queue
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct AmqpBinding<'a> {
+    /// The queue receiving messages
+    #[serde(borrow)]
+    pub queue: Cow<'a, str>,
+    /// The exchange messages are published to
+    #[serde(borrow)]
+    pub exchange: Cow<'a, str>,
+    /// The routing key, or pattern for topic exchanges; empty for fanout exchanges
+    #[serde(borrow)]
+    pub routing_key: Cow<'a, str>,
+}
+
+impl AmqpBinding<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AmqpBinding<'static> {
+        AmqpBinding {
+            queue: owned(self.queue),
+            exchange: owned(self.exchange),
+            routing_key: owned(self.routing_key),
+        }
+    }
+}
+
+/// Actions to take when a message is published to an exchange
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct AmqpPublishHandler<'a> {
+    /// The exchange the message is published to
+    #[serde(borrow)]
+    pub exchange: Cow<'a, str>,
+    /// A routing key pattern, where `*` matches one dot separated word and `#` any number of
+    /// them, or none to handle every message published to the exchange
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
+    pub routing_key: Option<Cow<'a, str>>,
+    /// Actions that take effect for each handled message
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl AmqpPublishHandler<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> AmqpPublishHandler<'static> {
+        AmqpPublishHandler {
+            exchange: owned(self.exchange),
+            routing_key: self.routing_key.map(owned),
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Allows for specifying parts of a gRPC statement
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcStatement<'a> {
+    /// Protocol sequence, selecting the binding as for HTTP statements
+    pub sequence: u8,
+    /// The fully-qualified service name, such as `acme.users.v1.UserService`,
+    /// where `*` matches any run of characters
+    #[serde(borrow)]
+    pub service: Cow<'a, str>,
+    /// The method names to match, where `*` matches any run of characters;
+    /// empty matches every method
+    #[serde(borrow, default, deserialize_with = "borrow::vec")]
+    pub methods: Vec<Cow<'a, str>>,
+    /// The kind of call to match, or any kind if not set
+    pub streaming: Option<GrpcStreamingKind>,
+    /// Metadata the call must carry; every entry must match
+    #[serde(borrow, default)]
+    pub metadata: Vec<GrpcMetadataMatch<'a>>,
+    /// Actions that take effect once a call matches
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl GrpcStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> GrpcStatement<'static> {
+        GrpcStatement {
+            sequence: self.sequence,
+            service: owned(self.service),
+            methods: self.methods.into_iter().map(owned).collect(),
+            streaming: self.streaming,
+            metadata: self
+                .metadata
+                .into_iter()
+                .map(GrpcMetadataMatch::into_owned)
+                .collect(),
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// The kinds of gRPC call
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum GrpcStreamingKind {
+    /// One request and one response
+    Unary,
+    /// A stream of requests and one response
+    ClientStreaming,
+    /// One request and a stream of responses
+    ServerStreaming,
+    /// Streams of requests and responses
+    Bidirectional,
+}
+
+/// A metadata entry a gRPC call must carry
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcMetadataMatch<'a> {
+    /// The metadata key, compared case-insensitively
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The exact value required, or none to only require the key to be present
+    #[serde(borrow, default, deserialize_with = "borrow::option")]
+    pub value: Option<Cow<'a, str>>,
+}
+
+impl GrpcMetadataMatch<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> GrpcMetadataMatch<'static> {
+        GrpcMetadataMatch {
+            name: owned(self.name),
+            value: self.value.map(owned),
+        }
+    }
+}
+
+/// Allows for specifying parts of an MQTT statement
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct MqttStatement<'a> {
+    /// Protocol sequence, selecting the binding as for HTTP statements
+    pub sequence: u8,
+    /// Handlers for publishes and subscriptions; every matching handler runs, in order
+    #[serde(borrow, default)]
+    pub handlers: Vec<MqttHandler<'a>>,
+}
+
+impl MqttStatement<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> MqttStatement<'static> {
+        MqttStatement {
+            sequence: self.sequence,
+            handlers: self
+                .handlers
+                .into_iter()
+                .map(MqttHandler::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Actions to take when a client publishes or subscribes to matching topics
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct MqttHandler<'a> {
+    /// Whether the handler applies to publishes or subscriptions
+    pub event: MqttEvent,
+    /// The topic filter to match, where `+` matches one level and `#` every remaining level
+    #[serde(borrow)]
+    pub topic_filter: Cow<'a, str>,
+    /// The QoS level to match, or any level if not set
+    pub qos: Option<MqttQos>,
+    /// The retain flag to match on publishes, or either if not set
+    pub retain: Option<bool>,
+    /// Actions that take effect for each matching publish or subscription
+    #[serde(borrow, default)]
+    pub actions: Vec<RapidRecastAction<'a>>,
+}
+
+impl MqttHandler<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> MqttHandler<'static> {
+        MqttHandler {
+            event: self.event,
+            topic_filter: owned(self.topic_filter),
+            qos: self.qos,
+            retain: self.retain,
+            actions: self
+                .actions
+                .into_iter()
+                .map(RapidRecastAction::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// The MQTT client requests a handler can match
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum MqttEvent {
+    /// A client publishing a message
+    Publish,
+    /// A client subscribing to a topic filter
+    Subscribe,
+}
+
+/// MQTT delivery guarantees
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum MqttQos {
+    /// QoS 0, fire and forget
+    AtMostOnce,
+    /// QoS 1, acknowledged delivery
+    AtLeastOnce,
+    /// QoS 2, assured delivery
+    ExactlyOnce,
+}
+
+/// Check that an MQTT topic filter is well formed:
+/// `+` must fill a whole level, and `#` must fill the last one.
+pub fn check_mqtt_topic_filter(filter: &str) -> Result<(), RrdlError> {
+    let invalid = |reason: &str| {
+        Err(RrdlError::new(format!(
+            "Invalid MQTT topic filter '{}', {}",
+            filter, reason
+        )))
+    };
+    if filter.is_empty() {
+        return invalid("it must not be empty");
+    }
+    let mut levels = filter.split('/').peekable();
+    while let Some(level) = levels.next() {
+        if level != "+" && level.contains('+') {
+            return invalid("'+' must fill a whole level");
+        }
+        if level.contains('#') && (level != "#" || levels.peek().is_some()) {
+            return invalid("'#' must fill the last level");
+        }
+    }
+    Ok(())
+}
+
+/// Whether an MQTT topic name matches a topic filter.
+/// As the MQTT specification requires, topics starting with `$` never match a leading wildcard.
+pub fn mqtt_topic_matches(filter: &str, topic: &str) -> bool {
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+    let mut filter = filter.split('/');
+    let mut topic = topic.split('/');
+    loop {
+        match (filter.next(), topic.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(expected), Some(level)) if expected == level => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Whether a gRPC service or method name matches a pattern from a [`GrpcStatement`],
+/// where `*` matches any run of characters, including none.
+pub fn grpc_name_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let Some(last) = parts.next_back() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Http Methods supported by RapidRecast
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum RapidRecastHttpMethod {
+    /// Get Method
+    GET,
+    /// Post Method
+    POST,
+    /// Delete Method
+    DELETE,
+    /// Update Method
+    UPDATE,
+    /// Patch Method
+    PATCH,
+    /// Put Method
+    PUT,
+    /// Options Method
+    OPTIONS,
+    /// Head Method
+    HEAD,
+    /// Connect Method
+    CONNECT,
+    /// Trace Method
+    TRACE,
+}
+
+/// The protocols available in RapidRecast
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub enum RapidRecastProtocolType {
+    /// HTTP 1,2, and 3 protocols
+    HTTP,
+    /// WebSockets on HTTP 1, 2, and 3 - it is a different model of communication from HTTP 2/3 streams
+    WebSocket,
+    /// Kafka protocol
+    Kafka,
+    /// RabbitMQ protocol
+    RabbitMQ,
+    /// gRPC protocol
+    Grpc,
+    /// MQTT protocol
+    Mqtt,
+}
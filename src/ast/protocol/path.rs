@@ -0,0 +1,212 @@
+//! HTTP path templates with typed parameter captures.
+
+use crate::ast::action::RecastValue;
+use crate::error::RrdlError;
+use std::borrow::Cow;
+
+/// A parsed HTTP path template, such as `/users/{id:int}/posts/{slug}` or `/static/{*rest}`.
+///
+/// Templates start with `/` and are split into segments by `/`. A segment is literal text,
+/// a capture `{name}` or `{name:type}` matching one whole segment, or a rest capture `{*name}`
+/// matching everything after it, which must come last. Capture types are `string`, the default,
+/// `int`, `float` and `uuid`.
+///
+/// Captured segments are available to conditions and actions as params of the same name,
+/// so names must be identifiers, other than the RRDL keywords, and must not repeat.
+#[derive(PartialEq, Debug, Clone)]
+pub struct PathTemplate {
+    segments: Vec<PathSegment>,
+}
+
+/// One segment of a path template
+#[derive(PartialEq, Debug, Clone)]
+pub enum PathSegment {
+    /// Text the segment must equal
+    Literal(String),
+    /// A whole segment captured as a param
+    Capture {
+        /// The param name
+        name: String,
+        /// The values the segment may take
+        kind: PathParamType,
+    },
+    /// The rest of the path captured as a param, slashes included
+    Rest(String),
+}
+
+/// The values a captured segment may take
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PathParamType {
+    /// Any non-empty segment
+    String,
+    /// A whole number
+    Int,
+    /// A finite number
+    Float,
+    /// A UUID in its hyphenated form
+    Uuid,
+}
+
+/// Words the RRDL lexer reserves, which could not be used as params in conditions
+const RESERVED: &[&str] = &[
+    "definition",
+    "model",
+    "protocol",
+    "topic",
+    "cron",
+    "if",
+    "else",
+];
+
+impl PathTemplate {
+    /// Parse a path template, rejecting malformed captures and repeated param names.
+    pub fn parse(template: &str) -> Result<Self, RrdlError> {
+        let invalid = |reason: String| {
+            RrdlError::new(format!("Invalid path template '{}', {}", template, reason))
+        };
+        let path = template
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("it must start with '/'".to_string()))?;
+        let parts = path.split('/').collect::<Vec<_>>();
+        let mut segments = Vec::with_capacity(parts.len());
+        for (index, part) in parts.iter().enumerate() {
+            let capture = match part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                Some(capture) => capture,
+                None if part.contains(['{', '}']) => {
+                    return Err(invalid("a capture must fill a whole segment".to_string()))
+                }
+                None => {
+                    segments.push(PathSegment::Literal(part.to_string()));
+                    continue;
+                }
+            };
+            let segment = match capture.strip_prefix('*') {
+                Some(name) if index + 1 < parts.len() => {
+                    return Err(invalid(format!("'{{*{}}}' must be the last segment", name)))
+                }
+                Some(name) => PathSegment::Rest(name.to_string()),
+                None => {
+                    let (name, kind) = capture.split_once(':').unwrap_or((capture, "string"));
+                    let kind = match kind {
+                        "string" => PathParamType::String,
+                        "int" => PathParamType::Int,
+                        "float" => PathParamType::Float,
+                        "uuid" => PathParamType::Uuid,
+                        other => {
+                            return Err(invalid(format!(
+                                "unknown param type '{}', expected one of string, int, float, uuid",
+                                other
+                            )))
+                        }
+                    };
+                    PathSegment::Capture {
+                        name: name.to_string(),
+                        kind,
+                    }
+                }
+            };
+            segments.push(segment);
+        }
+        let template = PathTemplate { segments };
+        let mut names = Vec::new();
+        for name in template.params() {
+            if !is_param_name(name) {
+                return Err(invalid(format!("'{}' is not a valid param name", name)));
+            }
+            if names.contains(&name) {
+                return Err(invalid(format!(
+                    "param '{}' is captured more than once",
+                    name
+                )));
+            }
+            names.push(name);
+        }
+        Ok(template)
+    }
+
+    /// The segments of the template, in order.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// The names of the params the template captures, in order.
+    pub fn params(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            PathSegment::Literal(_) => None,
+            PathSegment::Capture { name, .. } | PathSegment::Rest(name) => Some(name.as_str()),
+        })
+    }
+
+    /// Match a request path, returning the captured params, or `None` if it does not match.
+    ///
+    /// `int` and `float` captures are numbers, and the others are strings. Segments are
+    /// compared as written, without percent decoding. A rest capture needs the slash before it,
+    /// but may be empty.
+    pub fn captures<'p>(&self, path: &'p str) -> Option<Vec<(&str, RecastValue<'p>)>> {
+        let mut rest = path.strip_prefix('/')?;
+        let mut captures = Vec::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            let (part, remaining) = match rest.split_once('/') {
+                Some((part, remaining)) => (part, Some(remaining)),
+                None => (rest, None),
+            };
+            match segment {
+                PathSegment::Literal(text) if text == part => {}
+                PathSegment::Literal(_) => return None,
+                PathSegment::Capture { name, kind } => {
+                    captures.push((name.as_str(), kind.value(part)?))
+                }
+                PathSegment::Rest(name) => {
+                    captures.push((name.as_str(), RecastValue::String(Cow::Borrowed(rest))));
+                    return Some(captures);
+                }
+            }
+            match remaining {
+                Some(remaining) => rest = remaining,
+                None if index + 1 == self.segments.len() => return Some(captures),
+                None => return None,
+            }
+        }
+        None
+    }
+}
+
+impl PathParamType {
+    fn value<'p>(&self, segment: &'p str) -> Option<RecastValue<'p>> {
+        match self {
+            PathParamType::String if !segment.is_empty() => {
+                Some(RecastValue::String(Cow::Borrowed(segment)))
+            }
+            PathParamType::Int => segment
+                .parse::<i64>()
+                .ok()
+                .map(|n| RecastValue::Number(n as f64)),
+            PathParamType::Float => segment
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(RecastValue::Number),
+            PathParamType::Uuid if is_uuid(segment) => {
+                Some(RecastValue::String(Cow::Borrowed(segment)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether a name can be used as a param in conditions and templates.
+pub(crate) fn is_param_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED.contains(&name)
+}
+
+fn is_uuid(segment: &str) -> bool {
+    let groups = segment.split('-').collect::<Vec<_>>();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
@@ -0,0 +1,179 @@
+use crate::ast::action::RecastValue;
+use crate::ast::protocol::{
+    HttpRegex, HttpValueMatch, PathParamType, PathSegment, PathTemplate, RapidProtocolDefinition,
+};
+use crate::ast::RapidAstStatement;
+use crate::json::JsonRRDL;
+use crate::test::schema_with_http_matchers;
+use crate::{ParseRRDL, SaveRRDL};
+use std::borrow::Cow;
+
+fn captures<'p>(template: &str, path: &'p str) -> Option<Vec<(String, RecastValue<'p>)>> {
+    let template = PathTemplate::parse(template).unwrap();
+    template.captures(path).map(|captures| {
+        captures
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    })
+}
+
+#[test]
+pub fn typed_captures() {
+    let template = PathTemplate::parse("/users/{id:int}/posts/{slug}").unwrap();
+    assert_eq!(
+        template.segments(),
+        &[
+            PathSegment::Literal("users".to_string()),
+            PathSegment::Capture {
+                name: "id".to_string(),
+                kind: PathParamType::Int
+            },
+            PathSegment::Literal("posts".to_string()),
+            PathSegment::Capture {
+                name: "slug".to_string(),
+                kind: PathParamType::String
+            },
+        ]
+    );
+    assert_eq!(template.params().collect::<Vec<_>>(), vec!["id", "slug"]);
+    assert_eq!(
+        captures("/users/{id:int}/posts/{slug}", "/users/42/posts/hello"),
+        Some(vec![
+            ("id".to_string(), RecastValue::Number(42.0)),
+            (
+                "slug".to_string(),
+                RecastValue::String(Cow::Borrowed("hello"))
+            ),
+        ])
+    );
+    assert_eq!(
+        captures("/users/{id:int}/posts/{slug}", "/users/me/posts/hello"),
+        None
+    );
+    assert_eq!(
+        captures("/users/{id:int}/posts/{slug}", "/users/42/posts/"),
+        None
+    );
+    assert_eq!(
+        captures("/users/{id:int}/posts/{slug}", "/users/42/posts/hello/more"),
+        None
+    );
+    assert_eq!(
+        captures(
+            "/things/{id:uuid}",
+            "/things/123e4567-e89b-12d3-a456-426614174000"
+        ),
+        Some(vec![(
+            "id".to_string(),
+            RecastValue::String(Cow::Borrowed("123e4567-e89b-12d3-a456-426614174000"))
+        )])
+    );
+    assert_eq!(captures("/things/{id:uuid}", "/things/123e4567"), None);
+    assert_eq!(captures("/", "/"), Some(vec![]));
+}
+
+#[test]
+pub fn rest_captures() {
+    assert_eq!(
+        captures("/static/{*rest}", "/static/css/site.css"),
+        Some(vec![(
+            "rest".to_string(),
+            RecastValue::String(Cow::Borrowed("css/site.css"))
+        )])
+    );
+    assert_eq!(
+        captures("/static/{*rest}", "/static/"),
+        Some(vec![(
+            "rest".to_string(),
+            RecastValue::String(Cow::Borrowed(""))
+        )])
+    );
+    assert_eq!(captures("/static/{*rest}", "/static"), None);
+}
+
+#[test]
+pub fn invalid_path_templates() {
+    let error = |template| PathTemplate::parse(template).unwrap_err().message;
+    assert_eq!(
+        error("users/{id}"),
+        "Invalid path template 'users/{id}', it must start with '/'"
+    );
+    assert_eq!(
+        error("/files/{name}.json"),
+        "Invalid path template '/files/{name}.json', a capture must fill a whole segment"
+    );
+    assert_eq!(
+        error("/static/{*rest}/more"),
+        "Invalid path template '/static/{*rest}/more', '{*rest}' must be the last segment"
+    );
+    assert_eq!(
+        error("/users/{id:date}"),
+        "Invalid path template '/users/{id:date}', unknown param type 'date', expected one of string, int, float, uuid"
+    );
+    assert_eq!(
+        error("/users/{user-id}"),
+        "Invalid path template '/users/{user-id}', 'user-id' is not a valid param name"
+    );
+    assert_eq!(
+        error("/{topic}"),
+        "Invalid path template '/{topic}', 'topic' is not a valid param name"
+    );
+    assert_eq!(
+        error("/{id}/{id:int}"),
+        "Invalid path template '/{id}/{id:int}', param 'id' is captured more than once"
+    );
+}
+
+#[test]
+pub fn http_value_matches() {
+    let exact = HttpValueMatch::Exact(Cow::Borrowed("2"));
+    assert!(exact.matches(Some("2")).unwrap());
+    assert!(!exact.matches(Some("2.1")).unwrap());
+    let prefix = HttpValueMatch::Prefix(Cow::Borrowed("application/json"));
+    assert!(prefix
+        .matches(Some("application/json; charset=utf-8"))
+        .unwrap());
+    assert!(!prefix.matches(Some("text/plain")).unwrap());
+    let regex = HttpValueMatch::Regex(HttpRegex::new(Cow::Borrowed("^2\\.[0-9]+$")));
+    assert!(regex.matches(Some("2.10")).unwrap());
+    assert!(!regex.matches(Some("3.0")).unwrap());
+    assert!(HttpValueMatch::Present.matches(Some("")).unwrap());
+    assert!(!HttpValueMatch::Present.matches(None).unwrap());
+    assert!(!exact.matches(None).unwrap());
+
+    let invalid = HttpValueMatch::Regex(HttpRegex::new(Cow::Borrowed("a{2")));
+    assert!(invalid.check().is_err());
+    assert!(invalid.matches(Some("aa")).is_err());
+}
+
+#[test]
+pub fn regexes_compile_once() {
+    let regex = HttpRegex::new(Cow::Borrowed("^2\\.[0-9]+$"));
+    assert!(regex.compiled.get().is_none());
+    let value = HttpValueMatch::Regex(regex);
+    value.check().unwrap();
+    let HttpValueMatch::Regex(regex) = value.into_owned() else {
+        unreachable!()
+    };
+    assert!(regex.compiled.get().is_some());
+    assert_eq!(regex.as_str(), "^2\\.[0-9]+$");
+}
+
+#[test]
+pub fn loading_compiles_regexes() {
+    let input = JsonRRDL {}.save_rrdl(&schema_with_http_matchers()).unwrap();
+    let definition = JsonRRDL {}
+        .parse_rrdl(std::str::from_utf8(&input).unwrap())
+        .unwrap();
+    let RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+        http,
+    )) = &definition.ast[0]
+    else {
+        unreachable!()
+    };
+    match &http.headers[0].value {
+        HttpValueMatch::Regex(regex) => assert!(regex.compiled.get().is_some()),
+        other => panic!("Unexpected matcher {:?}", other),
+    }
+}
@@ -0,0 +1,149 @@
+use crate::ast::action::{
+    ConditionStatement, LogicBasedAction, RapidRecastAction, RapidRecastRbacSubject, RecastValue,
+};
+use crate::ast::protocol::{
+    check_mqtt_topic_filter, grpc_name_matches, mqtt_topic_matches, RapidProtocolDefinition,
+};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::rrdl::{RrdlParser, RrdlPrinter};
+use crate::test::schema_with_auth_actions;
+use crate::{ParseRRDL, SaveRRDL};
+use std::borrow::Cow;
+
+fn cache(definition: RapidRecastDefinition<'_>) -> RapidRecastDefinition<'static> {
+    definition.into_owned()
+}
+
+#[test]
+pub fn definition_outlives_its_input() {
+    let input = String::from_utf8(
+        RrdlPrinter {}
+            .save_rrdl(&schema_with_auth_actions())
+            .unwrap(),
+    )
+    .unwrap();
+    let parsed = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert!(matches!(parsed.id, Cow::Borrowed(_)));
+
+    let cached = cache(parsed);
+    drop(input);
+    assert!(matches!(cached.id, Cow::Owned(_)));
+    assert_eq!(cached, schema_with_auth_actions());
+}
+
+#[test]
+pub fn statements_and_actions_into_owned() {
+    let namespace = String::from("ns");
+    let condition = ConditionStatement::Equals(
+        RecastValue::Param(Cow::Borrowed(&namespace)),
+        RecastValue::String(Cow::Borrowed("admin")),
+    );
+    let action = RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+        condition: condition.clone(),
+        if_true: Box::new(RapidRecastAction::LogicBasedAction(
+            LogicBasedAction::ConditionBlock {
+                condition: ConditionStatement::Not(RecastValue::Number(1.0)),
+                if_true: Box::new(schema_actions()),
+                if_false: None,
+            },
+        )),
+        if_false: Some(Box::new(schema_actions())),
+    });
+    let owned_condition = condition.clone().into_owned();
+    let owned_action = action.clone().into_owned();
+    let owned_subject =
+        RapidRecastRbacSubject::UserOrRole(Cow::Borrowed(&namespace), Cow::Borrowed("u"))
+            .into_owned();
+    drop(namespace);
+
+    assert_eq!(
+        owned_condition,
+        ConditionStatement::Equals(
+            RecastValue::Param(Cow::Owned("ns".to_string())),
+            RecastValue::String(Cow::Owned("admin".to_string())),
+        )
+    );
+    match owned_action {
+        RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+            condition: ConditionStatement::Equals(RecastValue::Param(param), _),
+            ..
+        }) => assert!(matches!(param, Cow::Owned(_))),
+        other => panic!("Unexpected action {:?}", other),
+    }
+    assert_eq!(
+        owned_subject,
+        RapidRecastRbacSubject::UserOrRole(Cow::Owned("ns".into()), Cow::Owned("u".into()))
+    );
+}
+
+/// The first action of the auth fixture's HTTP statement
+fn schema_actions() -> RapidRecastAction<'static> {
+    match schema_with_auth_actions().ast.remove(0).into_owned() {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            mut http,
+        )) => http.actions.remove(0),
+        other => panic!("Unexpected statement {:?}", other),
+    }
+}
+
+#[test]
+pub fn mqtt_topic_filters_match_topics() {
+    assert!(mqtt_topic_matches(
+        "sensors/+/temperature",
+        "sensors/kitchen/temperature"
+    ));
+    assert!(!mqtt_topic_matches(
+        "sensors/+/temperature",
+        "sensors/kitchen/humidity"
+    ));
+    assert!(!mqtt_topic_matches(
+        "sensors/+",
+        "sensors/kitchen/temperature"
+    ));
+    assert!(mqtt_topic_matches(
+        "sensors/#",
+        "sensors/kitchen/temperature"
+    ));
+    assert!(mqtt_topic_matches("sensors/#", "sensors"));
+    assert!(mqtt_topic_matches("+/+", "/finance"));
+    assert!(!mqtt_topic_matches("#", "$SYS/broker/uptime"));
+    assert!(mqtt_topic_matches("$SYS/#", "$SYS/broker/uptime"));
+}
+
+#[test]
+pub fn grpc_names_match_patterns() {
+    let service = "acme.users.v1.UserService";
+    assert!(grpc_name_matches(service, service));
+    assert!(!grpc_name_matches(service, "acme.users.v1.UserServiceV2"));
+    assert!(!grpc_name_matches("GetUser", "ListUsers"));
+    assert!(grpc_name_matches("*", "ListUsers"));
+    assert!(grpc_name_matches("*", ""));
+    assert!(grpc_name_matches("acme.users.*", service));
+    assert!(grpc_name_matches("*.UserService", service));
+    assert!(grpc_name_matches("acme.*.v1.*Service", service));
+    assert!(!grpc_name_matches("acme.*.v2.*", service));
+    assert!(!grpc_name_matches("Get*User", "GetUsers"));
+    assert!(!grpc_name_matches("ab*ba", "aba"));
+}
+
+#[test]
+pub fn invalid_mqtt_topic_filters() {
+    let error = |filter| check_mqtt_topic_filter(filter).unwrap_err().message;
+    assert!(check_mqtt_topic_filter("+/a/#").is_ok());
+    assert_eq!(
+        error(""),
+        "Invalid MQTT topic filter '', it must not be empty"
+    );
+    assert_eq!(
+        error("a/b#"),
+        "Invalid MQTT topic filter 'a/b#', '#' must fill the last level"
+    );
+    assert_eq!(
+        error("#/a"),
+        "Invalid MQTT topic filter '#/a', '#' must fill the last level"
+    );
+    assert_eq!(
+        error("a+/b"),
+        "Invalid MQTT topic filter 'a+/b', '+' must fill a whole level"
+    );
+}
@@ -1,6 +1,79 @@
 //! Topic Definition related AST
+use crate::ast::action::{RapidRecastRbacAction, RapidRecastRbacSubject};
+use crate::ast::model::ModelReference;
+use crate::ast::owned;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 /// A Topic Definition
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
-pub struct RapidTopicDefinition {}
+pub struct RapidTopicDefinition<'a> {
+    /// The namespace the topic belongs to
+    #[serde(borrow)]
+    pub namespace: Cow<'a, str>,
+    /// The name of the topic within its namespace
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    /// The number of partitions, or the server default if not set
+    pub partitions: Option<u32>,
+    /// How long and how much data the topic keeps
+    #[serde(default)]
+    pub retention: TopicRetention,
+    /// Whether only the latest message for each key is kept
+    #[serde(default)]
+    pub compacted: bool,
+    /// The model describing message keys
+    #[serde(borrow)]
+    pub key_schema: Option<ModelReference<'a>>,
+    /// The model describing message values
+    #[serde(borrow)]
+    pub value_schema: Option<ModelReference<'a>>,
+    /// Access granted on the topic when it is created
+    #[serde(borrow, default)]
+    pub acl: Vec<TopicAcl<'a>>,
+}
+
+impl RapidTopicDefinition<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> RapidTopicDefinition<'static> {
+        RapidTopicDefinition {
+            namespace: owned(self.namespace),
+            name: owned(self.name),
+            partitions: self.partitions,
+            retention: self.retention,
+            compacted: self.compacted,
+            key_schema: self.key_schema.map(ModelReference::into_owned),
+            value_schema: self.value_schema.map(ModelReference::into_owned),
+            acl: self.acl.into_iter().map(TopicAcl::into_owned).collect(),
+        }
+    }
+}
+
+/// Limits after which old messages are removed; unset limits use the server default
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TopicRetention {
+    /// How long messages are kept, in milliseconds
+    pub time_ms: Option<u64>,
+    /// How many bytes each partition keeps
+    pub size_bytes: Option<u64>,
+}
+
+/// A default access rule on a topic
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct TopicAcl<'a> {
+    /// Who the rule applies to
+    #[serde(borrow)]
+    pub subject: RapidRecastRbacSubject<'a>,
+    /// What they are allowed to do with the topic
+    pub action: RapidRecastRbacAction,
+}
+
+impl TopicAcl<'_> {
+    /// See [`RapidRecastDefinition::into_owned`](crate::ast::RapidRecastDefinition::into_owned).
+    pub fn into_owned(self) -> TopicAcl<'static> {
+        TopicAcl {
+            subject: self.subject.into_owned(),
+            action: self.action,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Checks run on every definition once it has been read, whatever its format.
+//!
+//! The RRDL and XML readers report these problems as they read, with a position, while
+//! serde only checks the shape of JSON, YAML and TOML. Every [`ParseRRDL`](crate::ParseRRDL)
+//! implementation runs [`RapidRecastDefinition::validate`] on what it read, so a definition
+//! loads the same way in every format.
+
+use crate::ast::action::{
+    AuthBasedAction, LogicBasedAction, RapidRecastAction, RapidRecastRbacObject,
+};
+use crate::ast::cron::RapidCronDefinition;
+use crate::ast::model::ModelField;
+use crate::ast::protocol::{
+    check_mqtt_topic_filter, HttpStatement, HttpValueMatch, RapidProtocolDefinition,
+};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::error::RrdlError;
+
+impl RapidRecastDefinition<'_> {
+    /// Check the parts of the definition that serde cannot, such as path templates, cron
+    /// schedules and model defaults, returning the first problem found.
+    pub fn validate(&self) -> Result<(), RrdlError> {
+        self.ast.iter().try_for_each(statement)
+    }
+}
+
+fn statement(statement: &RapidAstStatement) -> Result<(), RrdlError> {
+    match statement {
+        RapidAstStatement::ProtocolDefinition(protocol) => protocol_definition(protocol),
+        RapidAstStatement::CronDefinition(cron) => cron_definition(cron),
+        RapidAstStatement::ModelDefinition(model) => {
+            model.fields.iter().try_for_each(ModelField::check)
+        }
+        RapidAstStatement::TopicDefinition(_) => Ok(()),
+    }
+}
+
+fn cron_definition(cron: &RapidCronDefinition) -> Result<(), RrdlError> {
+    cron.parsed_schedule()?;
+    cron.time_zone()?;
+    actions(&cron.actions)
+}
+
+fn protocol_definition(protocol: &RapidProtocolDefinition) -> Result<(), RrdlError> {
+    match protocol {
+        RapidProtocolDefinition::HttpProtocolDefinition(http) => http_statement(http),
+        RapidProtocolDefinition::WebSocketProtocolDefinition(websocket) => {
+            actions(&websocket.on_connect)?;
+            for handler in &websocket.on_message {
+                actions(&handler.actions)?;
+            }
+            actions(&websocket.on_close)
+        }
+        RapidProtocolDefinition::KafkaProtocolDefinition(kafka) => actions(&kafka.actions),
+        RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp) => amqp
+            .on_publish
+            .iter()
+            .try_for_each(|handler| actions(&handler.actions)),
+        RapidProtocolDefinition::GrpcProtocolDefinition(grpc) => actions(&grpc.actions),
+        RapidProtocolDefinition::MqttProtocolDefinition(mqtt) => {
+            mqtt.handlers.iter().try_for_each(|handler| {
+                check_mqtt_topic_filter(&handler.topic_filter)?;
+                actions(&handler.actions)
+            })
+        }
+    }
+}
+
+fn http_statement(http: &HttpStatement) -> Result<(), RrdlError> {
+    http.parsed_paths()?;
+    http.check_body_size()?;
+    let fields = http
+        .headers
+        .iter()
+        .chain(&http.query)
+        .map(|field| &field.value);
+    http.host
+        .iter()
+        .chain(&http.content_type)
+        .chain(fields)
+        .try_for_each(HttpValueMatch::check)?;
+    actions(&http.actions)
+}
+
+fn actions(actions: &[RapidRecastAction]) -> Result<(), RrdlError> {
+    actions.iter().try_for_each(action)
+}
+
+fn action(action: &RapidRecastAction) -> Result<(), RrdlError> {
+    match action {
+        RapidRecastAction::ResponseBasedAction(response) => response.check(),
+        RapidRecastAction::ForwardAction(forward) => forward.check(),
+        RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+            if_true,
+            if_false,
+            ..
+        }) => {
+            self::action(if_true)?;
+            if_false.as_deref().map_or(Ok(()), self::action)
+        }
+        RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+            policy, ..
+        }) => policy.iter().try_for_each(|policy| match &policy.object {
+            RapidRecastRbacObject::MqttTopic(filter) => check_mqtt_topic_filter(filter),
+            _ => Ok(()),
+        }),
+        RapidRecastAction::AuthBasedAction(_) => Ok(()),
+    }
+}
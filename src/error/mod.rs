@@ -0,0 +1,279 @@
+//! Diagnostics produced while reading or writing RapidRecast Definitions.
+
+#[cfg(test)]
+mod test;
+
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// How serious a diagnostic is
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Severity {
+    /// The definition could not be processed
+    Error,
+    /// The definition was processed, but something is likely wrong
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A position in the source, both 1-based. Columns count characters, not bytes.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LineColumn {
+    /// The line, starting from 1
+    pub line: usize,
+    /// The column, starting from 1
+    pub column: usize,
+}
+
+impl LineColumn {
+    /// Find the line and column of a byte offset into the source.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let before = source.get(..offset).unwrap_or(source);
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        LineColumn {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// The region of the source a diagnostic refers to. The end is exclusive.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct SourceSpan {
+    /// Where the region starts
+    pub start: LineColumn,
+    /// Where the region ends
+    pub end: LineColumn,
+}
+
+impl SourceSpan {
+    /// A span covering a single position.
+    pub fn point(line: usize, column: usize) -> Self {
+        let position = LineColumn { line, column };
+        SourceSpan {
+            start: position,
+            end: LineColumn {
+                line,
+                column: column + 1,
+            },
+        }
+    }
+
+    /// Convert a byte range into the source into lines and columns.
+    pub fn from_byte_range(source: &str, range: Range<usize>) -> Self {
+        SourceSpan {
+            start: LineColumn::from_offset(source, range.start),
+            end: LineColumn::from_offset(source, range.end),
+        }
+    }
+}
+
+/// A diagnostic about a RapidRecast Definition.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RrdlError {
+    /// How serious the diagnostic is
+    pub severity: Severity,
+    /// What went wrong
+    pub message: String,
+    /// The name of the file the definition came from, if known
+    pub file: Option<String>,
+    /// Where in the source the problem is, if known
+    pub span: Option<SourceSpan>,
+    /// What would have been valid at this point
+    pub expected: Vec<String>,
+    /// What was found instead
+    pub found: Option<String>,
+}
+
+impl RrdlError {
+    /// Create an error with only a message.
+    pub fn new(message: impl Into<String>) -> Self {
+        RrdlError {
+            severity: Severity::Error,
+            message: message.into(),
+            file: None,
+            span: None,
+            expected: vec![],
+            found: None,
+        }
+    }
+
+    /// Set the severity of the diagnostic.
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set the name of the file the diagnostic refers to.
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Set where in the source the diagnostic refers to.
+    pub fn with_span(mut self, span: SourceSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Set what would have been valid.
+    pub fn with_expected(mut self, expected: Vec<String>) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Set what was found instead.
+    pub fn with_found(mut self, found: impl Into<String>) -> Self {
+        self.found = Some(found.into());
+        self
+    }
+
+    /// Render the diagnostic with the offending source lines and carets under the problem.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("{}: {}\n", self.severity, self.summary());
+        let Some(span) = self.span else {
+            if let Some(file) = &self.file {
+                out.push_str(&format!(" --> {}\n", file));
+            }
+            return out;
+        };
+        out.push_str(&format!(
+            " --> {}:{}:{}\n",
+            self.file.as_deref().unwrap_or("<input>"),
+            span.start.line,
+            span.start.column
+        ));
+        let Some(line) = source.lines().nth(span.start.line.saturating_sub(1)) else {
+            return out;
+        };
+        let gutter = " ".repeat(span.start.line.to_string().len());
+        let width = if span.end.line == span.start.line {
+            span.end.column.saturating_sub(span.start.column).max(1)
+        } else {
+            (line.chars().count() + 1)
+                .saturating_sub(span.start.column)
+                .max(1)
+        };
+        out.push_str(&format!("{} |\n", gutter));
+        out.push_str(&format!("{} | {}\n", span.start.line, line));
+        out.push_str(&format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(span.start.column.saturating_sub(1)),
+            "^".repeat(width)
+        ));
+        out
+    }
+
+    /// Point a diagnostic without a position at where the value it quotes first appears in
+    /// `source`, such as the regex in `Invalid regex '(true', unclosed group`.
+    /// Values that cannot be found, for example because the source escapes them, leave the
+    /// diagnostic as it is.
+    pub(crate) fn located_in(self, source: &str) -> Self {
+        if self.span.is_some() {
+            return self;
+        }
+        let Some(quoted) = self.message.split('\'').nth(1).filter(|q| !q.is_empty()) else {
+            return self;
+        };
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let found = source.match_indices(quoted).find(|(start, _)| {
+            !is_word(source[..*start].chars().next_back())
+                && !is_word(source[start + quoted.len()..].chars().next())
+        });
+        match found {
+            Some((start, _)) => {
+                let span = SourceSpan::from_byte_range(source, start..start + quoted.len());
+                self.with_span(span)
+            }
+            None => self,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut summary = self.message.clone();
+        if !self.expected.is_empty() {
+            summary.push_str(&format!(", expected one of: {}", self.expected.join(", ")));
+        }
+        summary
+    }
+}
+
+impl Display for RrdlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.summary())?;
+        match (&self.file, &self.span) {
+            (Some(file), Some(span)) => {
+                write!(f, " at {}:{}:{}", file, span.start.line, span.start.column)
+            }
+            (None, Some(span)) => write!(f, " at {}:{}", span.start.line, span.start.column),
+            (Some(file), None) => write!(f, " in {}", file),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for RrdlError {}
+
+impl From<serde_json::Error> for RrdlError {
+    fn from(e: serde_json::Error) -> Self {
+        let message = e.to_string();
+        let suffix = format!(" at line {} column {}", e.line(), e.column());
+        let message = message.strip_suffix(&suffix).unwrap_or(&message);
+        let error = RrdlError::new(message);
+        if e.line() == 0 {
+            return error;
+        }
+        error.with_span(SourceSpan::point(e.line(), e.column().max(1)))
+    }
+}
+
+impl From<std::io::Error> for RrdlError {
+    fn from(e: std::io::Error) -> Self {
+        RrdlError::new(e.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for RrdlError {
+    fn from(e: serde_yaml::Error) -> Self {
+        let message = e.to_string();
+        let Some(location) = e.location() else {
+            return RrdlError::new(message);
+        };
+        let position = format!(" at line {} column {}", location.line(), location.column());
+        RrdlError::new(message.replacen(&position, "", 1))
+            .with_span(SourceSpan::point(location.line(), location.column()))
+    }
+}
+
+impl RrdlError {
+    /// Convert a TOML error, which only knows the byte range it refers to in `source`.
+    pub(crate) fn from_toml(e: toml::de::Error, source: &str) -> Self {
+        let error = RrdlError::new(e.message().trim_end());
+        match e.span() {
+            Some(span) => error.with_span(SourceSpan::from_byte_range(source, span)),
+            None => error,
+        }
+    }
+}
+
+impl From<toml::ser::Error> for RrdlError {
+    fn from(e: toml::ser::Error) -> Self {
+        RrdlError::new(e.to_string())
+    }
+}
+
+impl From<quick_xml::Error> for RrdlError {
+    fn from(e: quick_xml::Error) -> Self {
+        RrdlError::new(e.to_string())
+    }
+}
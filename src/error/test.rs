@@ -0,0 +1,114 @@
+use crate::error::{LineColumn, RrdlError, Severity, SourceSpan};
+use crate::json::JsonRRDL;
+use crate::rrdl::RrdlPrinter;
+use crate::test::schema_with_auth_actions;
+use crate::toml::TomlRRDL;
+use crate::xml::XmlRRDL;
+use crate::yaml::YamlRRDL;
+use crate::{ParseRRDL, SaveRRDL};
+use std::io::{ErrorKind, Write};
+
+#[test]
+pub fn line_column_from_offset() {
+    let source = "ab\ncdé\nf";
+    assert_eq!(
+        LineColumn::from_offset(source, 0),
+        LineColumn { line: 1, column: 1 }
+    );
+    assert_eq!(
+        LineColumn::from_offset(source, 4),
+        LineColumn { line: 2, column: 2 }
+    );
+    assert_eq!(
+        LineColumn::from_offset(source, 7),
+        LineColumn { line: 2, column: 4 }
+    );
+    assert_eq!(
+        LineColumn::from_offset(source, source.len()),
+        LineColumn { line: 3, column: 2 }
+    );
+}
+
+#[test]
+pub fn render_with_carets() {
+    let source = "definition \"id\" {\n    language_version 1.2;\n}";
+    let error = RrdlError::new("Unexpected '1.2'")
+        .with_file("some.rrdl")
+        .with_expected(vec!["version".to_string()])
+        .with_found("'1.2'")
+        .with_span(SourceSpan::from_byte_range(source, 39..42));
+    assert_eq!(
+        error.render(source),
+        r#"error: Unexpected '1.2', expected one of: version
+ --> some.rrdl:2:22
+  |
+2 |     language_version 1.2;
+  |                      ^^^
+"#
+    );
+    assert_eq!(
+        error.to_string(),
+        "error: Unexpected '1.2', expected one of: version at some.rrdl:2:22"
+    );
+}
+
+#[test]
+pub fn render_at_line_zero() {
+    let error = RrdlError::new("Something is odd").with_span(SourceSpan::point(0, 0));
+    assert_eq!(
+        error.render("definition"),
+        r#"error: Something is odd
+ --> <input>:0:0
+  |
+0 | definition
+  | ^
+"#
+    );
+}
+
+#[test]
+pub fn render_without_span() {
+    let error = RrdlError::new("Something is odd").with_severity(Severity::Warning);
+    assert_eq!(error.render(""), "warning: Something is odd\n");
+    assert_eq!(error.to_string(), "warning: Something is odd");
+}
+
+#[test]
+pub fn json_errors_have_positions() {
+    let input = "{\n  \"id\": 12\n}";
+    let error = JsonRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(
+        error.message,
+        "invalid type: integer `12`, expected a string"
+    );
+    assert_eq!(error.span, Some(SourceSpan::point(2, 10)));
+}
+
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(ErrorKind::BrokenPipe, "pipe closed"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn write_failures_are_reported() {
+    let definition = schema_with_auth_actions();
+    let errors = [
+        JsonRRDL {}.save_to(&definition, ClosedPipe).unwrap_err(),
+        YamlRRDL {}.save_to(&definition, ClosedPipe).unwrap_err(),
+        TomlRRDL {}.save_to(&definition, ClosedPipe).unwrap_err(),
+        XmlRRDL {}.save_to(&definition, ClosedPipe).unwrap_err(),
+        RrdlPrinter {}.save_to(&definition, ClosedPipe).unwrap_err(),
+    ];
+    for error in errors {
+        assert!(error.message.contains("pipe closed"), "{}", error);
+        assert_eq!(error.span, None);
+    }
+}
@@ -0,0 +1,157 @@
+//! Detection of the format a definition is written in, and loading from any format.
+
+#[cfg(test)]
+mod test;
+
+use crate::ast::RapidRecastDefinition;
+use crate::error::RrdlError;
+use crate::json::JsonRRDL;
+use crate::rrdl::RrdlParser;
+use crate::toml::TomlRRDL;
+use crate::xml::XmlRRDL;
+use crate::yaml::YamlRRDL;
+use crate::ParseRRDL;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// The formats a RapidRecast Definition can be written in
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Format {
+    /// The RapidRecast Definition Language itself
+    Rrdl,
+    /// JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// TOML
+    Toml,
+    /// XML
+    Xml,
+}
+
+impl Format {
+    /// The format conventionally used by files with the given extension, without the dot.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "rrdl" => Some(Format::Rrdl),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "xml" => Some(Format::Xml),
+            _ => None,
+        }
+    }
+
+    /// The format conventionally used by a file, judging by its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+    }
+
+    /// Guess the format of a definition from its content.
+    ///
+    /// XML starts with `<`, JSON with `{`, and RRDL with the `definition` keyword.
+    /// Otherwise the first line that is not blank or a comment decides between
+    /// TOML (`key = value` or a `[table]` header) and YAML.
+    pub fn detect(input: &str) -> Format {
+        let input = input.trim_start_matches('\u{feff}').trim_start();
+        if input.starts_with('<') {
+            return Format::Xml;
+        }
+        if input.starts_with('{') {
+            return Format::Json;
+        }
+        if starts_with_definition_keyword(input) {
+            return Format::Rrdl;
+        }
+        let first_line = input
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'));
+        match first_line {
+            Some(line) if line.starts_with('[') || is_toml_key_value(line) => Format::Toml,
+            _ => Format::Yaml,
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Rrdl => write!(f, "RRDL"),
+            Format::Json => write!(f, "JSON"),
+            Format::Yaml => write!(f, "YAML"),
+            Format::Toml => write!(f, "TOML"),
+            Format::Xml => write!(f, "XML"),
+        }
+    }
+}
+
+/// Skip RRDL comments and check whether the `definition` keyword comes first.
+fn starts_with_definition_keyword(mut input: &str) -> bool {
+    loop {
+        input = input.trim_start();
+        if let Some(rest) = input.strip_prefix("//") {
+            input = rest.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+        } else if let Some(rest) = input.strip_prefix("/*") {
+            input = rest.split_once("*/").map(|(_, rest)| rest).unwrap_or("");
+        } else {
+            break;
+        }
+    }
+    input
+        .strip_prefix("definition")
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_whitespace() || c == '"')
+}
+
+/// Whether a line looks like a TOML `key = value` pair, with a bare or quoted key.
+fn is_toml_key_value(line: &str) -> bool {
+    let key = match line.split_once('=') {
+        Some((key, _)) => key.trim(),
+        None => return false,
+    };
+    let quoted = key.len() >= 2
+        && ((key.starts_with('"') && key.ends_with('"'))
+            || (key.starts_with('\'') && key.ends_with('\'')));
+    quoted
+        || (!key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+}
+
+/// Parse a definition in any supported format.
+///
+/// With a hint, the input is parsed in that format; a hint usually comes from
+/// [`Format::from_path`]. Without one, the format is detected from the content.
+/// Returns the format that was used along with the definition.
+pub fn load_definition<'input>(
+    input: &'input str,
+    hint: Option<Format>,
+) -> Result<(Format, RapidRecastDefinition<'input>), RrdlError> {
+    let format = hint.unwrap_or_else(|| Format::detect(input));
+    let definition = match format {
+        Format::Rrdl => RrdlParser {}.parse_rrdl(input),
+        Format::Json => JsonRRDL {}.parse_rrdl(input),
+        Format::Yaml => YamlRRDL {}.parse_rrdl(input),
+        Format::Toml => TomlRRDL {}.parse_rrdl(input),
+        Format::Xml => XmlRRDL {}.parse_rrdl(input),
+    }?;
+    Ok((format, definition))
+}
+
+/// Parse a definition read from the file at `path`.
+///
+/// The format is chosen by the file's extension, or detected from the content when the
+/// extension is not a known one, and diagnostics name the file.
+pub fn load_definition_file<'input>(
+    path: impl AsRef<Path>,
+    input: &'input str,
+) -> Result<(Format, RapidRecastDefinition<'input>), RrdlError> {
+    let path = path.as_ref();
+    load_definition(input, Format::from_path(path))
+        .map_err(|e| e.with_file(path.display().to_string()))
+}
@@ -0,0 +1,405 @@
+use crate::ast::action::{
+    AuthBasedAction, ForwardAction, LogicBasedAction, RapidRecastAction, RapidRecastRbacObject,
+    ResponseBasedAction, ResponseBody,
+};
+use crate::ast::model::{ModelConstraint, ModelField, ModelValue};
+use crate::ast::protocol::{HttpRegex, HttpStatement, HttpValueMatch, RapidProtocolDefinition};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::format::{load_definition, load_definition_file, Format};
+use crate::json::JsonRRDL;
+use crate::rrdl::RrdlPrinter;
+use crate::test::{
+    bare_minimum_schema, schema_with_auth_actions, schema_with_cron, schema_with_forward,
+    schema_with_http_matchers, schema_with_model, schema_with_mqtt, schema_with_responses,
+};
+use crate::toml::TomlRRDL;
+use crate::xml::XmlRRDL;
+use crate::yaml::YamlRRDL;
+use crate::SaveRRDL;
+use std::collections::BTreeMap;
+
+fn saved(saver: impl SaveRRDL) -> String {
+    save(saver, &schema_with_auth_actions())
+}
+
+fn save(saver: impl SaveRRDL, definition: &RapidRecastDefinition) -> String {
+    String::from_utf8(saver.save_rrdl(definition).unwrap()).unwrap()
+}
+
+/// Save an invalid definition in every format, and check each one refuses to load it alike.
+fn assert_rejected(definition: &RapidRecastDefinition, message: &str) {
+    let cases = [
+        (Format::Rrdl, save(RrdlPrinter {}, definition)),
+        (Format::Json, save(JsonRRDL {}, definition)),
+        (Format::Yaml, save(YamlRRDL {}, definition)),
+        (Format::Toml, save(TomlRRDL {}, definition)),
+        (Format::Xml, save(XmlRRDL {}, definition)),
+    ];
+    for (format, input) in cases {
+        let error = load_definition(&input, None).unwrap_err();
+        // Formats that track where they are in the document name the field at fault first.
+        let prefixed = error.message.ends_with(&format!(": {}", message));
+        assert!(
+            error.message == message || prefixed,
+            "{}: {}\n{}",
+            format,
+            error.message,
+            input
+        );
+    }
+}
+
+fn with_statement(statement: RapidAstStatement<'static>) -> RapidRecastDefinition<'static> {
+    let mut definition = bare_minimum_schema();
+    definition.ast.push(statement);
+    definition
+}
+
+#[test]
+pub fn detects_every_saved_format() {
+    let cases = [
+        (Format::Rrdl, saved(RrdlPrinter {})),
+        (Format::Json, saved(JsonRRDL {})),
+        (Format::Yaml, saved(YamlRRDL {})),
+        (Format::Toml, saved(TomlRRDL {})),
+        (Format::Xml, saved(XmlRRDL {})),
+    ];
+    for (format, input) in cases {
+        assert_eq!(Format::detect(&input), format, "{}", input);
+        let (detected, definition) = load_definition(&input, None).unwrap();
+        assert_eq!(detected, format);
+        assert_eq!(definition, schema_with_auth_actions());
+    }
+}
+
+#[test]
+pub fn detection_skips_comments_and_whitespace() {
+    assert_eq!(
+        Format::detect("\u{feff}\n// comment\n/* block */ definition \"id\" {}"),
+        Format::Rrdl
+    );
+    assert_eq!(
+        Format::detect("  <?xml version=\"1.0\"?><definition/>"),
+        Format::Xml
+    );
+    assert_eq!(Format::detect("# comment\n\n\"id\" = \"x\""), Format::Toml);
+    assert_eq!(Format::detect("# comment\n[[ast]]"), Format::Toml);
+    assert_eq!(Format::detect("# comment\nid: x"), Format::Yaml);
+    assert_eq!(Format::detect("definitions: x"), Format::Yaml);
+}
+
+#[test]
+pub fn hint_overrides_detection() {
+    let input = saved(JsonRRDL {});
+    let (format, _) = load_definition(&input, Some(Format::Yaml)).unwrap();
+    assert_eq!(format, Format::Yaml);
+    let error = load_definition(&input, Some(Format::Xml)).unwrap_err();
+    assert!(error.span.is_some());
+}
+
+#[test]
+pub fn format_from_path() {
+    assert_eq!(Format::from_path("config/api.rrdl"), Some(Format::Rrdl));
+    assert_eq!(Format::from_path("api.JSON"), Some(Format::Json));
+    assert_eq!(Format::from_path("api.yml"), Some(Format::Yaml));
+    assert_eq!(Format::from_path("api.yaml"), Some(Format::Yaml));
+    assert_eq!(Format::from_path("api.toml"), Some(Format::Toml));
+    assert_eq!(Format::from_path("api.xml"), Some(Format::Xml));
+    assert_eq!(Format::from_path("api.txt"), None);
+    assert_eq!(Format::from_path("api"), None);
+}
+
+#[test]
+pub fn every_format_checks_path_templates() {
+    let definition = with_statement(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+            sequence: 0,
+            paths: vec!["/users/{id:bogus}".into()],
+            methods: vec![],
+            min_body_bytes: None,
+            max_body_bytes: None,
+            host: None,
+            content_type: None,
+            headers: vec![],
+            query: vec![],
+            actions: vec![],
+        }),
+    ));
+    assert_rejected(
+        &definition,
+        "Invalid path template '/users/{id:bogus}', unknown param type 'bogus', expected one of string, int, float, uuid",
+    );
+}
+
+#[test]
+pub fn every_format_checks_cron_schedules_and_timezones() {
+    let cron = |schedule: &'static str, timezone: &'static str| {
+        let mut definition = schema_with_cron();
+        match &mut definition.ast[0] {
+            RapidAstStatement::CronDefinition(cron) => {
+                cron.schedule = schedule.into();
+                cron.timezone = Some(timezone.into());
+            }
+            other => panic!("Unexpected statement {:?}", other),
+        }
+        definition
+    };
+    assert_rejected(
+        &cron("61 * * * *", "Europe/London"),
+        "Invalid minute '61' in cron schedule, expected 0 to 59",
+    );
+    assert_rejected(
+        &cron("@hourly", "Europe/Nowhere"),
+        "Unknown timezone 'Europe/Nowhere'",
+    );
+}
+
+#[test]
+pub fn every_format_checks_http_regexes() {
+    let mut definition = schema_with_http_matchers();
+    match &mut definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) => http.query[0].value = HttpValueMatch::Regex(HttpRegex::new("(true".into())),
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    assert_rejected(&definition, "Invalid regex '(true', unclosed group");
+}
+
+#[test]
+pub fn every_format_checks_response_actions() {
+    let respond = |status: u16, body: &'static str| {
+        let mut definition = schema_with_responses();
+        match &mut definition.ast[2] {
+            RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::HttpProtocolDefinition(http),
+            ) => {
+                http.actions = vec![RapidRecastAction::ResponseBasedAction(
+                    ResponseBasedAction::Respond {
+                        status,
+                        headers: vec![],
+                        body: Some(ResponseBody::Template(body.into())),
+                    },
+                )]
+            }
+            other => panic!("Unexpected statement {:?}", other),
+        }
+        definition
+    };
+    assert_rejected(
+        &respond(600, "ok"),
+        "Invalid response status 600, expected 100 to 599",
+    );
+    assert_rejected(
+        &respond(200, "{id"),
+        "Invalid template '{id', '{' is never closed, write '{{' for a literal brace",
+    );
+
+    let mut nested = schema_with_responses();
+    match &mut nested.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) => match &mut http.actions[0] {
+            RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+                if_true,
+                ..
+            }) => {
+                **if_true = RapidRecastAction::ResponseBasedAction(ResponseBasedAction::Redirect {
+                    status: 200,
+                    location: "/login".into(),
+                })
+            }
+            other => panic!("Unexpected action {:?}", other),
+        },
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    assert_rejected(
+        &nested,
+        "Invalid redirect status 200, expected one of 301, 302, 303, 307, 308",
+    );
+}
+
+#[test]
+pub fn every_format_checks_forward_actions() {
+    let forward = |change: fn(&mut ForwardAction)| {
+        let mut definition = schema_with_forward();
+        match &mut definition.ast[0] {
+            RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::HttpProtocolDefinition(http),
+            ) => match &mut http.actions[0] {
+                RapidRecastAction::ForwardAction(forward) => change(forward),
+                other => panic!("Unexpected action {:?}", other),
+            },
+            other => panic!("Unexpected statement {:?}", other),
+        }
+        definition
+    };
+    assert_rejected(
+        &forward(|forward| forward.upstreams[1] = "users-b:8080".into()),
+        "Invalid upstream 'users-b:8080', expected an http:// or https:// URL with a host",
+    );
+    assert_rejected(
+        &forward(|forward| forward.rewrite_path = Some("/v2/{id".into())),
+        "Invalid template '/v2/{id', '{' is never closed, write '{{' for a literal brace",
+    );
+    assert_rejected(
+        &forward(|forward| forward.retry.statuses.push(99)),
+        "Invalid retry status 99, expected 100 to 599",
+    );
+    assert_rejected(
+        &forward(|forward| {
+            forward
+                .add_headers
+                .insert("X-Forwarded-By".into(), "proxy".into());
+        }),
+        "Header 'x-forwarded-by' is added more than once",
+    );
+}
+
+#[test]
+pub fn every_format_lowercases_header_names() {
+    let mut definition = schema_with_forward();
+    match &mut definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) => match &mut http.actions[0] {
+            RapidRecastAction::ForwardAction(forward) => {
+                forward.remove_headers = vec!["Cookie".into()];
+                forward.add_headers =
+                    BTreeMap::from([("X-Forwarded-By".into(), "rapidrecast".into())]);
+            }
+            other => panic!("Unexpected action {:?}", other),
+        },
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    let cases = [
+        (Format::Rrdl, save(RrdlPrinter {}, &definition)),
+        (Format::Json, save(JsonRRDL {}, &definition)),
+        (Format::Yaml, save(YamlRRDL {}, &definition)),
+        (Format::Toml, save(TomlRRDL {}, &definition)),
+        (Format::Xml, save(XmlRRDL {}, &definition)),
+    ];
+    for (format, input) in cases {
+        let (_, loaded) = load_definition(&input, None).unwrap();
+        assert_eq!(loaded, schema_with_forward(), "{}: {}", format, input);
+    }
+}
+
+#[test]
+pub fn every_format_checks_mqtt_topic_filters() {
+    let mut definition = schema_with_mqtt();
+    match &mut definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::MqttProtocolDefinition(
+            mqtt,
+        )) => mqtt.handlers[0].topic_filter = "sensors/#/temperature".into(),
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    assert_rejected(
+        &definition,
+        "Invalid MQTT topic filter 'sensors/#/temperature', '#' must fill the last level",
+    );
+
+    let mut definition = schema_with_mqtt();
+    match &mut definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::MqttProtocolDefinition(
+            mqtt,
+        )) => match &mut mqtt.handlers[0].actions[0] {
+            RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+                policy,
+                ..
+            }) => policy[0].object = RapidRecastRbacObject::MqttTopic("a/#/b".into()),
+            other => panic!("Unexpected action {:?}", other),
+        },
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    assert_rejected(
+        &definition,
+        "Invalid MQTT topic filter 'a/#/b', '#' must fill the last level",
+    );
+}
+
+#[test]
+pub fn every_format_checks_model_fields() {
+    let model = |change: fn(&mut Vec<ModelField>)| {
+        let mut definition = schema_with_model();
+        match &mut definition.ast[0] {
+            RapidAstStatement::ModelDefinition(model) => change(&mut model.fields),
+            other => panic!("Unexpected statement {:?}", other),
+        }
+        definition
+    };
+    assert_rejected(
+        &model(|fields| fields[0].constraints = vec![ModelConstraint::Regex("[a-z".into())]),
+        "Invalid regex '[a-z', unclosed character class",
+    );
+    assert_rejected(
+        &model(|fields| {
+            fields[1].constraints = vec![ModelConstraint::Min(200.0), ModelConstraint::Max(150.0)]
+        }),
+        "Invalid field 'age', min 200 is greater than max 150",
+    );
+    assert_rejected(
+        &model(|fields| fields[1].default = Some(ModelValue::String("old".into()))),
+        "Invalid default for field 'age', expected integer",
+    );
+    assert_rejected(
+        &model(|fields| fields[1].default = Some(ModelValue::Number(1.5))),
+        "Invalid default for field 'age', expected integer",
+    );
+    assert_rejected(
+        &model(|fields| fields[7].default = Some(ModelValue::List(vec![ModelValue::Bool(true)]))),
+        "Invalid default for field 'tags', expected list<string>",
+    );
+    assert_rejected(
+        &model(|fields| {
+            fields[6].constraints = vec![ModelConstraint::OneOf(vec![
+                ModelValue::String("user".into()),
+                ModelValue::Number(1.0),
+            ])]
+        }),
+        "Invalid one_of value for field 'role', expected string",
+    );
+    assert_rejected(
+        &model(|fields| fields[1].constraints = vec![ModelConstraint::Regex("^[0-9]+$".into())]),
+        "Invalid field 'age', a regex needs a string field but it is integer",
+    );
+}
+
+#[test]
+pub fn every_format_checks_body_sizes() {
+    let mut definition = schema_with_http_matchers();
+    match &mut definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) => {
+            http.min_body_bytes = Some(100);
+            http.max_body_bytes = Some(10);
+        }
+        other => panic!("Unexpected statement {:?}", other),
+    }
+    assert_rejected(
+        &definition,
+        "The body size min must not be larger than the max",
+    );
+}
+
+#[test]
+pub fn checks_after_loading_point_into_the_file() {
+    let input = r#"{
+    "id": "id",
+    "language_version": "1.2.3",
+    "file_version": "4.5.6",
+    "ast": [{"CronDefinition": {"namespace": "billing", "name": "nightly",
+        "schedule": "0 0 * * *", "timezone": "Mars/Olympus"}}]
+}"#;
+    let error = load_definition_file("config/billing.json", input).unwrap_err();
+    assert_eq!(
+        error.render(input),
+        r#"error: Unknown timezone 'Mars/Olympus'
+ --> config/billing.json:6:47
+  |
+6 |         "schedule": "0 0 * * *", "timezone": "Mars/Olympus"}}]
+  |                                               ^^^^^^^^^^^^
+"#
+    );
+}
@@ -3,27 +3,30 @@
 mod test;
 
 use crate::ast::RapidRecastDefinition;
+use crate::error::RrdlError;
 use crate::{ParseRRDL, SaveRRDL};
-use std::io::Cursor;
+use std::io::Write;
 
 /// A parser for the RapidRecastDefinition Language in JSON
 pub struct JsonRRDL {}
 
-impl<'self_life, 'input_life> ParseRRDL<'self_life, 'input_life> for JsonRRDL {
-    fn parse_rrdl(
-        &'self_life self,
-        input: &'input_life str,
-    ) -> Result<RapidRecastDefinition<'input_life>, String> {
-        serde_json::from_str(input).map_err(|e| format!("Failed at le serde json lol: {}", e))
+impl ParseRRDL for JsonRRDL {
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError> {
+        let definition: RapidRecastDefinition = serde_json::from_str(input)?;
+        definition.validate().map_err(|e| e.located_in(input))?;
+        Ok(definition)
     }
 }
 
-impl SaveRRDL<Cursor<Vec<u8>>> for JsonRRDL {
-    fn save_rrdl<'param>(
-        &'param self,
-        definition: &'param RapidRecastDefinition,
-    ) -> Cursor<Vec<u8>> {
-        let res = serde_json::to_vec(definition).unwrap();
-        Cursor::new(res)
+impl SaveRRDL for JsonRRDL {
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        writer: W,
+    ) -> Result<(), RrdlError> {
+        serde_json::to_writer(writer, definition).map_err(RrdlError::from)
     }
 }
@@ -1,7 +1,18 @@
-use crate::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastHttpMethod};
-use crate::ast::RapidAstStatement::ProtocolDefinition;
-use crate::ast::{RapidRecastDefinition, Version};
+use crate::ast::action::{AuthBasedAction, RapidRecastAction};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::{ModelField, ModelFieldType, RapidModelDefinition, ScalarType};
+use crate::ast::protocol::{
+    AmqpPublishHandler, AmqpStatement, GrpcStatement, HttpStatement, KafkaStatement, MqttEvent,
+    MqttHandler, MqttStatement, RapidProtocolDefinition, RapidRecastHttpMethod,
+    WebSocketMessageHandler, WebSocketMessageKind, WebSocketStatement,
+};
+use crate::ast::topic::{RapidTopicDefinition, TopicRetention};
+use crate::ast::RapidAstStatement::{
+    CronDefinition, ModelDefinition, ProtocolDefinition, TopicDefinition,
+};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition, Version};
 use crate::json::JsonRRDL;
+use crate::test::schema_with_auth_actions;
 use crate::ParseRRDL;
 use std::borrow::Cow;
 
@@ -22,7 +33,7 @@ pub fn bare_minimum() {
     assert_eq!(
         res,
         RapidRecastDefinition {
-            id: Cow::Owned("unique-schema-id-123".to_string()),
+            id: Cow::Borrowed("unique-schema-id-123"),
             language_version: Version {
                 major: 1,
                 minor: 2,
@@ -68,7 +79,7 @@ pub fn protocol_definition() {
     assert_eq!(
         res,
         RapidRecastDefinition {
-            id: Cow::Owned("unique-schema-id-123".to_string()),
+            id: Cow::Borrowed("unique-schema-id-123"),
             language_version: Version {
                 major: 1,
                 minor: 2,
@@ -86,9 +97,283 @@ pub fn protocol_definition() {
                     sequence: 0,
                     paths: vec![Cow::Borrowed("/user")],
                     methods: vec![RapidRecastHttpMethod::POST],
+                    min_body_bytes: None,
+                    max_body_bytes: None,
+                    host: None,
+                    content_type: None,
+                    headers: vec![],
+                    query: vec![],
                     actions: vec![],
                 })
             ),],
         }
     );
 }
+
+#[test]
+pub fn parse_from_reader() {
+    let input = serde_json::to_vec(&schema_with_auth_actions()).unwrap();
+    let res: RapidRecastDefinition<'static> = JsonRRDL {}.parse_reader(input.as_slice()).unwrap();
+    assert_eq!(res, schema_with_auth_actions());
+}
+
+#[test]
+pub fn reader_failures_are_reported() {
+    let error = JsonRRDL {}.parse_reader(&[0xff, 0xfe][..]).unwrap_err();
+    assert!(error.message.contains("valid UTF-8"), "{}", error);
+}
+
+#[cfg(feature = "async")]
+#[test]
+pub fn parse_from_async_reader() {
+    let input = serde_json::to_vec(&schema_with_auth_actions()).unwrap();
+    let reader = futures_util::io::Cursor::new(input);
+    let res = futures_executor::block_on(JsonRRDL {}.parse_async_reader(reader)).unwrap();
+    assert_eq!(res, schema_with_auth_actions());
+}
+
+#[test]
+pub fn strings_are_borrowed_from_the_input() {
+    let input = serde_json::to_string(&schema_with_auth_actions()).unwrap();
+    let res = JsonRRDL {}.parse_rrdl(&input).unwrap();
+    assert!(matches!(res.id, Cow::Borrowed(_)));
+    let http = match &res.ast[..] {
+        [ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(http))] => http,
+        other => panic!("Unexpected statements {:?}", other),
+    };
+    assert!(matches!(http.paths[0], Cow::Borrowed("/create-user")));
+    match &http.actions[..] {
+        [RapidRecastAction::AuthBasedAction(AuthBasedAction::CreateUser { subject, password }), RapidRecastAction::AuthBasedAction(AuthBasedAction::AddMetadataToUser {
+            metadata, ..
+        }), _] => {
+            assert!(matches!(subject.username, Cow::Borrowed(_)));
+            assert!(matches!(password, Some(Cow::Borrowed(_))));
+            assert!(metadata
+                .iter()
+                .all(|entry| matches!(entry, (Cow::Borrowed(_), Cow::Borrowed(_)))));
+        }
+        other => panic!("Unexpected actions {:?}", other),
+    }
+}
+
+#[test]
+pub fn escaped_strings_are_owned() {
+    let input = r#"
+    {
+        "id": "id-with-\"quotes\"",
+        "language_version": "1.2.3",
+        "file_version": "4.5.6",
+        "name": "plain",
+        "ast": []
+    }
+    "#;
+    let res = JsonRRDL {}.parse_rrdl(input).unwrap();
+    assert_eq!(res.id, "id-with-\"quotes\"");
+    assert!(matches!(res.id, Cow::Owned(_)));
+    assert!(matches!(res.name, Some(Cow::Borrowed("plain"))));
+    assert_eq!(res.description, None);
+}
+
+#[test]
+pub fn path_templates_are_checked() {
+    let input = r#"
+    {
+        "id": "unique-schema-id-123",
+        "language_version": "1.2.3",
+        "file_version": "4.5.6",
+        "ast": [
+            {
+                "ProtocolDefinition": {
+                    "HttpProtocolDefinition": {
+                        "sequence": 0,
+                        "paths": ["/users/{id:bogus}"],
+                        "methods": ["GET"],
+                        "actions": []
+                    }
+                }
+            }
+        ]
+    }
+    "#;
+    let error = JsonRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid path template '/users/{id:bogus}', unknown param type 'bogus', expected one of string, int, float, uuid"
+    );
+}
+
+/// Parse a definition holding only the given statement.
+fn statement(statement: &str) -> RapidAstStatement<'static> {
+    let input = format!(
+        r#"{{"id": "id", "language_version": "1.2.3", "file_version": "4.5.6", "ast": [{}]}}"#,
+        statement
+    );
+    let mut definition = JsonRRDL {}.parse_rrdl(&input).unwrap().into_owned();
+    definition.ast.pop().unwrap()
+}
+
+/// Parse a definition holding only the given protocol definition.
+fn protocol(protocol: &str) -> RapidProtocolDefinition<'static> {
+    match statement(&format!(r#"{{"ProtocolDefinition": {}}}"#, protocol)) {
+        ProtocolDefinition(protocol) => protocol,
+        other => panic!("Unexpected statement {:?}", other),
+    }
+}
+
+#[test]
+pub fn websocket_lists_may_be_left_out() {
+    assert_eq!(
+        protocol(
+            r#"{"WebSocketProtocolDefinition": {"sequence": 0, "path": "/chat",
+                "on_message": [{"kind": "Text"}]}}"#
+        ),
+        RapidProtocolDefinition::WebSocketProtocolDefinition(WebSocketStatement {
+            sequence: 0,
+            path: Cow::Borrowed("/chat"),
+            subprotocols: vec![],
+            on_connect: vec![],
+            on_message: vec![WebSocketMessageHandler {
+                kind: WebSocketMessageKind::Text,
+                condition: None,
+                actions: vec![],
+            }],
+            on_close: vec![],
+        })
+    );
+}
+
+#[test]
+pub fn kafka_lists_may_be_left_out() {
+    assert_eq!(
+        protocol(r#"{"KafkaProtocolDefinition": {"sequence": 1}}"#),
+        RapidProtocolDefinition::KafkaProtocolDefinition(KafkaStatement {
+            sequence: 1,
+            operations: vec![],
+            topics: vec![],
+            consumer_groups: vec![],
+            actions: vec![],
+        })
+    );
+}
+
+#[test]
+pub fn rabbitmq_lists_may_be_left_out() {
+    assert_eq!(
+        protocol(
+            r#"{"RabbitMQProtocolDefinition": {"sequence": 3,
+                "on_publish": [{"exchange": "orders"}]}}"#
+        ),
+        RapidProtocolDefinition::RabbitMQProtocolDefinition(AmqpStatement {
+            sequence: 3,
+            exchanges: vec![],
+            queues: vec![],
+            bindings: vec![],
+            on_publish: vec![AmqpPublishHandler {
+                exchange: Cow::Borrowed("orders"),
+                routing_key: None,
+                actions: vec![],
+            }],
+        })
+    );
+}
+
+#[test]
+pub fn grpc_lists_may_be_left_out() {
+    assert_eq!(
+        protocol(
+            r#"{"GrpcProtocolDefinition": {"sequence": 4, "service": "acme.users.v1.UserService"}}"#
+        ),
+        RapidProtocolDefinition::GrpcProtocolDefinition(GrpcStatement {
+            sequence: 4,
+            service: Cow::Borrowed("acme.users.v1.UserService"),
+            methods: vec![],
+            streaming: None,
+            metadata: vec![],
+            actions: vec![],
+        })
+    );
+}
+
+#[test]
+pub fn mqtt_lists_may_be_left_out() {
+    assert_eq!(
+        protocol(r#"{"MqttProtocolDefinition": {"sequence": 5}}"#),
+        RapidProtocolDefinition::MqttProtocolDefinition(MqttStatement {
+            sequence: 5,
+            handlers: vec![],
+        })
+    );
+    assert_eq!(
+        protocol(
+            r#"{"MqttProtocolDefinition": {"sequence": 5,
+                "handlers": [{"event": "Subscribe", "topic_filter": "sensors/#"}]}}"#
+        ),
+        RapidProtocolDefinition::MqttProtocolDefinition(MqttStatement {
+            sequence: 5,
+            handlers: vec![MqttHandler {
+                event: MqttEvent::Subscribe,
+                topic_filter: Cow::Borrowed("sensors/#"),
+                qos: None,
+                retain: None,
+                actions: vec![],
+            }],
+        })
+    );
+}
+
+#[test]
+pub fn cron_defaults_may_be_left_out() {
+    assert_eq!(
+        statement(
+            r#"{"CronDefinition": {"namespace": "billing", "name": "nightly",
+                "schedule": "0 0 * * *"}}"#
+        ),
+        CronDefinition(RapidCronDefinition {
+            namespace: Cow::Borrowed("billing"),
+            name: Cow::Borrowed("nightly"),
+            schedule: Cow::Borrowed("0 0 * * *"),
+            timezone: None,
+            jitter_ms: 0,
+            overlap: CronOverlapPolicy::Allow,
+            actions: vec![],
+        })
+    );
+}
+
+#[test]
+pub fn topic_defaults_may_be_left_out() {
+    assert_eq!(
+        statement(r#"{"TopicDefinition": {"namespace": "orders", "name": "created"}}"#),
+        TopicDefinition(RapidTopicDefinition {
+            namespace: Cow::Borrowed("orders"),
+            name: Cow::Borrowed("created"),
+            partitions: None,
+            retention: TopicRetention::default(),
+            compacted: false,
+            key_schema: None,
+            value_schema: None,
+            acl: vec![],
+        })
+    );
+}
+
+#[test]
+pub fn model_field_defaults_may_be_left_out() {
+    assert_eq!(
+        statement(
+            r#"{"ModelDefinition": {"namespace": "users", "name": "User",
+                "fields": [{"name": "id", "field_type": {"Scalar": "String"}}]}}"#
+        ),
+        ModelDefinition(RapidModelDefinition {
+            namespace: Cow::Borrowed("users"),
+            name: Cow::Borrowed("User"),
+            fields: vec![ModelField {
+                name: Cow::Borrowed("id"),
+                field_type: ModelFieldType::Scalar(ScalarType::String),
+                required: true,
+                default: None,
+                constraints: vec![],
+            }],
+        })
+    );
+}
@@ -4,30 +4,81 @@
 
 #![deny(missing_docs)]
 #![deny(warnings)]
+// Diagnostics carry their full context, and chumsky's errors are returned unboxed by design.
+#![allow(clippy::result_large_err)]
 
 use crate::ast::RapidRecastDefinition;
-use std::io::Read;
+use crate::error::RrdlError;
+#[cfg(feature = "async")]
+use futures_util::io::{AsyncRead, AsyncReadExt};
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::io::{Read, Write};
 
 pub mod ast;
+pub mod error;
+pub mod format;
 pub mod json;
 pub mod rrdl;
 #[cfg(test)]
 mod test;
+pub mod toml;
+pub mod xml;
+pub mod yaml;
 
 /// The trait defining how a parser behaves.
 /// You can also create your own implementations.
-pub trait ParseRRDL<'self_life, 'input_life> {
-    /// Given a string, return the AST.
-    ///
-    /// TODO use a sync and async reader?
-    fn parse_rrdl(
-        &'self_life self,
-        input: &'input_life str,
-    ) -> Result<RapidRecastDefinition<'input_life>, String>;
+pub trait ParseRRDL {
+    /// Given a string, return the AST borrowing from it.
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError>;
+
+    /// Read the whole input from a file, stdin or a stream, and return the AST.
+    /// The AST owns its data, as the buffer it was parsed from is dropped.
+    fn parse_reader<R: Read>(
+        &self,
+        mut reader: R,
+    ) -> Result<RapidRecastDefinition<'static>, RrdlError> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        self.parse_rrdl(&input)
+            .map(RapidRecastDefinition::into_owned)
+    }
+
+    /// Read the whole input from an asynchronous stream, and return the AST.
+    /// The AST owns its data, as the buffer it was parsed from is dropped.
+    #[cfg(feature = "async")]
+    fn parse_async_reader<R: AsyncRead + Unpin + Send>(
+        &self,
+        mut reader: R,
+    ) -> impl Future<Output = Result<RapidRecastDefinition<'static>, RrdlError>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut input = String::new();
+            reader.read_to_string(&mut input).await?;
+            self.parse_rrdl(&input)
+                .map(RapidRecastDefinition::into_owned)
+        }
+    }
 }
 
 /// Save a provided RapidRecast Definition Language AST into a specified output.
-pub trait SaveRRDL<READ: Read> {
-    /// Given a definition, save it to an output
-    fn save_rrdl<'param>(&'param self, definition: &'param RapidRecastDefinition) -> READ;
+pub trait SaveRRDL {
+    /// Given a definition, write it to an output such as a file or a socket.
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        writer: W,
+    ) -> Result<(), RrdlError>;
+
+    /// Given a definition, save it into an in-memory buffer.
+    fn save_rrdl(&self, definition: &RapidRecastDefinition) -> Result<Vec<u8>, RrdlError> {
+        let mut buffer = Vec::new();
+        self.save_to(definition, &mut buffer)?;
+        Ok(buffer)
+    }
 }
@@ -0,0 +1,207 @@
+//! Lexer for the RapidRecast Definition Language.
+
+use logos::Logos;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// A byte range into the lexed input.
+pub type Span = Range<usize>;
+
+/// The Lexer Tokens available for the RapidRecast Definition Language.
+///
+/// Whitespace, line comments (`// ...`) and block comments (`/* ... */`) are skipped.
+#[derive(Logos, PartialEq, Eq, Hash, Debug, Clone)]
+#[logos(skip r"[ \t\r\n\f]+")]
+#[logos(skip r"//[^\n]*")]
+#[logos(skip r"/\*[^*]*\*+([^/*][^*]*\*+)*/")]
+pub enum LexerToken<'a> {
+    /// The `definition` keyword, which opens the definition header
+    #[token("definition")]
+    Definition,
+    /// The `model` keyword
+    #[token("model")]
+    Model,
+    /// The `protocol` keyword
+    #[token("protocol")]
+    Protocol,
+    /// The `topic` keyword
+    #[token("topic")]
+    Topic,
+    /// The `cron` keyword
+    #[token("cron")]
+    Cron,
+    /// The `if` keyword
+    #[token("if")]
+    If,
+    /// The `else` keyword
+    #[token("else")]
+    Else,
+
+    /// An identifier, such as a parameter name or a contextual keyword like `GET`
+    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*")]
+    Identifier(&'a str),
+    /// A string literal; the value is the raw content between the quotes, escapes included
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| { let s = lex.slice(); &s[1..s.len() - 1] })]
+    String(&'a str),
+    /// A number literal, kept as written so that tokens stay hashable
+    #[regex(r"-?[0-9]+(\.[0-9]+)?([eE][+-]?[0-9]+)?")]
+    Number(&'a str),
+    /// A semantic version literal such as `1.2.3`
+    #[regex(r"[0-9]+\.[0-9]+\.[0-9]+")]
+    Version(&'a str),
+
+    /// `==`
+    #[token("==")]
+    Equals,
+    /// `!=`
+    #[token("!=")]
+    NotEquals,
+    /// `>=`
+    #[token(">=")]
+    GreaterThanOrEqual,
+    /// `<=`
+    #[token("<=")]
+    LessThanOrEqual,
+    /// `>`
+    #[token(">")]
+    GreaterThan,
+    /// `<`
+    #[token("<")]
+    LessThan,
+    /// `&&`
+    #[token("&&")]
+    And,
+    /// `||`
+    #[token("||")]
+    Or,
+    /// `!`
+    #[token("!")]
+    Not,
+    /// `=`
+    #[token("=")]
+    Assign,
+
+    /// `{`
+    #[token("{")]
+    LeftBrace,
+    /// `}`
+    #[token("}")]
+    RightBrace,
+    /// `(`
+    #[token("(")]
+    LeftParen,
+    /// `)`
+    #[token(")")]
+    RightParen,
+    /// `[`
+    #[token("[")]
+    LeftBracket,
+    /// `]`
+    #[token("]")]
+    RightBracket,
+    /// `,`
+    #[token(",")]
+    Comma,
+    /// `;`
+    #[token(";")]
+    Semicolon,
+    /// `:`
+    #[token(":")]
+    Colon,
+}
+
+impl Display for LexerToken<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexerToken::Definition => write!(f, "definition"),
+            LexerToken::Model => write!(f, "model"),
+            LexerToken::Protocol => write!(f, "protocol"),
+            LexerToken::Topic => write!(f, "topic"),
+            LexerToken::Cron => write!(f, "cron"),
+            LexerToken::If => write!(f, "if"),
+            LexerToken::Else => write!(f, "else"),
+            LexerToken::Identifier(s) => write!(f, "{}", s),
+            LexerToken::String(s) => write!(f, "\"{}\"", s),
+            LexerToken::Number(s) => write!(f, "{}", s),
+            LexerToken::Version(s) => write!(f, "{}", s),
+            LexerToken::Equals => write!(f, "=="),
+            LexerToken::NotEquals => write!(f, "!="),
+            LexerToken::GreaterThanOrEqual => write!(f, ">="),
+            LexerToken::LessThanOrEqual => write!(f, "<="),
+            LexerToken::GreaterThan => write!(f, ">"),
+            LexerToken::LessThan => write!(f, "<"),
+            LexerToken::And => write!(f, "&&"),
+            LexerToken::Or => write!(f, "||"),
+            LexerToken::Not => write!(f, "!"),
+            LexerToken::Assign => write!(f, "="),
+            LexerToken::LeftBrace => write!(f, "{{"),
+            LexerToken::RightBrace => write!(f, "}}"),
+            LexerToken::LeftParen => write!(f, "("),
+            LexerToken::RightParen => write!(f, ")"),
+            LexerToken::LeftBracket => write!(f, "["),
+            LexerToken::RightBracket => write!(f, "]"),
+            LexerToken::Comma => write!(f, ","),
+            LexerToken::Semicolon => write!(f, ";"),
+            LexerToken::Colon => write!(f, ":"),
+        }
+    }
+}
+
+/// The input could not be split into tokens.
+#[derive(PartialEq, Debug, Clone)]
+pub struct LexError {
+    /// Where in the input the unrecognised text is
+    pub span: Span,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unrecognised input at {}..{}",
+            self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Lex RapidRecast Definition Language into a list of LexerTokens and their spans.
+pub fn lex(input: &str) -> Result<Vec<(LexerToken<'_>, Span)>, LexError> {
+    let mut tokens = Vec::new();
+    for (token, span) in LexerToken::lexer(input).spanned() {
+        match token {
+            Ok(token) => tokens.push((token, span)),
+            Err(()) => return Err(LexError { span }),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Resolve the escape sequences in the raw content of a [`LexerToken::String`].
+///
+/// Strings without escapes are borrowed from the input.
+/// Returns `None` if the string contains an unknown escape sequence.
+pub fn unescape(raw: &str) -> Option<Cow<'_, str>> {
+    if !raw.contains('\\') {
+        return Some(Cow::Borrowed(raw));
+    }
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '"' => unescaped.push('"'),
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            't' => unescaped.push('\t'),
+            _ => return None,
+        }
+    }
+    Some(Cow::Owned(unescaped))
+}
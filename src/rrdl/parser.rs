@@ -0,0 +1,1781 @@
+//! Parser turning RapidRecast Definition Language tokens into the AST.
+//!
+//! A definition is a header followed by any number of statements:
+//!
+//! ```text
+//! definition "unique-schema-id-123" {
+//!     language_version 1.2.3;
+//!     file_version 4.5.6;
+//!     name "some name";
+//!     description "some description";
+//! }
+//!
+//! protocol http 0 {
+//!     paths "/create-user", "/users/{id:int}/posts/{slug}", "/static/{*rest}";
+//!     methods PUT, POST;
+//!     host = "api.example.com";
+//!     content_type prefix "application/json";
+//!     header "x-api-version" regex "^2\\.[0-9]+$";
+//!     header "authorization";
+//!     query "dry_run" = "true";
+//!     body_size max 1MiB;
+//!     actions {
+//!         create_user user("some-namespace", "some-username") password "some-password";
+//!         add_metadata user("some-namespace", "some-username") {
+//!             "key1" = "value1";
+//!         }
+//!         grant user("some-namespace", "some-username") {
+//!             admin create topic new "some-namespace";
+//!         }
+//!         if method == "POST" {
+//!             ...
+//!         } else {
+//!             ...
+//!         }
+//!     }
+//! }
+//!
+//! protocol http 1 {
+//!     paths "/users/{id:int}";
+//!     actions {
+//!         if id > 1000 {
+//!             redirect 308 "/archive/users/{id}";
+//!         } else {
+//!             respond 200 {
+//!                 header "content-type" = "application/json";
+//!                 body template "{{\"id\": {id}}}";
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! protocol http 2 {
+//!     paths "/static/{*rest}";
+//!     actions {
+//!         serve_file "/var/www" "{rest}" content_type "text/css";
+//!     }
+//! }
+//!
+//! protocol http 3 {
+//!     paths "/api/users/{id}";
+//!     actions {
+//!         forward {
+//!             upstreams "http://users-a:8080", "http://users-b:8080";
+//!             load_balancing least_connections;
+//!             rewrite_path "/v2/users/{id}";
+//!             timeout 5s;
+//!             remove_header "cookie";
+//!             add_header "x-forwarded-by" = "rapidrecast";
+//!             retry 2 backoff 100ms on 502, 503;
+//!         }
+//!     }
+//! }
+//!
+//! protocol websocket 0 {
+//!     path "/chat";
+//!     subprotocols "chat.v2", "chat.v1";
+//!     on connect {
+//!         ...
+//!     }
+//!     on text if message == "ping" {
+//!         ...
+//!     }
+//!     on binary {
+//!         ...
+//!     }
+//!     on close {
+//!         ...
+//!     }
+//! }
+//!
+//! protocol kafka 2 {
+//!     operations produce, fetch;
+//!     topics "some-namespace" "orders-*", new "some-namespace";
+//!     consumer_groups "billing-*";
+//!     actions {
+//!         ...
+//!     }
+//! }
+//!
+//! protocol rabbitmq 3 {
+//!     exchange "orders" topic durable;
+//!     queue "billing" durable;
+//!     bind "billing" to "orders" key "order.*.created";
+//!     on publish "orders" key "order.#" {
+//!         ...
+//!     }
+//! }
+//!
+//! protocol grpc 4 {
+//!     service "acme.users.v1.UserService";
+//!     methods "GetUser", "List*";
+//!     streaming server;
+//!     metadata "x-tenant" = "acme";
+//!     metadata "authorization";
+//!     actions {
+//!         ...
+//!     }
+//! }
+//!
+//! protocol mqtt 5 {
+//!     on publish "sensors/+/temperature" qos 1 retain true {
+//!         ...
+//!     }
+//!     on subscribe "sensors/#" qos 2 {
+//!         ...
+//!     }
+//! }
+//!
+//! model "some-namespace" "User" {
+//!     id: string regex "^[a-z0-9-]+$";
+//!     age: optional integer min 0 max 150;
+//!     role: string = "user" one_of "user", "admin";
+//!     tags: list<string> = [];
+//!     labels: map<string>;
+//!     address: optional model "some-namespace" "Address";
+//! }
+//!
+//! topic "some-namespace" "users" {
+//!     partitions 12;
+//!     retention time 7d size 10GiB;
+//!     compacted;
+//!     key model "some-namespace" "UserKey";
+//!     value model "some-namespace" "User";
+//!     acl {
+//!         anon read;
+//!         user("some-namespace", "some-username") write;
+//!     }
+//! }
+//!
+//! cron "some-namespace" "nightly-cleanup" {
+//!     schedule "30 2 * * mon-fri";
+//!     timezone "Europe/London";
+//!     jitter 5m;
+//!     overlap skip;
+//!     actions {
+//!         ...
+//!     }
+//! }
+//! ```
+//!
+//! The clauses of a block may be written in any order. A clause that takes a single value,
+//! such as `timezone` or `timeout`, may appear at most once; repeatable clauses such as
+//! `header` or `on message` may appear any number of times.
+
+use crate::ast::action::{
+    add_header, header_name, AuthBasedAction, ClientObject, ConditionStatement, ForwardAction,
+    LoadBalancingStrategy, LogicBasedAction, NamespaceObject, RapidRecastAction,
+    RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy, RapidRecastRbacSubject,
+    RecastValue, ResponseBasedAction, ResponseBody, ResponseHeader, RetryPolicy, TopicObject,
+    UserIdentifier,
+};
+use crate::ast::cron::{parse_time_zone, CronOverlapPolicy, CronSchedule, RapidCronDefinition};
+use crate::ast::model::{
+    ModelConstraint, ModelField, ModelFieldType, ModelReference, ModelValue, RapidModelDefinition,
+    ScalarType,
+};
+use crate::ast::protocol::{
+    check_body_size, check_mqtt_topic_filter, AmqpBinding, AmqpExchange, AmqpExchangeType,
+    AmqpPublishHandler, AmqpQueue, AmqpStatement, GrpcMetadataMatch, GrpcStatement,
+    GrpcStreamingKind, HttpFieldMatch, HttpRegex, HttpStatement, HttpValueMatch, KafkaOperation,
+    KafkaStatement, MqttEvent, MqttHandler, MqttQos, MqttStatement, PathTemplate,
+    RapidProtocolDefinition, RapidRecastHttpMethod, RapidRecastProtocolType,
+    WebSocketMessageHandler, WebSocketMessageKind, WebSocketStatement,
+};
+use crate::ast::topic::{RapidTopicDefinition, TopicAcl, TopicRetention};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition, Version};
+use crate::error::{RrdlError, Severity, SourceSpan};
+use crate::rrdl::lexer::{unescape, LexError, LexerToken, Span};
+use crate::ParseRRDL;
+use chumsky::error::{Cheap, SimpleReason};
+use chumsky::prelude::*;
+use chumsky::Stream;
+use logos::Logos;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// A parser for the RapidRecast Definition Language
+pub struct RrdlParser {}
+
+impl RrdlParser {
+    /// Parse a definition, recovering from errors at statement boundaries.
+    ///
+    /// Returns every diagnostic found, sorted by position, along with the definition
+    /// built from the statements that did parse. The definition is `None` only when
+    /// the header itself is broken.
+    pub fn parse_with_diagnostics<'input>(
+        &self,
+        input: &'input str,
+    ) -> (Option<RapidRecastDefinition<'input>>, Vec<RrdlError>) {
+        // Most definitions are well formed, so the first attempt lexes straight into the
+        // parser and uses errors that cost nothing to build. Only a definition that fails it
+        // is lexed and parsed again to describe its errors.
+        let eoi = input.len()..input.len();
+        let mut lexed_cleanly = true;
+        let tokens = LexerToken::lexer(input)
+            .spanned()
+            .filter_map(|(token, span)| match token {
+                Ok(token) => Some((token, span)),
+                Err(()) => {
+                    lexed_cleanly = false;
+                    None
+                }
+            });
+        let (parsed, errors) =
+            definition::<Cheap<_, _>>().parse_recovery(Stream::from_iter(eoi.clone(), tokens));
+        if lexed_cleanly && errors.is_empty() {
+            return (parsed.flatten(), Vec::new());
+        }
+
+        let mut tokens = Vec::new();
+        let mut diagnostics = Vec::new();
+        for (token, span) in LexerToken::lexer(input).spanned() {
+            match token {
+                Ok(token) => tokens.push((token, span)),
+                Err(()) => diagnostics.push(lex_error(input, LexError { span })),
+            }
+        }
+        let (parsed, errors) =
+            definition::<Simple<_>>().parse_recovery(Stream::from_iter(eoi, tokens.into_iter()));
+        diagnostics.extend(errors.into_iter().map(|e| parse_error(input, e)));
+        diagnostics.sort_by_key(|d| d.span.map(|s| (s.start.line, s.start.column)));
+        (parsed.flatten(), diagnostics)
+    }
+}
+
+impl ParseRRDL for RrdlParser {
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError> {
+        let (definition, diagnostics) = self.parse_with_diagnostics(input);
+        match diagnostics
+            .into_iter()
+            .find(|d| d.severity == Severity::Error)
+        {
+            Some(error) => Err(error),
+            None => {
+                let definition =
+                    definition.ok_or_else(|| RrdlError::new("Missing definition header"))?;
+                definition.validate().map_err(|e| e.located_in(input))?;
+                Ok(definition)
+            }
+        }
+    }
+}
+
+fn lex_error(input: &str, error: LexError) -> RrdlError {
+    RrdlError::new("Unrecognised input")
+        .with_found(&input[error.span.clone()])
+        .with_span(SourceSpan::from_byte_range(input, error.span))
+}
+
+fn parse_error(input: &str, error: Simple<LexerToken>) -> RrdlError {
+    let span = SourceSpan::from_byte_range(input, error.span());
+    let found = match error.found() {
+        Some(token) => format!("'{}'", token),
+        None => "end of input".to_string(),
+    };
+    if let SimpleReason::Custom(message) = error.reason() {
+        return RrdlError::new(message.clone())
+            .with_span(span)
+            .with_found(found);
+    }
+    let mut expected = error
+        .expected()
+        .map(|token| match token {
+            Some(token) => format!("'{}'", token),
+            None => "end of input".to_string(),
+        })
+        .collect::<Vec<_>>();
+    expected.sort();
+    RrdlError::new(format!("Unexpected {}", found))
+        .with_span(span)
+        .with_expected(expected)
+        .with_found(found)
+}
+
+/// The errors the parsers report. A definition is parsed with `Cheap` errors first, which
+/// record no expected tokens and so allocate nothing for the many alternatives that fail
+/// along the way; only when that parse fails is it repeated with `Simple` errors to
+/// describe what went wrong.
+trait ParseError<'a>: chumsky::Error<LexerToken<'a>, Span = Span, Label = &'static str> + 'a {
+    /// An error with a message of its own rather than an unexpected token.
+    fn custom(span: Span, message: impl ToString) -> Self;
+}
+
+impl<'a> ParseError<'a> for Simple<LexerToken<'a>> {
+    fn custom(span: Span, message: impl ToString) -> Self {
+        Simple::custom(span, message)
+    }
+}
+
+impl<'a> ParseError<'a> for Cheap<LexerToken<'a>, Span> {
+    fn custom(span: Span, _message: impl ToString) -> Self {
+        Cheap::expected_input_found(span, [], None)
+    }
+}
+
+/// A contextual keyword; these are lexed as identifiers so they remain usable as parameter names.
+fn keyword<'a, E: ParseError<'a>>(
+    word: &'static str,
+) -> impl Parser<LexerToken<'a>, (), Error = E> + Clone {
+    just(LexerToken::Identifier(word)).ignored()
+}
+
+fn semicolon<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, (), Error = E> + Clone {
+    just(LexerToken::Semicolon).ignored()
+}
+
+fn string<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, Cow<'a, str>, Error = E> + Clone {
+    select! { LexerToken::String(s) => s }.try_map(|s, span: Span| {
+        unescape(s).ok_or_else(|| E::custom(span, "Invalid escape sequence in string"))
+    })
+}
+
+fn number<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, f64, Error = E> + Clone {
+    select! { LexerToken::Number(n) => n }.try_map(|n, span: Span| {
+        n.parse::<f64>()
+            .map_err(|_| E::custom(span, format!("Invalid number {}", n)))
+    })
+}
+
+fn sequence<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, u8, Error = E> + Clone {
+    select! { LexerToken::Number(n) => n }.try_map(|n, span: Span| {
+        n.parse::<u8>().map_err(|_| {
+            E::custom(
+                span,
+                format!("Expected a sequence from 0 to 255, found {}", n),
+            )
+        })
+    })
+}
+
+fn version<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, Version, Error = E> + Clone {
+    select! { LexerToken::Version(v) => v }
+        .try_map(|v, span: Span| v.parse::<Version>().map_err(|e| E::custom(span, e)))
+}
+
+fn braced<'a, O, E: ParseError<'a>>(
+    inner: impl Parser<LexerToken<'a>, O, Error = E> + Clone,
+) -> impl Parser<LexerToken<'a>, O, Error = E> + Clone {
+    inner.delimited_by(just(LexerToken::LeftBrace), just(LexerToken::RightBrace))
+}
+
+/// The clauses of a braced block, in whatever order they are written, each with its span.
+fn clauses<'a, C, E: ParseError<'a>>(
+    clause: impl Parser<LexerToken<'a>, C, Error = E> + Clone,
+) -> impl Parser<LexerToken<'a>, Vec<(C, Span)>, Error = E> + Clone {
+    braced(
+        clause
+            .map_with_span(|clause, span| (clause, span))
+            .repeated(),
+    )
+}
+
+/// Fill in a clause that may appear at most once in its block.
+fn once<'a, T, E: ParseError<'a>>(
+    slot: &mut Option<T>,
+    name: &str,
+    value: T,
+    span: Span,
+) -> Result<(), E> {
+    match slot {
+        Some(_) => Err(E::custom(span, format!("Duplicate '{}' clause", name))),
+        None => {
+            *slot = Some(value);
+            Ok(())
+        }
+    }
+}
+
+/// A clause the block cannot do without, reported at the closing brace of the block's span.
+fn required<'a, T, E: ParseError<'a>>(slot: Option<T>, name: &str, span: Span) -> Result<T, E> {
+    let end = span.end.saturating_sub(1)..span.end;
+    slot.ok_or_else(|| E::custom(end, format!("Missing '{}' clause", name)))
+}
+
+/// One of several tokens, each standing for a value.
+/// Unlike `select!`, a mismatch lists every one of the tokens as expected. Unlike `one_of`,
+/// which clones its list on every attempt, nothing is allocated unless the match fails.
+fn tokens<'a, T: Clone + 'a, E: ParseError<'a>>(
+    choices: impl IntoIterator<Item = (LexerToken<'a>, T)>,
+) -> impl Parser<LexerToken<'a>, T, Error = E> + Clone {
+    let choices = choices.into_iter().collect::<Vec<_>>();
+    let accepted = choices
+        .iter()
+        .map(|(token, _)| Some(token.clone()))
+        .collect::<Vec<_>>();
+    filter_map(move |span, token| {
+        choices
+            .iter()
+            .find(|(choice, _)| *choice == token)
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| E::expected_input_found(span, [], Some(token)))
+    })
+    .map_err_with_span(move |error: E, span| {
+        error.merge(E::expected_input_found(
+            span,
+            accepted.iter().cloned(),
+            None,
+        ))
+    })
+}
+
+/// One of several contextual keywords, each standing for a value.
+fn keywords<'a, T: Clone + 'a, E: ParseError<'a>>(
+    choices: &[(&'static str, T)],
+) -> impl Parser<LexerToken<'a>, T, Error = E> + Clone {
+    tokens(
+        choices
+            .iter()
+            .map(|(word, value)| (LexerToken::Identifier(word), value.clone()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The full definition: the header followed by the statements until the end of input.
+///
+/// Broken statements are skipped up to the next statement keyword so the rest still parse,
+/// and a broken header yields no definition while still reporting errors in the statements.
+fn definition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, Option<RapidRecastDefinition<'a>>, Error = E> {
+    let header = just(LexerToken::Definition)
+        .ignore_then(string())
+        .then(clauses(choice((
+            keyword("language_version")
+                .ignore_then(version())
+                .then_ignore(semicolon())
+                .map(HeaderClause::LanguageVersion),
+            keyword("file_version")
+                .ignore_then(version())
+                .then_ignore(semicolon())
+                .map(HeaderClause::FileVersion),
+            keyword("name")
+                .ignore_then(string())
+                .then_ignore(semicolon())
+                .map(HeaderClause::Name),
+            keyword("description")
+                .ignore_then(string())
+                .then_ignore(semicolon())
+                .map(HeaderClause::Description),
+        ))))
+        .try_map(|(id, clauses), span: Span| {
+            let (mut language_version, mut file_version) = (None, None);
+            let (mut name, mut description) = (None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    HeaderClause::LanguageVersion(version) => {
+                        once(&mut language_version, "language_version", version, at)?
+                    }
+                    HeaderClause::FileVersion(version) => {
+                        once(&mut file_version, "file_version", version, at)?
+                    }
+                    HeaderClause::Name(value) => once(&mut name, "name", value, at)?,
+                    HeaderClause::Description(value) => {
+                        once(&mut description, "description", value, at)?
+                    }
+                }
+            }
+            Ok(RapidRecastDefinition {
+                id,
+                language_version: required(language_version, "language_version", span.clone())?,
+                file_version: required(file_version, "file_version", span)?,
+                name,
+                description,
+                ast: vec![],
+            })
+        })
+        .map(Some)
+        .recover_with(skip_parser(skip_statement().to(None)));
+
+    let statement = statement()
+        .map(Some)
+        .recover_with(skip_parser(skip_statement().to(None)));
+
+    header
+        .then(statement.repeated())
+        .then_ignore(end())
+        .map(|(header, ast)| {
+            Some(RapidRecastDefinition {
+                ast: ast.into_iter().flatten().collect(),
+                ..header?
+            })
+        })
+}
+
+/// A clause of the definition header.
+enum HeaderClause<'a> {
+    LanguageVersion(Version),
+    FileVersion(Version),
+    Name(Cow<'a, str>),
+    Description(Cow<'a, str>),
+}
+
+/// Skip a broken statement: its first token, then everything up to the next statement
+/// keyword outside of braces.
+fn skip_statement<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, (), Error = E> + Clone {
+    let block = recursive(|block| {
+        just(LexerToken::LeftBrace)
+            .ignore_then(
+                block
+                    .or(none_of([LexerToken::LeftBrace, LexerToken::RightBrace]).ignored())
+                    .repeated(),
+            )
+            .then_ignore(just(LexerToken::RightBrace))
+            .ignored()
+    });
+
+    any()
+        .ignore_then(
+            block
+                .or(none_of([
+                    LexerToken::Model,
+                    LexerToken::Protocol,
+                    LexerToken::Topic,
+                    LexerToken::Cron,
+                    LexerToken::LeftBrace,
+                    LexerToken::RightBrace,
+                ])
+                .ignored())
+                .repeated(),
+        )
+        .ignored()
+}
+
+fn statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidAstStatement<'a>, Error = E> + Clone {
+    let model = model_definition().map(RapidAstStatement::ModelDefinition);
+    let topic = topic_definition().map(RapidAstStatement::TopicDefinition);
+    let cron = cron_definition().map(RapidAstStatement::CronDefinition);
+    let protocol = just(LexerToken::Protocol)
+        .ignore_then(protocol_definition())
+        .map(RapidAstStatement::ProtocolDefinition);
+
+    choice((model, protocol, topic, cron))
+}
+
+fn model_definition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidModelDefinition<'a>, Error = E> + Clone {
+    let name = select! { LexerToken::Identifier(name) => Cow::Borrowed(name) }.or(string());
+
+    let constraint = choice((
+        keyword("min")
+            .ignore_then(number())
+            .map(ModelConstraint::Min),
+        keyword("max")
+            .ignore_then(number())
+            .map(ModelConstraint::Max),
+        keyword("regex")
+            .ignore_then(string())
+            .map(ModelConstraint::Regex),
+        keyword("one_of")
+            .ignore_then(
+                model_value()
+                    .separated_by(just(LexerToken::Comma))
+                    .at_least(1),
+            )
+            .map(ModelConstraint::OneOf),
+    ));
+
+    let field = name
+        .then_ignore(just(LexerToken::Colon))
+        .then(keyword("optional").or_not())
+        .then(model_field_type())
+        .then(just(LexerToken::Assign).ignore_then(model_value()).or_not())
+        .then(constraint.repeated())
+        .then_ignore(semicolon())
+        .map(
+            |((((name, optional), field_type), default), constraints)| ModelField {
+                name,
+                field_type,
+                required: optional.is_none(),
+                default,
+                constraints,
+            },
+        )
+        .try_map(|field, span: Span| {
+            field
+                .check()
+                .map(|_| field)
+                .map_err(|e| E::custom(span, e.message))
+        });
+
+    just(LexerToken::Model)
+        .ignore_then(string())
+        .then(string())
+        .then(braced(field.repeated()))
+        .map(|((namespace, name), fields)| RapidModelDefinition {
+            namespace,
+            name,
+            fields,
+        })
+}
+
+fn model_reference<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ModelReference<'a>, Error = E> + Clone {
+    just(LexerToken::Model)
+        .ignore_then(string())
+        .then(string())
+        .map(|(namespace, name)| ModelReference { namespace, name })
+}
+
+fn model_field_type<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ModelFieldType<'a>, Error = E> + Clone {
+    recursive(|field_type| {
+        let scalar = keywords(&[
+            ("string", ScalarType::String),
+            ("integer", ScalarType::Integer),
+            ("float", ScalarType::Float),
+            ("bool", ScalarType::Bool),
+            ("timestamp", ScalarType::Timestamp),
+            ("bytes", ScalarType::Bytes),
+        ]);
+        let item = field_type
+            .delimited_by(just(LexerToken::LessThan), just(LexerToken::GreaterThan))
+            .map(Box::new);
+
+        choice((
+            scalar.map(ModelFieldType::Scalar),
+            keyword("list")
+                .ignore_then(item.clone())
+                .map(ModelFieldType::List),
+            keyword("map").ignore_then(item).map(ModelFieldType::Map),
+            model_reference().map(ModelFieldType::Model),
+        ))
+    })
+}
+
+fn model_value<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ModelValue<'a>, Error = E> + Clone {
+    recursive(|value| {
+        choice((
+            string().map(ModelValue::String),
+            number().map(ModelValue::Number),
+            keyword("true").to(ModelValue::Bool(true)),
+            keyword("false").to(ModelValue::Bool(false)),
+            value
+                .separated_by(just(LexerToken::Comma))
+                .delimited_by(
+                    just(LexerToken::LeftBracket),
+                    just(LexerToken::RightBracket),
+                )
+                .map(ModelValue::List),
+        ))
+    })
+}
+
+/// Units of time, such as retention or jitter, in milliseconds
+pub(crate) const TIME_UNITS: &[(&str, u64)] = &[
+    ("ms", 1),
+    ("s", 1_000),
+    ("m", 60_000),
+    ("h", 3_600_000),
+    ("d", 86_400_000),
+    ("w", 604_800_000),
+];
+
+/// Units of retention size, in bytes
+pub(crate) const SIZE_UNITS: &[(&str, u64)] = &[
+    ("B", 1),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+];
+
+/// A whole number followed by one of the given units, such as `7d`, converted to the base unit.
+fn quantity<'a, E: ParseError<'a>>(
+    what: &'static str,
+    units: &'static [(&'static str, u64)],
+) -> impl Parser<LexerToken<'a>, u64, Error = E> + Clone {
+    select! { LexerToken::Number(n) => n }
+        .then(select! { LexerToken::Identifier(unit) => unit })
+        .try_map(move |(n, unit), span: Span| {
+            let multiplier = units
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or_else(|| {
+                    let names = units.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                    E::custom(
+                        span.clone(),
+                        format!(
+                            "Unknown {} unit '{}', expected one of {}",
+                            what,
+                            unit,
+                            names.join(", ")
+                        ),
+                    )
+                })?;
+            n.parse::<u64>()
+                .ok()
+                .and_then(|n| n.checked_mul(multiplier))
+                .ok_or_else(|| E::custom(span, format!("Invalid {} {}{}", what, n, unit)))
+        })
+}
+
+fn topic_definition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidTopicDefinition<'a>, Error = E> + Clone {
+    let partitions = keyword("partitions")
+        .ignore_then(
+            select! { LexerToken::Number(n) => n }.try_map(|n, span: Span| {
+                n.parse::<u32>().map_err(|_| {
+                    E::custom(span, format!("Expected a partition count, found {}", n))
+                })
+            }),
+        )
+        .then_ignore(semicolon());
+
+    let retention = keyword("retention")
+        .ignore_then(
+            keyword("time")
+                .ignore_then(quantity("time", TIME_UNITS))
+                .or_not()
+                .then(
+                    keyword("size")
+                        .ignore_then(quantity("size", SIZE_UNITS))
+                        .or_not(),
+                ),
+        )
+        .then_ignore(semicolon())
+        .try_map(
+            |(time_ms, size_bytes), span: Span| match (time_ms, size_bytes) {
+                (None, None) => Err(E::custom(span, "Expected a retention time or size")),
+                _ => Ok(TopicRetention {
+                    time_ms,
+                    size_bytes,
+                }),
+            },
+        );
+
+    let compacted = keyword("compacted").then_ignore(semicolon());
+    let key = keyword("key")
+        .ignore_then(model_reference())
+        .then_ignore(semicolon());
+    let value = keyword("value")
+        .ignore_then(model_reference())
+        .then_ignore(semicolon());
+    let acl = keyword("acl").ignore_then(braced(
+        rbac_subject()
+            .then(rbac_action())
+            .then_ignore(semicolon())
+            .map(|(subject, action)| TopicAcl { subject, action })
+            .repeated(),
+    ));
+
+    just(LexerToken::Topic)
+        .ignore_then(string())
+        .then(string())
+        .then(clauses(choice((
+            partitions.map(TopicClause::Partitions),
+            retention.map(TopicClause::Retention),
+            compacted.map(|_| TopicClause::Compacted),
+            key.map(TopicClause::Key),
+            value.map(TopicClause::Value),
+            acl.map(TopicClause::Acl),
+        ))))
+        .try_map(|((namespace, name), clauses), _| {
+            let mut topic = RapidTopicDefinition {
+                namespace,
+                name,
+                partitions: None,
+                retention: TopicRetention::default(),
+                compacted: false,
+                key_schema: None,
+                value_schema: None,
+                acl: vec![],
+            };
+            let (mut retention, mut compacted, mut acl) = (None, None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    TopicClause::Partitions(count) => {
+                        once(&mut topic.partitions, "partitions", count, at)?
+                    }
+                    TopicClause::Retention(limits) => {
+                        once(&mut retention, "retention", limits, at)?
+                    }
+                    TopicClause::Compacted => once(&mut compacted, "compacted", true, at)?,
+                    TopicClause::Key(model) => once(&mut topic.key_schema, "key", model, at)?,
+                    TopicClause::Value(model) => once(&mut topic.value_schema, "value", model, at)?,
+                    TopicClause::Acl(rules) => once(&mut acl, "acl", rules, at)?,
+                }
+            }
+            topic.retention = retention.unwrap_or_default();
+            topic.compacted = compacted.unwrap_or_default();
+            topic.acl = acl.unwrap_or_default();
+            Ok(topic)
+        })
+}
+
+/// A clause of a topic definition.
+enum TopicClause<'a> {
+    Partitions(u32),
+    Retention(TopicRetention),
+    Compacted,
+    Key(ModelReference<'a>),
+    Value(ModelReference<'a>),
+    Acl(Vec<TopicAcl<'a>>),
+}
+
+fn cron_definition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidCronDefinition<'a>, Error = E> + Clone {
+    let schedule = keyword("schedule")
+        .ignore_then(string().try_map(|schedule, span: Span| {
+            CronSchedule::parse(&schedule)
+                .map(|_| schedule)
+                .map_err(|e| E::custom(span, e.message))
+        }))
+        .then_ignore(semicolon());
+    let timezone = keyword("timezone")
+        .ignore_then(string().try_map(|timezone, span: Span| {
+            parse_time_zone(&timezone)
+                .map(|_| timezone)
+                .map_err(|e| E::custom(span, e.message))
+        }))
+        .then_ignore(semicolon());
+    let jitter = keyword("jitter")
+        .ignore_then(quantity("time", TIME_UNITS))
+        .then_ignore(semicolon());
+    let overlap = keyword("overlap")
+        .ignore_then(keywords(&[
+            ("allow", CronOverlapPolicy::Allow),
+            ("skip", CronOverlapPolicy::Skip),
+            ("queue", CronOverlapPolicy::Queue),
+        ]))
+        .then_ignore(semicolon());
+
+    just(LexerToken::Cron)
+        .ignore_then(string())
+        .then(string())
+        .then(clauses(choice((
+            schedule.map(CronClause::Schedule),
+            timezone.map(CronClause::Timezone),
+            jitter.map(CronClause::Jitter),
+            overlap.map(CronClause::Overlap),
+            actions().map(CronClause::Actions),
+        ))))
+        .try_map(|((namespace, name), clauses), span: Span| {
+            let (mut schedule, mut timezone, mut jitter) = (None, None, None);
+            let (mut overlap, mut actions) = (None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    CronClause::Schedule(value) => once(&mut schedule, "schedule", value, at)?,
+                    CronClause::Timezone(value) => once(&mut timezone, "timezone", value, at)?,
+                    CronClause::Jitter(value) => once(&mut jitter, "jitter", value, at)?,
+                    CronClause::Overlap(value) => once(&mut overlap, "overlap", value, at)?,
+                    CronClause::Actions(value) => once(&mut actions, "actions", value, at)?,
+                }
+            }
+            Ok(RapidCronDefinition {
+                namespace,
+                name,
+                schedule: required(schedule, "schedule", span)?,
+                timezone,
+                jitter_ms: jitter.unwrap_or_default(),
+                overlap: overlap.unwrap_or_default(),
+                actions: actions.unwrap_or_default(),
+            })
+        })
+}
+
+/// A clause of a cron definition.
+enum CronClause<'a> {
+    Schedule(Cow<'a, str>),
+    Timezone(Cow<'a, str>),
+    Jitter(u64),
+    Overlap(CronOverlapPolicy),
+    Actions(Vec<RapidRecastAction<'a>>),
+}
+
+fn protocol_definition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidProtocolDefinition<'a>, Error = E> + Clone {
+    let method = keywords(&[
+        ("GET", RapidRecastHttpMethod::GET),
+        ("POST", RapidRecastHttpMethod::POST),
+        ("DELETE", RapidRecastHttpMethod::DELETE),
+        ("UPDATE", RapidRecastHttpMethod::UPDATE),
+        ("PATCH", RapidRecastHttpMethod::PATCH),
+        ("PUT", RapidRecastHttpMethod::PUT),
+        ("OPTIONS", RapidRecastHttpMethod::OPTIONS),
+        ("HEAD", RapidRecastHttpMethod::HEAD),
+        ("CONNECT", RapidRecastHttpMethod::CONNECT),
+        ("TRACE", RapidRecastHttpMethod::TRACE),
+    ]);
+
+    let path = string().try_map(|path, span: Span| {
+        PathTemplate::parse(&path)
+            .map(|_| path)
+            .map_err(|e| E::custom(span, e.message))
+    });
+    let paths = keyword("paths")
+        .ignore_then(path.separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+    let methods = keyword("methods")
+        .ignore_then(method.separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+    let host = keyword("host")
+        .ignore_then(http_value_match())
+        .then_ignore(semicolon());
+    let content_type = keyword("content_type")
+        .ignore_then(http_value_match())
+        .then_ignore(semicolon());
+    let field = |name| {
+        keyword(name)
+            .ignore_then(string())
+            .then(http_value_match())
+            .then_ignore(semicolon())
+            .map(|(name, value)| HttpFieldMatch { name, value })
+    };
+    let body_size = keyword("body_size")
+        .ignore_then(
+            keyword("min")
+                .ignore_then(quantity("size", SIZE_UNITS))
+                .or_not()
+                .then(
+                    keyword("max")
+                        .ignore_then(quantity("size", SIZE_UNITS))
+                        .or_not(),
+                ),
+        )
+        .then_ignore(semicolon())
+        .try_map(|(min, max), span: Span| match (min, max) {
+            (None, None) => Err(E::custom(span, "Expected a body size min or max")),
+            bounds => check_body_size(min, max)
+                .map(|_| bounds)
+                .map_err(|e| E::custom(span, e.message)),
+        });
+
+    let http = keyword("http")
+        .ignore_then(sequence())
+        .then(clauses(choice((
+            paths.map(HttpClause::Paths),
+            methods.map(HttpClause::Methods),
+            host.map(HttpClause::Host),
+            content_type.map(HttpClause::ContentType),
+            field("header").map(HttpClause::Header),
+            field("query").map(HttpClause::Query),
+            body_size.map(HttpClause::BodySize),
+            actions().map(HttpClause::Actions),
+        ))))
+        .try_map(|(sequence, clauses), _| {
+            let mut http = HttpStatement {
+                sequence,
+                paths: vec![],
+                methods: vec![],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![],
+            };
+            let (mut paths, mut methods, mut body_size, mut actions) = (None, None, None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    HttpClause::Paths(value) => once(&mut paths, "paths", value, at)?,
+                    HttpClause::Methods(value) => once(&mut methods, "methods", value, at)?,
+                    HttpClause::Host(value) => once(&mut http.host, "host", value, at)?,
+                    HttpClause::ContentType(value) => {
+                        once(&mut http.content_type, "content_type", value, at)?
+                    }
+                    HttpClause::Header(field) => http.headers.push(field),
+                    HttpClause::Query(field) => http.query.push(field),
+                    HttpClause::BodySize(value) => once(&mut body_size, "body_size", value, at)?,
+                    HttpClause::Actions(value) => once(&mut actions, "actions", value, at)?,
+                }
+            }
+            (http.min_body_bytes, http.max_body_bytes) = body_size.unwrap_or_default();
+            http.paths = paths.unwrap_or_default();
+            http.methods = methods.unwrap_or_default();
+            http.actions = actions.unwrap_or_default();
+            Ok(RapidProtocolDefinition::HttpProtocolDefinition(http))
+        });
+
+    choice((
+        http,
+        websocket_statement().map(RapidProtocolDefinition::WebSocketProtocolDefinition),
+        kafka_statement().map(RapidProtocolDefinition::KafkaProtocolDefinition),
+        amqp_statement().map(RapidProtocolDefinition::RabbitMQProtocolDefinition),
+        grpc_statement().map(RapidProtocolDefinition::GrpcProtocolDefinition),
+        mqtt_statement().map(RapidProtocolDefinition::MqttProtocolDefinition),
+    ))
+}
+
+/// A clause of an HTTP statement.
+enum HttpClause<'a> {
+    Paths(Vec<Cow<'a, str>>),
+    Methods(Vec<RapidRecastHttpMethod>),
+    Host(HttpValueMatch<'a>),
+    ContentType(HttpValueMatch<'a>),
+    Header(HttpFieldMatch<'a>),
+    Query(HttpFieldMatch<'a>),
+    BodySize((Option<u64>, Option<u64>)),
+    Actions(Vec<RapidRecastAction<'a>>),
+}
+
+/// `= "value"`, `prefix "value"`, `regex "value"`, or nothing to only require presence.
+fn http_value_match<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, HttpValueMatch<'a>, Error = E> + Clone {
+    let regex = string().try_map(|regex, span: Span| {
+        let value = HttpValueMatch::Regex(HttpRegex::new(regex));
+        value
+            .check()
+            .map(|_| value)
+            .map_err(|e| E::custom(span, e.message))
+    });
+    choice((
+        just(LexerToken::Assign)
+            .ignore_then(string())
+            .map(HttpValueMatch::Exact),
+        keyword("prefix")
+            .ignore_then(string())
+            .map(HttpValueMatch::Prefix),
+        keyword("regex").ignore_then(regex),
+    ))
+    .or_not()
+    .map(|value| value.unwrap_or(HttpValueMatch::Present))
+}
+
+fn grpc_statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, GrpcStatement<'a>, Error = E> + Clone {
+    let service = keyword("service")
+        .ignore_then(string())
+        .then_ignore(semicolon());
+    let methods = keyword("methods")
+        .ignore_then(string().separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+    let streaming = keyword("streaming")
+        .ignore_then(keywords(&[
+            ("unary", GrpcStreamingKind::Unary),
+            ("client", GrpcStreamingKind::ClientStreaming),
+            ("server", GrpcStreamingKind::ServerStreaming),
+            ("bidirectional", GrpcStreamingKind::Bidirectional),
+        ]))
+        .then_ignore(semicolon());
+    let metadata = keyword("metadata")
+        .ignore_then(string())
+        .then(just(LexerToken::Assign).ignore_then(string()).or_not())
+        .then_ignore(semicolon())
+        .map(|(name, value)| GrpcMetadataMatch { name, value });
+
+    keyword("grpc")
+        .ignore_then(sequence())
+        .then(clauses(choice((
+            service.map(GrpcClause::Service),
+            methods.map(GrpcClause::Methods),
+            streaming.map(GrpcClause::Streaming),
+            metadata.map(GrpcClause::Metadata),
+            actions().map(GrpcClause::Actions),
+        ))))
+        .try_map(|(sequence, clauses), span: Span| {
+            let (mut service, mut methods, mut streaming) = (None, None, None);
+            let (mut metadata, mut actions) = (vec![], None);
+            for (clause, at) in clauses {
+                match clause {
+                    GrpcClause::Service(value) => once(&mut service, "service", value, at)?,
+                    GrpcClause::Methods(value) => once(&mut methods, "methods", value, at)?,
+                    GrpcClause::Streaming(value) => once(&mut streaming, "streaming", value, at)?,
+                    GrpcClause::Metadata(value) => metadata.push(value),
+                    GrpcClause::Actions(value) => once(&mut actions, "actions", value, at)?,
+                }
+            }
+            Ok(GrpcStatement {
+                sequence,
+                service: required(service, "service", span)?,
+                methods: methods.unwrap_or_default(),
+                streaming,
+                metadata,
+                actions: actions.unwrap_or_default(),
+            })
+        })
+}
+
+/// A clause of a gRPC statement.
+enum GrpcClause<'a> {
+    Service(Cow<'a, str>),
+    Methods(Vec<Cow<'a, str>>),
+    Streaming(GrpcStreamingKind),
+    Metadata(GrpcMetadataMatch<'a>),
+    Actions(Vec<RapidRecastAction<'a>>),
+}
+
+fn mqtt_statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, MqttStatement<'a>, Error = E> + Clone {
+    let event = keywords(&[
+        ("publish", MqttEvent::Publish),
+        ("subscribe", MqttEvent::Subscribe),
+    ]);
+    let qos = keyword("qos").ignore_then(tokens([
+        (LexerToken::Number("0"), MqttQos::AtMostOnce),
+        (LexerToken::Number("1"), MqttQos::AtLeastOnce),
+        (LexerToken::Number("2"), MqttQos::ExactlyOnce),
+    ]));
+    let retain = keyword("retain").ignore_then(choice((
+        keyword("true").to(true),
+        keyword("false").to(false),
+    )));
+    let handler = keyword("on")
+        .ignore_then(event)
+        .then(mqtt_topic_filter())
+        .then(qos.or_not())
+        .then(retain.or_not())
+        .then(braced(action().repeated()))
+        .map(
+            |((((event, topic_filter), qos), retain), actions)| MqttHandler {
+                event,
+                topic_filter,
+                qos,
+                retain,
+                actions,
+            },
+        );
+
+    keyword("mqtt")
+        .ignore_then(sequence())
+        .then(braced(handler.repeated()))
+        .map(|(sequence, handlers)| MqttStatement { sequence, handlers })
+}
+
+fn mqtt_topic_filter<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, Cow<'a, str>, Error = E> + Clone {
+    string().try_map(|filter, span: Span| {
+        check_mqtt_topic_filter(&filter)
+            .map(|_| filter)
+            .map_err(|e| E::custom(span, e.message))
+    })
+}
+
+fn amqp_statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, AmqpStatement<'a>, Error = E> + Clone {
+    let exchange_type = tokens([
+        (LexerToken::Identifier("direct"), AmqpExchangeType::Direct),
+        (LexerToken::Identifier("fanout"), AmqpExchangeType::Fanout),
+        (LexerToken::Topic, AmqpExchangeType::Topic),
+        (LexerToken::Identifier("headers"), AmqpExchangeType::Headers),
+    ]);
+    let durable = keyword("durable").or_not().map(|durable| durable.is_some());
+
+    let exchange = keyword("exchange")
+        .ignore_then(string())
+        .then(exchange_type)
+        .then(durable.clone())
+        .then_ignore(semicolon())
+        .map(|((name, exchange_type), durable)| AmqpExchange {
+            name,
+            exchange_type,
+            durable,
+        });
+    let queue = keyword("queue")
+        .ignore_then(string())
+        .then(durable)
+        .then_ignore(semicolon())
+        .map(|(name, durable)| AmqpQueue { name, durable });
+    let routing_key = keyword("key").ignore_then(string());
+    let binding = keyword("bind")
+        .ignore_then(string())
+        .then_ignore(keyword("to"))
+        .then(string())
+        .then(routing_key.clone().or_not())
+        .then_ignore(semicolon())
+        .map(|((queue, exchange), routing_key)| AmqpBinding {
+            queue,
+            exchange,
+            routing_key: routing_key.unwrap_or_default(),
+        });
+    let on_publish = keyword("on")
+        .then(keyword("publish"))
+        .ignore_then(string())
+        .then(routing_key.or_not())
+        .then(braced(action().repeated()))
+        .map(|((exchange, routing_key), actions)| AmqpPublishHandler {
+            exchange,
+            routing_key,
+            actions,
+        });
+
+    keyword("rabbitmq")
+        .ignore_then(sequence())
+        .then(clauses(choice((
+            exchange.map(AmqpClause::Exchange),
+            queue.map(AmqpClause::Queue),
+            binding.map(AmqpClause::Binding),
+            on_publish.map(AmqpClause::OnPublish),
+        ))))
+        .map(|(sequence, clauses)| {
+            let mut amqp = AmqpStatement {
+                sequence,
+                exchanges: vec![],
+                queues: vec![],
+                bindings: vec![],
+                on_publish: vec![],
+            };
+            for (clause, _) in clauses {
+                match clause {
+                    AmqpClause::Exchange(exchange) => amqp.exchanges.push(exchange),
+                    AmqpClause::Queue(queue) => amqp.queues.push(queue),
+                    AmqpClause::Binding(binding) => amqp.bindings.push(binding),
+                    AmqpClause::OnPublish(handler) => amqp.on_publish.push(handler),
+                }
+            }
+            amqp
+        })
+}
+
+/// A clause of a RabbitMQ statement.
+enum AmqpClause<'a> {
+    Exchange(AmqpExchange<'a>),
+    Queue(AmqpQueue<'a>),
+    Binding(AmqpBinding<'a>),
+    OnPublish(AmqpPublishHandler<'a>),
+}
+
+fn kafka_statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, KafkaStatement<'a>, Error = E> + Clone {
+    let operation = keywords(&[
+        ("produce", KafkaOperation::Produce),
+        ("fetch", KafkaOperation::Fetch),
+    ]);
+
+    let operations = keyword("operations")
+        .ignore_then(operation.separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+    let topics = keyword("topics")
+        .ignore_then(
+            topic_object()
+                .separated_by(just(LexerToken::Comma))
+                .at_least(1),
+        )
+        .then_ignore(semicolon());
+    let consumer_groups = keyword("consumer_groups")
+        .ignore_then(string().separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+
+    keyword("kafka")
+        .ignore_then(sequence())
+        .then(clauses(choice((
+            operations.map(KafkaClause::Operations),
+            topics.map(KafkaClause::Topics),
+            consumer_groups.map(KafkaClause::ConsumerGroups),
+            actions().map(KafkaClause::Actions),
+        ))))
+        .try_map(|(sequence, clauses), _| {
+            let (mut operations, mut topics) = (None, None);
+            let (mut consumer_groups, mut actions) = (None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    KafkaClause::Operations(value) => {
+                        once(&mut operations, "operations", value, at)?
+                    }
+                    KafkaClause::Topics(value) => once(&mut topics, "topics", value, at)?,
+                    KafkaClause::ConsumerGroups(value) => {
+                        once(&mut consumer_groups, "consumer_groups", value, at)?
+                    }
+                    KafkaClause::Actions(value) => once(&mut actions, "actions", value, at)?,
+                }
+            }
+            Ok(KafkaStatement {
+                sequence,
+                operations: operations.unwrap_or_default(),
+                topics: topics.unwrap_or_default(),
+                consumer_groups: consumer_groups.unwrap_or_default(),
+                actions: actions.unwrap_or_default(),
+            })
+        })
+}
+
+/// A clause of a Kafka statement.
+enum KafkaClause<'a> {
+    Operations(Vec<KafkaOperation>),
+    Topics(Vec<TopicObject<'a>>),
+    ConsumerGroups(Vec<Cow<'a, str>>),
+    Actions(Vec<RapidRecastAction<'a>>),
+}
+
+fn websocket_statement<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, WebSocketStatement<'a>, Error = E> + Clone {
+    let event = |name| {
+        keyword("on")
+            .then(keyword(name))
+            .ignore_then(braced(action().repeated()))
+    };
+    let kind = keywords(&[
+        ("text", WebSocketMessageKind::Text),
+        ("binary", WebSocketMessageKind::Binary),
+    ]);
+    let on_message = keyword("on")
+        .ignore_then(kind)
+        .then(just(LexerToken::If).ignore_then(condition()).or_not())
+        .then(braced(action().repeated()))
+        .map(|((kind, condition), actions)| WebSocketMessageHandler {
+            kind,
+            condition,
+            actions,
+        });
+
+    let path = keyword("path")
+        .ignore_then(string())
+        .then_ignore(semicolon());
+    let subprotocols = keyword("subprotocols")
+        .ignore_then(string().separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+
+    keyword("websocket")
+        .ignore_then(sequence())
+        .then(clauses(choice((
+            path.map(WebSocketClause::Path),
+            subprotocols.map(WebSocketClause::Subprotocols),
+            event("connect").map(WebSocketClause::OnConnect),
+            on_message.map(WebSocketClause::OnMessage),
+            event("close").map(WebSocketClause::OnClose),
+        ))))
+        .try_map(|(sequence, clauses), span: Span| {
+            let (mut path, mut subprotocols) = (None, None);
+            let (mut on_connect, mut on_message, mut on_close) = (None, vec![], None);
+            for (clause, at) in clauses {
+                match clause {
+                    WebSocketClause::Path(value) => once(&mut path, "path", value, at)?,
+                    WebSocketClause::Subprotocols(value) => {
+                        once(&mut subprotocols, "subprotocols", value, at)?
+                    }
+                    WebSocketClause::OnConnect(value) => {
+                        once(&mut on_connect, "on connect", value, at)?
+                    }
+                    WebSocketClause::OnMessage(handler) => on_message.push(handler),
+                    WebSocketClause::OnClose(value) => once(&mut on_close, "on close", value, at)?,
+                }
+            }
+            Ok(WebSocketStatement {
+                sequence,
+                path: required(path, "path", span)?,
+                subprotocols: subprotocols.unwrap_or_default(),
+                on_connect: on_connect.unwrap_or_default(),
+                on_message,
+                on_close: on_close.unwrap_or_default(),
+            })
+        })
+}
+
+/// A clause of a WebSocket statement.
+enum WebSocketClause<'a> {
+    Path(Cow<'a, str>),
+    Subprotocols(Vec<Cow<'a, str>>),
+    OnConnect(Vec<RapidRecastAction<'a>>),
+    OnMessage(WebSocketMessageHandler<'a>),
+    OnClose(Vec<RapidRecastAction<'a>>),
+}
+
+fn actions<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, Vec<RapidRecastAction<'a>>, Error = E> + Clone {
+    keyword("actions").ignore_then(braced(action().repeated()))
+}
+
+fn action<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidRecastAction<'a>, Error = E> + Clone {
+    recursive(|action| {
+        let user = user_identifier();
+
+        let create_user = keyword("create_user")
+            .ignore_then(user.clone())
+            .then(keyword("password").ignore_then(string()).or_not())
+            .then_ignore(semicolon())
+            .map(|(subject, password)| AuthBasedAction::CreateUser { subject, password });
+
+        let add_metadata = keyword("add_metadata")
+            .ignore_then(user.clone())
+            .then(braced(
+                string()
+                    .then_ignore(just(LexerToken::Assign))
+                    .then(string())
+                    .then_ignore(semicolon())
+                    .repeated(),
+            ))
+            .map(|(subject, entries)| AuthBasedAction::AddMetadataToUser {
+                subject,
+                metadata: entries.into_iter().collect(),
+            });
+
+        let grant = keyword("grant")
+            .ignore_then(user)
+            .then(braced(policy().repeated()))
+            .map(|(subject, policy)| AuthBasedAction::GrantPermissions { subject, policy });
+
+        let auth =
+            choice((create_user, add_metadata, grant)).map(RapidRecastAction::AuthBasedAction);
+
+        let condition_block = just(LexerToken::If)
+            .ignore_then(condition())
+            .then(braced(action.clone()))
+            .then(just(LexerToken::Else).ignore_then(braced(action)).or_not())
+            .map(|((condition, if_true), if_false)| {
+                RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+                    condition,
+                    if_true: Box::new(if_true),
+                    if_false: if_false.map(Box::new),
+                })
+            });
+
+        choice((
+            auth,
+            condition_block,
+            response_action().map(RapidRecastAction::ResponseBasedAction),
+            forward_action().map(RapidRecastAction::ForwardAction),
+        ))
+    })
+}
+
+fn status_code<'a, E: ParseError<'a>>() -> impl Parser<LexerToken<'a>, u16, Error = E> + Clone {
+    select! { LexerToken::Number(n) => n }.try_map(|n, span: Span| {
+        n.parse::<u16>()
+            .map_err(|_| E::custom(span, format!("Expected a status code, found {}", n)))
+    })
+}
+
+fn response_action<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ResponseBasedAction<'a>, Error = E> + Clone {
+    let header = keyword("header")
+        .ignore_then(string())
+        .then_ignore(just(LexerToken::Assign))
+        .then(string())
+        .then_ignore(semicolon())
+        .map(|(name, value)| ResponseHeader { name, value });
+    let body = keyword("body")
+        .ignore_then(keyword("template").or_not())
+        .then(string())
+        .then_ignore(semicolon())
+        .map(|(template, body)| match template {
+            Some(()) => ResponseBody::Template(body),
+            None => ResponseBody::Text(body),
+        });
+
+    let respond = keyword("respond")
+        .ignore_then(status_code())
+        .then(choice((
+            semicolon().map(|_| vec![]),
+            clauses(choice((
+                header.map(RespondClause::Header),
+                body.map(RespondClause::Body),
+            ))),
+        )))
+        .try_map(|(status, clauses), _| {
+            let (mut headers, mut body) = (vec![], None);
+            for (clause, at) in clauses {
+                match clause {
+                    RespondClause::Header(header) => headers.push(header),
+                    RespondClause::Body(value) => once(&mut body, "body", value, at)?,
+                }
+            }
+            Ok(ResponseBasedAction::Respond {
+                status,
+                headers,
+                body,
+            })
+        });
+    let redirect = keyword("redirect")
+        .ignore_then(status_code())
+        .then(string())
+        .then_ignore(semicolon())
+        .map(|(status, location)| ResponseBasedAction::Redirect { status, location });
+    let serve_file = keyword("serve_file")
+        .ignore_then(string())
+        .then(string())
+        .then(keyword("content_type").ignore_then(string()).or_not())
+        .then_ignore(semicolon())
+        .map(
+            |((root, path), content_type)| ResponseBasedAction::ServeFile {
+                root,
+                path,
+                content_type,
+            },
+        );
+
+    choice((respond, redirect, serve_file)).try_map(|response, span: Span| {
+        response
+            .check()
+            .map(|_| response)
+            .map_err(|e| E::custom(span, e.message))
+    })
+}
+
+/// A clause of a `respond` block.
+enum RespondClause<'a> {
+    Header(ResponseHeader<'a>),
+    Body(ResponseBody<'a>),
+}
+
+fn forward_action<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ForwardAction<'a>, Error = E> + Clone {
+    let upstreams = keyword("upstreams")
+        .ignore_then(string().separated_by(just(LexerToken::Comma)).at_least(1))
+        .then_ignore(semicolon());
+    let load_balancing = keyword("load_balancing")
+        .ignore_then(keywords(&[
+            ("round_robin", LoadBalancingStrategy::RoundRobin),
+            ("random", LoadBalancingStrategy::Random),
+            ("least_connections", LoadBalancingStrategy::LeastConnections),
+            ("failover", LoadBalancingStrategy::Failover),
+        ]))
+        .then_ignore(semicolon());
+    let rewrite_path = keyword("rewrite_path")
+        .ignore_then(string())
+        .then_ignore(semicolon());
+    let timeout = keyword("timeout")
+        .ignore_then(quantity("time", TIME_UNITS))
+        .then_ignore(semicolon());
+    let remove_header = keyword("remove_header")
+        .ignore_then(string())
+        .then_ignore(semicolon())
+        .map(header_name);
+    let added_header = keyword("add_header")
+        .ignore_then(string())
+        .then_ignore(just(LexerToken::Assign))
+        .then(string())
+        .then_ignore(semicolon());
+    let attempts = select! { LexerToken::Number(n) => n }.try_map(|n, span: Span| {
+        n.parse::<u32>()
+            .map_err(|_| E::custom(span, format!("Expected a number of retries, found {}", n)))
+    });
+    let retry = keyword("retry")
+        .ignore_then(attempts)
+        .then(
+            keyword("backoff")
+                .ignore_then(quantity("time", TIME_UNITS))
+                .or_not(),
+        )
+        .then(
+            keyword("on")
+                .ignore_then(
+                    status_code()
+                        .separated_by(just(LexerToken::Comma))
+                        .at_least(1),
+                )
+                .or_not(),
+        )
+        .then_ignore(semicolon())
+        .map(|((attempts, backoff_ms), statuses)| RetryPolicy {
+            attempts,
+            backoff_ms: backoff_ms.unwrap_or_default(),
+            statuses: statuses.unwrap_or_default(),
+        });
+
+    keyword("forward")
+        .ignore_then(clauses(choice((
+            upstreams.map(ForwardClause::Upstreams),
+            load_balancing.map(ForwardClause::LoadBalancing),
+            rewrite_path.map(ForwardClause::RewritePath),
+            timeout.map(ForwardClause::Timeout),
+            remove_header.map(ForwardClause::RemoveHeader),
+            added_header.map(ForwardClause::AddHeader),
+            retry.map(ForwardClause::Retry),
+        ))))
+        .try_map(|clauses, span: Span| {
+            let mut forward = ForwardAction {
+                upstreams: vec![],
+                load_balancing: LoadBalancingStrategy::default(),
+                rewrite_path: None,
+                timeout_ms: None,
+                remove_headers: vec![],
+                add_headers: BTreeMap::new(),
+                retry: RetryPolicy::default(),
+            };
+            let (mut upstreams, mut load_balancing, mut retry) = (None, None, None);
+            for (clause, at) in clauses {
+                match clause {
+                    ForwardClause::Upstreams(value) => {
+                        once(&mut upstreams, "upstreams", value, at)?
+                    }
+                    ForwardClause::LoadBalancing(value) => {
+                        once(&mut load_balancing, "load_balancing", value, at)?
+                    }
+                    ForwardClause::RewritePath(value) => {
+                        once(&mut forward.rewrite_path, "rewrite_path", value, at)?
+                    }
+                    ForwardClause::Timeout(value) => {
+                        once(&mut forward.timeout_ms, "timeout", value, at)?
+                    }
+                    ForwardClause::RemoveHeader(name) => forward.remove_headers.push(name),
+                    ForwardClause::AddHeader((name, value)) => {
+                        add_header(&mut forward.add_headers, name, value)
+                            .map_err(|e| E::custom(at, e.message))?
+                    }
+                    ForwardClause::Retry(value) => once(&mut retry, "retry", value, at)?,
+                }
+            }
+            forward.upstreams = required(upstreams, "upstreams", span)?;
+            forward.load_balancing = load_balancing.unwrap_or_default();
+            forward.retry = retry.unwrap_or_default();
+            Ok(forward)
+        })
+        .try_map(|forward, span: Span| {
+            forward
+                .check()
+                .map(|_| forward)
+                .map_err(|e| E::custom(span, e.message))
+        })
+}
+
+/// A clause of a forward action.
+enum ForwardClause<'a> {
+    Upstreams(Vec<Cow<'a, str>>),
+    LoadBalancing(LoadBalancingStrategy),
+    RewritePath(Cow<'a, str>),
+    Timeout(u64),
+    RemoveHeader(Cow<'a, str>),
+    AddHeader((Cow<'a, str>, Cow<'a, str>)),
+    Retry(RetryPolicy),
+}
+
+fn user_identifier<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, UserIdentifier<'a>, Error = E> + Clone {
+    keyword("user")
+        .ignore_then(
+            string()
+                .then_ignore(just(LexerToken::Comma))
+                .then(string())
+                .delimited_by(just(LexerToken::LeftParen), just(LexerToken::RightParen)),
+        )
+        .map(|(namespace, username)| UserIdentifier {
+            namespace,
+            username,
+        })
+}
+
+fn rbac_subject<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidRecastRbacSubject<'a>, Error = E> + Clone {
+    choice((
+        keyword("admin").to(RapidRecastRbacSubject::Admin),
+        keyword("anon").to(RapidRecastRbacSubject::Anon),
+        user_identifier()
+            .map(|user| RapidRecastRbacSubject::UserOrRole(user.namespace, user.username)),
+    ))
+}
+
+fn rbac_action<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidRecastRbacAction, Error = E> + Clone {
+    keywords(&[
+        ("create", RapidRecastRbacAction::Create),
+        ("read", RapidRecastRbacAction::Read),
+        ("update", RapidRecastRbacAction::Update),
+        ("delete", RapidRecastRbacAction::Delete),
+        ("write", RapidRecastRbacAction::Write),
+        ("list", RapidRecastRbacAction::List),
+        ("rename", RapidRecastRbacAction::Rename),
+    ])
+}
+
+fn policy<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidRecastRbacPolicy<'a>, Error = E> + Clone {
+    rbac_subject()
+        .then(rbac_action())
+        .then(rbac_object())
+        .then_ignore(semicolon())
+        .map(|((subject, action), object)| RapidRecastRbacPolicy {
+            subject,
+            object,
+            action,
+        })
+}
+
+fn rbac_object<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, RapidRecastRbacObject<'a>, Error = E> + Clone {
+    let namespace = keyword("namespace").ignore_then(choice((
+        keyword("existing")
+            .ignore_then(string())
+            .map(NamespaceObject::ExistingNamespace),
+        keyword("new")
+            .ignore_then(string())
+            .map(NamespaceObject::NonExistingNamespace),
+    )));
+
+    let protocol = just(LexerToken::Protocol).ignore_then(keywords(&[
+        ("http", RapidRecastProtocolType::HTTP),
+        ("websocket", RapidRecastProtocolType::WebSocket),
+        ("kafka", RapidRecastProtocolType::Kafka),
+        ("rabbitmq", RapidRecastProtocolType::RabbitMQ),
+        ("grpc", RapidRecastProtocolType::Grpc),
+        ("mqtt", RapidRecastProtocolType::Mqtt),
+    ]));
+
+    let topic = just(LexerToken::Topic).ignore_then(topic_object());
+
+    let mqtt_topic = keyword("mqtt")
+        .then(just(LexerToken::Topic))
+        .ignore_then(mqtt_topic_filter());
+
+    let client = keyword("client").ignore_then(keywords(&[
+        ("http1", ClientObject::Http1),
+        ("http2", ClientObject::Http2),
+        ("http3", ClientObject::Http3),
+        ("kafka", ClientObject::Kafka),
+        ("mqtt", ClientObject::Mqtt),
+    ]));
+
+    choice((
+        namespace.map(RapidRecastRbacObject::Namespace),
+        protocol.map(RapidRecastRbacObject::Protocol),
+        topic.map(RapidRecastRbacObject::Topic),
+        client.map(RapidRecastRbacObject::Client),
+        mqtt_topic.map(RapidRecastRbacObject::MqttTopic),
+    ))
+}
+
+/// A topic that does not exist yet in a namespace, `new "ns"`, or an existing one, `"ns" "t"`.
+fn topic_object<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, TopicObject<'a>, Error = E> + Clone {
+    choice((
+        keyword("new")
+            .ignore_then(string())
+            .map(TopicObject::NonExistingTopic),
+        string()
+            .then(string())
+            .map(|(namespace, topic)| TopicObject::NamespaceTopic(namespace, topic)),
+    ))
+}
+
+/// A comparison of params, strings and numbers.
+/// A param whose name is not an identifier is written as `param("x-request-id")`.
+fn condition<'a, E: ParseError<'a>>(
+) -> impl Parser<LexerToken<'a>, ConditionStatement<'a>, Error = E> + Clone {
+    let quoted_param = keyword("param").ignore_then(
+        string().delimited_by(just(LexerToken::LeftParen), just(LexerToken::RightParen)),
+    );
+    let value = choice((
+        quoted_param.map(RecastValue::Param),
+        select! { LexerToken::Identifier(p) => RecastValue::Param(Cow::Borrowed(p)) },
+        string().map(RecastValue::String),
+        number().map(RecastValue::Number),
+    ));
+
+    type Operator<'a> = fn(RecastValue<'a>, RecastValue<'a>) -> ConditionStatement<'a>;
+    let operator = tokens([
+        (
+            LexerToken::Equals,
+            ConditionStatement::Equals as Operator<'a>,
+        ),
+        (LexerToken::NotEquals, ConditionStatement::NotEquals),
+        (LexerToken::GreaterThan, ConditionStatement::GreaterThan),
+        (LexerToken::LessThan, ConditionStatement::LessThan),
+        (
+            LexerToken::GreaterThanOrEqual,
+            ConditionStatement::GreaterThanOrEqual,
+        ),
+        (
+            LexerToken::LessThanOrEqual,
+            ConditionStatement::LessThanOrEqual,
+        ),
+        (LexerToken::And, ConditionStatement::And),
+        (LexerToken::Or, ConditionStatement::Or),
+    ]);
+
+    choice((
+        just(LexerToken::Not)
+            .ignore_then(value.clone())
+            .map(ConditionStatement::Not),
+        value
+            .clone()
+            .then(operator)
+            .then(value)
+            .map(|((left, operator), right)| operator(left, right)),
+    ))
+}
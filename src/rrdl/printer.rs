@@ -0,0 +1,926 @@
+//! Printer rendering the AST as canonical RapidRecast Definition Language.
+//!
+//! The output is indented with four spaces, omits empty optional sections,
+//! and is parsed back by [`RrdlParser`](crate::rrdl::RrdlParser) into the same AST.
+
+use crate::ast::action::{
+    AuthBasedAction, ClientObject, ConditionStatement, ForwardAction, LoadBalancingStrategy,
+    LogicBasedAction, NamespaceObject, RapidRecastAction, RapidRecastRbacAction,
+    RapidRecastRbacObject, RapidRecastRbacPolicy, RapidRecastRbacSubject, RecastValue,
+    ResponseBasedAction, ResponseBody, RetryPolicy, TopicObject, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::{
+    ModelConstraint, ModelField, ModelFieldType, ModelReference, ModelValue, RapidModelDefinition,
+    ScalarType,
+};
+use crate::ast::protocol::{
+    AmqpExchangeType, AmqpStatement, GrpcStatement, GrpcStreamingKind, HttpStatement,
+    HttpValueMatch, KafkaOperation, KafkaStatement, MqttEvent, MqttQos, MqttStatement,
+    RapidProtocolDefinition, RapidRecastHttpMethod, RapidRecastProtocolType, WebSocketMessageKind,
+    WebSocketStatement,
+};
+use crate::ast::topic::RapidTopicDefinition;
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::error::{RrdlError, SourceSpan};
+use crate::rrdl::parser::{SIZE_UNITS, TIME_UNITS};
+use crate::rrdl::{lex, LexerToken, RrdlParser, Span};
+use crate::{ParseRRDL, SaveRRDL};
+use std::io::Write;
+
+/// A printer for the RapidRecastDefinition Language
+pub struct RrdlPrinter {}
+
+impl SaveRRDL for RrdlPrinter {
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        mut writer: W,
+    ) -> Result<(), RrdlError> {
+        writer
+            .write_all(print(definition).as_bytes())
+            .map_err(RrdlError::from)
+    }
+}
+
+/// Reformat RapidRecast Definition Language text into its canonical layout.
+///
+/// Comments between the definition header and statements are kept, each on its own line
+/// before the block that follows it. Comments inside a block have nowhere to go in the AST,
+/// so input with such comments is refused rather than losing them.
+pub fn format(input: &str) -> Result<String, RrdlError> {
+    let definition = RrdlParser {}.parse_rrdl(input)?;
+    let comments = comments(input)?;
+    if let Some(inner) = comments.iter().find(|comment| comment.depth > 0) {
+        return Err(RrdlError::new(
+            "Comments inside a block cannot be kept when formatting, move them before the block",
+        )
+        .with_span(SourceSpan::from_byte_range(input, inner.span.clone())));
+    }
+    Ok(print_with_comments(&definition, &comments))
+}
+
+/// Render a definition as RapidRecast Definition Language text.
+pub fn print(definition: &RapidRecastDefinition) -> String {
+    print_with_comments(definition, &[])
+}
+
+/// A comment in RRDL text, with where it sits among the blocks.
+struct Comment<'a> {
+    text: &'a str,
+    span: Span,
+    /// How many braces are open around the comment
+    depth: usize,
+    /// How many top-level blocks, the header included, close before the comment
+    blocks_before: usize,
+}
+
+/// Find the comments in RRDL text, which the lexer skips along with whitespace.
+fn comments(input: &str) -> Result<Vec<Comment<'_>>, RrdlError> {
+    let tokens = lex(input).map_err(|e| {
+        RrdlError::new("Unrecognised input").with_span(SourceSpan::from_byte_range(input, e.span))
+    })?;
+    let gap_ends = tokens
+        .iter()
+        .map(|(_, span)| span.start)
+        .chain([input.len()]);
+    let mut comments = vec![];
+    let (mut gap_start, mut depth, mut blocks_before) = (0, 0, 0);
+    for (index, gap_end) in gap_ends.enumerate() {
+        let mut at = gap_start;
+        while at < gap_end {
+            let rest = &input[at..gap_end];
+            let length = if rest.starts_with("//") {
+                rest.find('\n').unwrap_or(rest.len())
+            } else if rest.starts_with("/*") {
+                rest.find("*/").map(|end| end + 2).unwrap_or(rest.len())
+            } else {
+                at += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                continue;
+            };
+            comments.push(Comment {
+                text: rest[..length].trim_end(),
+                span: at..at + length,
+                depth,
+                blocks_before,
+            });
+            at += length;
+        }
+        let Some((token, span)) = tokens.get(index) else {
+            break;
+        };
+        match token {
+            LexerToken::LeftBrace => depth += 1,
+            LexerToken::RightBrace => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    blocks_before += 1;
+                }
+            }
+            _ => {}
+        }
+        gap_start = span.end;
+    }
+    Ok(comments)
+}
+
+fn print_with_comments(definition: &RapidRecastDefinition, comments: &[Comment]) -> String {
+    let mut printer = Printer::default();
+    let comments_before = |printer: &mut Printer, block: usize| {
+        comments
+            .iter()
+            .filter(|comment| comment.blocks_before == block)
+            .for_each(|comment| printer.line(comment.text));
+    };
+    comments_before(&mut printer, 0);
+    printer.open(&format!("definition {}", quote(&definition.id)));
+    printer.line(&format!(
+        "language_version {};",
+        definition.language_version
+    ));
+    printer.line(&format!("file_version {};", definition.file_version));
+    if let Some(name) = &definition.name {
+        printer.line(&format!("name {};", quote(name)));
+    }
+    if let Some(description) = &definition.description {
+        printer.line(&format!("description {};", quote(description)));
+    }
+    printer.close();
+    for (index, statement) in definition.ast.iter().enumerate() {
+        printer.blank();
+        comments_before(&mut printer, index + 1);
+        printer.statement(statement);
+    }
+    let blocks = definition.ast.len() + 1;
+    if comments
+        .iter()
+        .any(|comment| comment.blocks_before == blocks)
+    {
+        printer.blank();
+        comments_before(&mut printer, blocks);
+    }
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn blank(&mut self) {
+        self.out.push('\n');
+    }
+
+    fn open(&mut self, text: &str) {
+        self.line(&format!("{} {{", text));
+        self.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn statement(&mut self, statement: &RapidAstStatement) {
+        match statement {
+            RapidAstStatement::ModelDefinition(model) => self.model(model),
+            RapidAstStatement::ProtocolDefinition(protocol) => self.protocol(protocol),
+            RapidAstStatement::TopicDefinition(topic) => self.topic(topic),
+            RapidAstStatement::CronDefinition(cron) => self.cron(cron),
+        }
+    }
+
+    fn model(&mut self, model: &RapidModelDefinition) {
+        let header = format!("model {} {}", quote(&model.namespace), quote(&model.name));
+        if model.fields.is_empty() {
+            self.line(&format!("{} {{}}", header));
+            return;
+        }
+        self.open(&header);
+        for field in &model.fields {
+            self.line(&model_field(field));
+        }
+        self.close();
+    }
+
+    fn topic(&mut self, topic: &RapidTopicDefinition) {
+        self.open(&format!(
+            "topic {} {}",
+            quote(&topic.namespace),
+            quote(&topic.name)
+        ));
+        if let Some(partitions) = topic.partitions {
+            self.line(&format!("partitions {};", partitions));
+        }
+        let retention = &topic.retention;
+        if retention.time_ms.is_some() || retention.size_bytes.is_some() {
+            let mut line = "retention".to_string();
+            if let Some(time_ms) = retention.time_ms {
+                line.push_str(&format!(" time {}", quantity(time_ms, TIME_UNITS)));
+            }
+            if let Some(size_bytes) = retention.size_bytes {
+                line.push_str(&format!(" size {}", quantity(size_bytes, SIZE_UNITS)));
+            }
+            line.push(';');
+            self.line(&line);
+        }
+        if topic.compacted {
+            self.line("compacted;");
+        }
+        if let Some(key) = &topic.key_schema {
+            self.line(&format!("key {};", model_reference(key)));
+        }
+        if let Some(value) = &topic.value_schema {
+            self.line(&format!("value {};", model_reference(value)));
+        }
+        if !topic.acl.is_empty() {
+            self.open("acl");
+            for acl in &topic.acl {
+                self.line(&format!(
+                    "{} {};",
+                    rbac_subject(&acl.subject),
+                    rbac_action(&acl.action)
+                ));
+            }
+            self.close();
+        }
+        self.close();
+    }
+
+    fn cron(&mut self, cron: &RapidCronDefinition) {
+        self.open(&format!(
+            "cron {} {}",
+            quote(&cron.namespace),
+            quote(&cron.name)
+        ));
+        self.line(&format!("schedule {};", quote(&cron.schedule)));
+        if let Some(timezone) = &cron.timezone {
+            self.line(&format!("timezone {};", quote(timezone)));
+        }
+        if cron.jitter_ms != 0 {
+            self.line(&format!("jitter {};", quantity(cron.jitter_ms, TIME_UNITS)));
+        }
+        match cron.overlap {
+            CronOverlapPolicy::Allow => {}
+            CronOverlapPolicy::Skip => self.line("overlap skip;"),
+            CronOverlapPolicy::Queue => self.line("overlap queue;"),
+        }
+        self.actions(&cron.actions);
+        self.close();
+    }
+
+    fn protocol(&mut self, protocol: &RapidProtocolDefinition) {
+        match protocol {
+            RapidProtocolDefinition::HttpProtocolDefinition(http) => self.http(http),
+            RapidProtocolDefinition::WebSocketProtocolDefinition(websocket) => {
+                self.websocket(websocket)
+            }
+            RapidProtocolDefinition::KafkaProtocolDefinition(kafka) => self.kafka(kafka),
+            RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp) => self.amqp(amqp),
+            RapidProtocolDefinition::GrpcProtocolDefinition(grpc) => self.grpc(grpc),
+            RapidProtocolDefinition::MqttProtocolDefinition(mqtt) => self.mqtt(mqtt),
+        }
+    }
+
+    /// `protocol http`, with its paths and request matchers before the actions.
+    fn http(&mut self, http: &HttpStatement) {
+        self.open(&format!("protocol http {}", http.sequence));
+        if !http.paths.is_empty() {
+            let paths = http.paths.iter().map(|p| quote(p)).collect::<Vec<_>>();
+            self.line(&format!("paths {};", paths.join(", ")));
+        }
+        if !http.methods.is_empty() {
+            let methods = http.methods.iter().map(method).collect::<Vec<_>>();
+            self.line(&format!("methods {};", methods.join(", ")));
+        }
+        if let Some(host) = &http.host {
+            self.line(&format!("host{};", http_value_match(host)));
+        }
+        if let Some(content_type) = &http.content_type {
+            self.line(&format!("content_type{};", http_value_match(content_type)));
+        }
+        for (keyword, fields) in [("header", &http.headers), ("query", &http.query)] {
+            for field in fields {
+                self.line(&format!(
+                    "{} {}{};",
+                    keyword,
+                    quote(&field.name),
+                    http_value_match(&field.value)
+                ));
+            }
+        }
+        if http.min_body_bytes.is_some() || http.max_body_bytes.is_some() {
+            let mut line = "body_size".to_string();
+            if let Some(min) = http.min_body_bytes {
+                line.push_str(&format!(" min {}", quantity(min, SIZE_UNITS)));
+            }
+            if let Some(max) = http.max_body_bytes {
+                line.push_str(&format!(" max {}", quantity(max, SIZE_UNITS)));
+            }
+            line.push(';');
+            self.line(&line);
+        }
+        self.actions(&http.actions);
+        self.close();
+    }
+
+    /// `protocol websocket`, with a block per connect, message and close handler.
+    fn websocket(&mut self, websocket: &WebSocketStatement) {
+        self.open(&format!("protocol websocket {}", websocket.sequence));
+        self.line(&format!("path {};", quote(&websocket.path)));
+        if !websocket.subprotocols.is_empty() {
+            let subprotocols = websocket
+                .subprotocols
+                .iter()
+                .map(|s| quote(s))
+                .collect::<Vec<_>>();
+            self.line(&format!("subprotocols {};", subprotocols.join(", ")));
+        }
+        if !websocket.on_connect.is_empty() {
+            self.block("on connect", &websocket.on_connect);
+        }
+        for handler in &websocket.on_message {
+            let kind = match handler.kind {
+                WebSocketMessageKind::Text => "text",
+                WebSocketMessageKind::Binary => "binary",
+            };
+            let header = match &handler.condition {
+                Some(statement) => format!("on {} if {}", kind, condition(statement)),
+                None => format!("on {}", kind),
+            };
+            self.block(&header, &handler.actions);
+        }
+        if !websocket.on_close.is_empty() {
+            self.block("on close", &websocket.on_close);
+        }
+        self.close();
+    }
+
+    /// `protocol kafka`, with the operations, topics and consumer groups it matches.
+    fn kafka(&mut self, kafka: &KafkaStatement) {
+        self.open(&format!("protocol kafka {}", kafka.sequence));
+        if !kafka.operations.is_empty() {
+            let operations = kafka
+                .operations
+                .iter()
+                .map(|operation| match operation {
+                    KafkaOperation::Produce => "produce",
+                    KafkaOperation::Fetch => "fetch",
+                })
+                .collect::<Vec<_>>();
+            self.line(&format!("operations {};", operations.join(", ")));
+        }
+        if !kafka.topics.is_empty() {
+            let topics = kafka.topics.iter().map(topic_object).collect::<Vec<_>>();
+            self.line(&format!("topics {};", topics.join(", ")));
+        }
+        if !kafka.consumer_groups.is_empty() {
+            let groups = kafka
+                .consumer_groups
+                .iter()
+                .map(|g| quote(g))
+                .collect::<Vec<_>>();
+            self.line(&format!("consumer_groups {};", groups.join(", ")));
+        }
+        self.actions(&kafka.actions);
+        self.close();
+    }
+
+    /// `protocol rabbitmq`, declaring exchanges, queues and bindings before the handlers.
+    fn amqp(&mut self, amqp: &AmqpStatement) {
+        let durable = |durable: bool| if durable { " durable" } else { "" };
+        let routing_key = |key: &str| format!(" key {}", quote(key));
+        self.open(&format!("protocol rabbitmq {}", amqp.sequence));
+        for exchange in &amqp.exchanges {
+            let exchange_type = match exchange.exchange_type {
+                AmqpExchangeType::Direct => "direct",
+                AmqpExchangeType::Fanout => "fanout",
+                AmqpExchangeType::Topic => "topic",
+                AmqpExchangeType::Headers => "headers",
+            };
+            self.line(&format!(
+                "exchange {} {}{};",
+                quote(&exchange.name),
+                exchange_type,
+                durable(exchange.durable)
+            ));
+        }
+        for queue in &amqp.queues {
+            self.line(&format!(
+                "queue {}{};",
+                quote(&queue.name),
+                durable(queue.durable)
+            ));
+        }
+        for binding in &amqp.bindings {
+            let key = match binding.routing_key.is_empty() {
+                true => String::new(),
+                false => routing_key(&binding.routing_key),
+            };
+            self.line(&format!(
+                "bind {} to {}{};",
+                quote(&binding.queue),
+                quote(&binding.exchange),
+                key
+            ));
+        }
+        for handler in &amqp.on_publish {
+            let key = handler
+                .routing_key
+                .as_deref()
+                .map(routing_key)
+                .unwrap_or_default();
+            self.block(
+                &format!("on publish {}{}", quote(&handler.exchange), key),
+                &handler.actions,
+            );
+        }
+        self.close();
+    }
+
+    /// `protocol grpc`, with the service, methods, streaming kind and metadata it matches.
+    fn grpc(&mut self, grpc: &GrpcStatement) {
+        self.open(&format!("protocol grpc {}", grpc.sequence));
+        self.line(&format!("service {};", quote(&grpc.service)));
+        if !grpc.methods.is_empty() {
+            let methods = grpc.methods.iter().map(|m| quote(m)).collect::<Vec<_>>();
+            self.line(&format!("methods {};", methods.join(", ")));
+        }
+        if let Some(streaming) = &grpc.streaming {
+            self.line(&format!(
+                "streaming {};",
+                match streaming {
+                    GrpcStreamingKind::Unary => "unary",
+                    GrpcStreamingKind::ClientStreaming => "client",
+                    GrpcStreamingKind::ServerStreaming => "server",
+                    GrpcStreamingKind::Bidirectional => "bidirectional",
+                }
+            ));
+        }
+        for metadata in &grpc.metadata {
+            match &metadata.value {
+                Some(value) => self.line(&format!(
+                    "metadata {} = {};",
+                    quote(&metadata.name),
+                    quote(value)
+                )),
+                None => self.line(&format!("metadata {};", quote(&metadata.name))),
+            }
+        }
+        self.actions(&grpc.actions);
+        self.close();
+    }
+
+    /// `protocol mqtt`, with a block per handler in the order they were declared.
+    fn mqtt(&mut self, mqtt: &MqttStatement) {
+        self.open(&format!("protocol mqtt {}", mqtt.sequence));
+        for handler in &mqtt.handlers {
+            let event = match handler.event {
+                MqttEvent::Publish => "publish",
+                MqttEvent::Subscribe => "subscribe",
+            };
+            let mut header = format!("on {} {}", event, quote(&handler.topic_filter));
+            if let Some(qos) = &handler.qos {
+                header.push_str(&format!(" qos {}", mqtt_qos(qos)));
+            }
+            if let Some(retain) = handler.retain {
+                header.push_str(&format!(" retain {}", retain));
+            }
+            self.block(&header, &handler.actions);
+        }
+        self.close();
+    }
+
+    /// A header followed by a braced list of actions, on one line when there are none.
+    fn block(&mut self, header: &str, actions: &[RapidRecastAction]) {
+        if actions.is_empty() {
+            self.line(&format!("{} {{}}", header));
+            return;
+        }
+        self.open(header);
+        for action in actions {
+            self.action(action);
+        }
+        self.close();
+    }
+
+    fn actions(&mut self, actions: &[RapidRecastAction]) {
+        if !actions.is_empty() {
+            self.block("actions", actions);
+        }
+    }
+
+    fn action(&mut self, action: &RapidRecastAction) {
+        match action {
+            RapidRecastAction::AuthBasedAction(auth) => self.auth(auth),
+            RapidRecastAction::LogicBasedAction(logic) => self.logic(logic),
+            RapidRecastAction::ResponseBasedAction(response) => self.response(response),
+            RapidRecastAction::ForwardAction(forward) => self.forward(forward),
+        }
+    }
+
+    fn forward(&mut self, forward: &ForwardAction) {
+        self.open("forward");
+        let upstreams = forward
+            .upstreams
+            .iter()
+            .map(|upstream| quote(upstream))
+            .collect::<Vec<_>>();
+        self.line(&format!("upstreams {};", upstreams.join(", ")));
+        match forward.load_balancing {
+            LoadBalancingStrategy::RoundRobin => {}
+            LoadBalancingStrategy::Random => self.line("load_balancing random;"),
+            LoadBalancingStrategy::LeastConnections => {
+                self.line("load_balancing least_connections;")
+            }
+            LoadBalancingStrategy::Failover => self.line("load_balancing failover;"),
+        }
+        if let Some(rewrite) = &forward.rewrite_path {
+            self.line(&format!("rewrite_path {};", quote(rewrite)));
+        }
+        if let Some(timeout) = forward.timeout_ms {
+            self.line(&format!("timeout {};", quantity(timeout, TIME_UNITS)));
+        }
+        for name in &forward.remove_headers {
+            self.line(&format!("remove_header {};", quote(name)));
+        }
+        for (name, value) in &forward.add_headers {
+            self.line(&format!("add_header {} = {};", quote(name), quote(value)));
+        }
+        let retry = &forward.retry;
+        if *retry != RetryPolicy::default() {
+            let mut line = format!("retry {}", retry.attempts);
+            if retry.backoff_ms != 0 {
+                line.push_str(&format!(
+                    " backoff {}",
+                    quantity(retry.backoff_ms, TIME_UNITS)
+                ));
+            }
+            if !retry.statuses.is_empty() {
+                let statuses = retry
+                    .statuses
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>();
+                line.push_str(&format!(" on {}", statuses.join(", ")));
+            }
+            self.line(&format!("{};", line));
+        }
+        self.close();
+    }
+
+    fn response(&mut self, response: &ResponseBasedAction) {
+        match response {
+            ResponseBasedAction::Respond {
+                status,
+                headers,
+                body,
+            } => {
+                if headers.is_empty() && body.is_none() {
+                    return self.line(&format!("respond {};", status));
+                }
+                self.open(&format!("respond {}", status));
+                for header in headers {
+                    self.line(&format!(
+                        "header {} = {};",
+                        quote(&header.name),
+                        quote(&header.value)
+                    ));
+                }
+                match body {
+                    Some(ResponseBody::Text(text)) => self.line(&format!("body {};", quote(text))),
+                    Some(ResponseBody::Template(template)) => {
+                        self.line(&format!("body template {};", quote(template)))
+                    }
+                    None => {}
+                }
+                self.close();
+            }
+            ResponseBasedAction::Redirect { status, location } => {
+                self.line(&format!("redirect {} {};", status, quote(location)))
+            }
+            ResponseBasedAction::ServeFile {
+                root,
+                path,
+                content_type,
+            } => {
+                let content_type = content_type
+                    .as_ref()
+                    .map(|content_type| format!(" content_type {}", quote(content_type)))
+                    .unwrap_or_default();
+                self.line(&format!(
+                    "serve_file {} {}{};",
+                    quote(root),
+                    quote(path),
+                    content_type
+                ))
+            }
+        }
+    }
+
+    fn auth(&mut self, auth: &AuthBasedAction) {
+        match auth {
+            AuthBasedAction::CreateUser { subject, password } => match password {
+                Some(password) => self.line(&format!(
+                    "create_user {} password {};",
+                    user(subject),
+                    quote(password)
+                )),
+                None => self.line(&format!("create_user {};", user(subject))),
+            },
+            AuthBasedAction::AddMetadataToUser { subject, metadata } => {
+                self.open(&format!("add_metadata {}", user(subject)));
+                for (key, value) in metadata {
+                    self.line(&format!("{} = {};", quote(key), quote(value)));
+                }
+                self.close();
+            }
+            AuthBasedAction::GrantPermissions { subject, policy } => {
+                self.open(&format!("grant {}", user(subject)));
+                for policy in policy {
+                    self.line(&rbac_policy(policy));
+                }
+                self.close();
+            }
+        }
+    }
+
+    fn logic(&mut self, logic: &LogicBasedAction) {
+        match logic {
+            LogicBasedAction::ConditionBlock {
+                condition: statement,
+                if_true,
+                if_false,
+            } => {
+                self.open(&format!("if {}", condition(statement)));
+                self.action(if_true);
+                if let Some(if_false) = if_false {
+                    self.indent -= 1;
+                    self.line("} else {");
+                    self.indent += 1;
+                    self.action(if_false);
+                }
+                self.close();
+            }
+        }
+    }
+}
+
+/// Quote and escape a string literal.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether the lexer reads `s` as an identifier, rather than a keyword or other tokens.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let keyword = matches!(
+        s,
+        "definition" | "model" | "protocol" | "topic" | "cron" | "if" | "else"
+    );
+    identifier && !keyword
+}
+
+/// A name written as an identifier where the lexer allows it, and quoted otherwise.
+fn name(s: &str) -> String {
+    if is_identifier(s) {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
+fn model_field(field: &ModelField) -> String {
+    let mut line = format!("{}: ", name(&field.name));
+    if !field.required {
+        line.push_str("optional ");
+    }
+    line.push_str(&model_field_type(&field.field_type));
+    if let Some(default) = &field.default {
+        line.push_str(&format!(" = {}", model_value(default)));
+    }
+    for constraint in &field.constraints {
+        line.push(' ');
+        line.push_str(&match constraint {
+            ModelConstraint::Min(min) => format!("min {}", min),
+            ModelConstraint::Max(max) => format!("max {}", max),
+            ModelConstraint::Regex(regex) => format!("regex {}", quote(regex)),
+            ModelConstraint::OneOf(values) => {
+                let values = values.iter().map(model_value).collect::<Vec<_>>();
+                format!("one_of {}", values.join(", "))
+            }
+        });
+    }
+    line.push(';');
+    line
+}
+
+fn model_field_type(field_type: &ModelFieldType) -> String {
+    match field_type {
+        ModelFieldType::Scalar(scalar) => match scalar {
+            ScalarType::String => "string",
+            ScalarType::Integer => "integer",
+            ScalarType::Float => "float",
+            ScalarType::Bool => "bool",
+            ScalarType::Timestamp => "timestamp",
+            ScalarType::Bytes => "bytes",
+        }
+        .to_string(),
+        ModelFieldType::List(item) => format!("list<{}>", model_field_type(item)),
+        ModelFieldType::Map(value) => format!("map<{}>", model_field_type(value)),
+        ModelFieldType::Model(model) => model_reference(model),
+    }
+}
+
+fn model_reference(model: &ModelReference) -> String {
+    format!("model {} {}", quote(&model.namespace), quote(&model.name))
+}
+
+fn model_value(value: &ModelValue) -> String {
+    match value {
+        ModelValue::String(s) => quote(s),
+        ModelValue::Number(n) => n.to_string(),
+        ModelValue::Bool(b) => b.to_string(),
+        ModelValue::List(values) => {
+            let values = values.iter().map(model_value).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+    }
+}
+
+fn method(method: &RapidRecastHttpMethod) -> &'static str {
+    match method {
+        RapidRecastHttpMethod::GET => "GET",
+        RapidRecastHttpMethod::POST => "POST",
+        RapidRecastHttpMethod::DELETE => "DELETE",
+        RapidRecastHttpMethod::UPDATE => "UPDATE",
+        RapidRecastHttpMethod::PATCH => "PATCH",
+        RapidRecastHttpMethod::PUT => "PUT",
+        RapidRecastHttpMethod::OPTIONS => "OPTIONS",
+        RapidRecastHttpMethod::HEAD => "HEAD",
+        RapidRecastHttpMethod::CONNECT => "CONNECT",
+        RapidRecastHttpMethod::TRACE => "TRACE",
+    }
+}
+
+fn user(user: &UserIdentifier) -> String {
+    format!(
+        "user({}, {})",
+        quote(&user.namespace),
+        quote(&user.username)
+    )
+}
+
+/// A number in the largest unit that represents it exactly, such as `7d`.
+fn quantity(value: u64, units: &[(&str, u64)]) -> String {
+    let (unit, multiplier) = units
+        .iter()
+        .rev()
+        .find(|(_, multiplier)| value.is_multiple_of(*multiplier))
+        .unwrap_or(&units[0]);
+    format!("{}{}", value / multiplier, unit)
+}
+
+fn rbac_subject(subject: &RapidRecastRbacSubject) -> String {
+    match subject {
+        RapidRecastRbacSubject::Admin => "admin".to_string(),
+        RapidRecastRbacSubject::Anon => "anon".to_string(),
+        RapidRecastRbacSubject::UserOrRole(namespace, username) => {
+            format!("user({}, {})", quote(namespace), quote(username))
+        }
+    }
+}
+
+fn rbac_action(action: &RapidRecastRbacAction) -> &'static str {
+    match action {
+        RapidRecastRbacAction::Create => "create",
+        RapidRecastRbacAction::Read => "read",
+        RapidRecastRbacAction::Update => "update",
+        RapidRecastRbacAction::Delete => "delete",
+        RapidRecastRbacAction::Write => "write",
+        RapidRecastRbacAction::List => "list",
+        RapidRecastRbacAction::Rename => "rename",
+    }
+}
+
+fn rbac_policy(policy: &RapidRecastRbacPolicy) -> String {
+    let subject = rbac_subject(&policy.subject);
+    let action = rbac_action(&policy.action);
+    let object = match &policy.object {
+        RapidRecastRbacObject::Namespace(NamespaceObject::ExistingNamespace(namespace)) => {
+            format!("namespace existing {}", quote(namespace))
+        }
+        RapidRecastRbacObject::Namespace(NamespaceObject::NonExistingNamespace(namespace)) => {
+            format!("namespace new {}", quote(namespace))
+        }
+        RapidRecastRbacObject::Protocol(protocol) => {
+            format!("protocol {}", protocol_type(protocol))
+        }
+        RapidRecastRbacObject::Topic(topic) => format!("topic {}", topic_object(topic)),
+        RapidRecastRbacObject::Client(client) => format!("client {}", client_type(client)),
+        RapidRecastRbacObject::MqttTopic(filter) => format!("mqtt topic {}", quote(filter)),
+        RapidRecastRbacObject::Model(model) => match *model {},
+    };
+    format!("{} {} {};", subject, action, object)
+}
+
+fn topic_object(topic: &TopicObject) -> String {
+    match topic {
+        TopicObject::NonExistingTopic(namespace) => format!("new {}", quote(namespace)),
+        TopicObject::NamespaceTopic(namespace, topic) => {
+            format!("{} {}", quote(namespace), quote(topic))
+        }
+    }
+}
+
+/// The matcher after a field, with a leading space unless it only requires presence
+fn http_value_match(value: &HttpValueMatch) -> String {
+    match value {
+        HttpValueMatch::Exact(value) => format!(" = {}", quote(value)),
+        HttpValueMatch::Prefix(prefix) => format!(" prefix {}", quote(prefix)),
+        HttpValueMatch::Regex(regex) => format!(" regex {}", quote(regex)),
+        HttpValueMatch::Present => String::new(),
+    }
+}
+
+fn mqtt_qos(qos: &MqttQos) -> u8 {
+    match qos {
+        MqttQos::AtMostOnce => 0,
+        MqttQos::AtLeastOnce => 1,
+        MqttQos::ExactlyOnce => 2,
+    }
+}
+
+fn protocol_type(protocol: &RapidRecastProtocolType) -> &'static str {
+    match protocol {
+        RapidRecastProtocolType::HTTP => "http",
+        RapidRecastProtocolType::WebSocket => "websocket",
+        RapidRecastProtocolType::Kafka => "kafka",
+        RapidRecastProtocolType::RabbitMQ => "rabbitmq",
+        RapidRecastProtocolType::Grpc => "grpc",
+        RapidRecastProtocolType::Mqtt => "mqtt",
+    }
+}
+
+fn client_type(client: &ClientObject) -> &'static str {
+    match client {
+        ClientObject::Http1 => "http1",
+        ClientObject::Http2 => "http2",
+        ClientObject::Http3 => "http3",
+        ClientObject::Kafka => "kafka",
+        ClientObject::Mqtt => "mqtt",
+    }
+}
+
+fn condition(condition: &ConditionStatement) -> String {
+    let binary = |left: &RecastValue, operator: &str, right: &RecastValue| {
+        format!("{} {} {}", value(left), operator, value(right))
+    };
+    match condition {
+        ConditionStatement::Equals(left, right) => binary(left, "==", right),
+        ConditionStatement::NotEquals(left, right) => binary(left, "!=", right),
+        ConditionStatement::GreaterThan(left, right) => binary(left, ">", right),
+        ConditionStatement::LessThan(left, right) => binary(left, "<", right),
+        ConditionStatement::GreaterThanOrEqual(left, right) => binary(left, ">=", right),
+        ConditionStatement::LessThanOrEqual(left, right) => binary(left, "<=", right),
+        ConditionStatement::And(left, right) => binary(left, "&&", right),
+        ConditionStatement::Or(left, right) => binary(left, "||", right),
+        ConditionStatement::Not(v) => format!("!{}", value(v)),
+    }
+}
+
+fn value(value: &RecastValue) -> String {
+    match value {
+        RecastValue::Param(param) if is_identifier(param) => param.to_string(),
+        RecastValue::Param(param) => format!("param({})", quote(param)),
+        RecastValue::String(s) => quote(s),
+        RecastValue::Number(n) => n.to_string(),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::rrdl::{lex, LexError, LexerToken};
+
+#[test]
+pub fn keywords_and_identifiers() {
+    let tokens = lex("definition model protocol topic cron if else GET user_name").unwrap();
+    let tokens: Vec<LexerToken> = tokens.into_iter().map(|(token, _)| token).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            LexerToken::Definition,
+            LexerToken::Model,
+            LexerToken::Protocol,
+            LexerToken::Topic,
+            LexerToken::Cron,
+            LexerToken::If,
+            LexerToken::Else,
+            LexerToken::Identifier("GET"),
+            LexerToken::Identifier("user_name"),
+        ]
+    );
+}
+
+#[test]
+pub fn literals_have_spans() {
+    let tokens = lex(r#""some \"name\"" 12 -3.5 1.2.3"#).unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            (LexerToken::String(r#"some \"name\""#), 0..15),
+            (LexerToken::Number("12"), 16..18),
+            (LexerToken::Number("-3.5"), 19..23),
+            (LexerToken::Version("1.2.3"), 24..29),
+        ]
+    );
+}
+
+#[test]
+pub fn operators_and_punctuation() {
+    let tokens = lex("== != >= <= > < && || ! = { } ( ) [ ] , ; :").unwrap();
+    let tokens: Vec<LexerToken> = tokens.into_iter().map(|(token, _)| token).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            LexerToken::Equals,
+            LexerToken::NotEquals,
+            LexerToken::GreaterThanOrEqual,
+            LexerToken::LessThanOrEqual,
+            LexerToken::GreaterThan,
+            LexerToken::LessThan,
+            LexerToken::And,
+            LexerToken::Or,
+            LexerToken::Not,
+            LexerToken::Assign,
+            LexerToken::LeftBrace,
+            LexerToken::RightBrace,
+            LexerToken::LeftParen,
+            LexerToken::RightParen,
+            LexerToken::LeftBracket,
+            LexerToken::RightBracket,
+            LexerToken::Comma,
+            LexerToken::Semicolon,
+            LexerToken::Colon,
+        ]
+    );
+}
+
+#[test]
+pub fn comments_are_skipped() {
+    let input = r#"
+    // a line comment
+    model /* a block
+    comment */ {
+    }
+    "#;
+    let tokens = lex(input).unwrap();
+    let tokens: Vec<LexerToken> = tokens.into_iter().map(|(token, _)| token).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            LexerToken::Model,
+            LexerToken::LeftBrace,
+            LexerToken::RightBrace
+        ]
+    );
+}
+
+#[test]
+pub fn unrecognised_input() {
+    let res = lex("model @ {}");
+    assert_eq!(res, Err(LexError { span: 6..7 }));
+}
@@ -0,0 +1,3 @@
+mod lexer;
+mod parser;
+mod printer;
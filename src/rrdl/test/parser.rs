@@ -0,0 +1,1050 @@
+use crate::ast::action::{
+    AuthBasedAction, ConditionStatement, LogicBasedAction, NamespaceObject, RapidRecastAction,
+    RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy, RapidRecastRbacSubject,
+    RecastValue, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::RapidModelDefinition;
+use crate::ast::protocol::{
+    HttpStatement, RapidProtocolDefinition, RapidRecastHttpMethod, RapidRecastProtocolType,
+};
+use crate::ast::topic::{RapidTopicDefinition, TopicRetention};
+use crate::ast::RapidAstStatement;
+use crate::error::{LineColumn, SourceSpan};
+use crate::rrdl::RrdlParser;
+use crate::test::{
+    bare_minimum_schema, schema_with_auth_actions, schema_with_cron, schema_with_forward,
+    schema_with_grpc, schema_with_http_matchers, schema_with_kafka, schema_with_model,
+    schema_with_mqtt, schema_with_proto_definition, schema_with_rabbitmq, schema_with_responses,
+    schema_with_topic, schema_with_websocket,
+};
+use crate::ParseRRDL;
+use std::borrow::Cow;
+
+const HEADER: &str = r#"
+definition "unique-schema-id-123" {
+    language_version 1.2.3;
+    file_version 4.5.6;
+    name "some name";
+    description "some description";
+}
+"#;
+
+#[test]
+pub fn bare_minimum() {
+    let res = RrdlParser {}.parse_rrdl(HEADER).unwrap();
+    assert_eq!(res, bare_minimum_schema());
+}
+
+#[test]
+pub fn optional_header_fields() {
+    let input = r#"
+    definition "unique-schema-id-123" {
+        language_version 1.2.3;
+        file_version 4.5.6;
+    }
+    "#;
+    let res = RrdlParser {}.parse_rrdl(input).unwrap();
+    let mut expected = bare_minimum_schema();
+    expected.name = None;
+    expected.description = None;
+    assert_eq!(res, expected);
+}
+
+#[test]
+pub fn protocol_definition() {
+    let input = format!(
+        r#"{}
+        protocol http 0 {{
+            paths "/";
+            methods GET;
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_proto_definition());
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let input = format!(
+        r#"{}
+        protocol http 0 {{
+            paths "/create-user";
+            methods PUT, POST, GET;
+            actions {{
+                create_user user("some-namespace", "some-username") password "some-password";
+                add_metadata user("some-namespace", "some-username") {{
+                    "key1" = "value1";
+                }}
+                grant user("some-namespace", "some-username") {{
+                    admin create topic new "some-namespace";
+                }}
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_auth_actions());
+}
+
+fn empty_model() -> RapidModelDefinition<'static> {
+    RapidModelDefinition {
+        namespace: Cow::Borrowed("ns"),
+        name: Cow::Borrowed("Empty"),
+        fields: vec![],
+    }
+}
+
+#[test]
+pub fn empty_statements() {
+    let input = format!(
+        "{}\nmodel \"ns\" \"Empty\" {{}}\ntopic \"ns\" \"Empty\" {{}}\ncron \"ns\" \"Empty\" {{ schedule \"@daily\"; }}",
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(
+        res.ast,
+        vec![
+            RapidAstStatement::ModelDefinition(empty_model()),
+            RapidAstStatement::TopicDefinition(RapidTopicDefinition {
+                namespace: Cow::Borrowed("ns"),
+                name: Cow::Borrowed("Empty"),
+                partitions: None,
+                retention: TopicRetention::default(),
+                compacted: false,
+                key_schema: None,
+                value_schema: None,
+                acl: vec![],
+            }),
+            RapidAstStatement::CronDefinition(RapidCronDefinition {
+                namespace: Cow::Borrowed("ns"),
+                name: Cow::Borrowed("Empty"),
+                schedule: Cow::Borrowed("@daily"),
+                timezone: None,
+                jitter_ms: 0,
+                overlap: CronOverlapPolicy::Allow,
+                actions: vec![],
+            }),
+        ]
+    );
+}
+
+#[test]
+pub fn nested_condition_blocks() {
+    let input = format!(
+        r#"{}
+        protocol http 1 {{
+            actions {{
+                if role == "admin" {{
+                    grant user("ns", "u") {{
+                        anon read protocol websocket;
+                    }}
+                }} else {{
+                    if !banned {{
+                        grant user("ns", "u") {{
+                            user("ns", "other") list namespace existing "ns";
+                        }}
+                    }}
+                }}
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    let grant = |subject, object, action| {
+        RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+            subject: UserIdentifier {
+                namespace: Cow::Borrowed("ns"),
+                username: Cow::Borrowed("u"),
+            },
+            policy: vec![RapidRecastRbacPolicy {
+                subject,
+                object,
+                action,
+            }],
+        })
+    };
+    assert_eq!(
+        res.ast,
+        vec![RapidAstStatement::ProtocolDefinition(
+            RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+                sequence: 1,
+                paths: vec![],
+                methods: vec![],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![RapidRecastAction::LogicBasedAction(
+                    LogicBasedAction::ConditionBlock {
+                        condition: ConditionStatement::Equals(
+                            RecastValue::Param(Cow::Borrowed("role")),
+                            RecastValue::String(Cow::Borrowed("admin")),
+                        ),
+                        if_true: Box::new(grant(
+                            RapidRecastRbacSubject::Anon,
+                            RapidRecastRbacObject::Protocol(RapidRecastProtocolType::WebSocket),
+                            RapidRecastRbacAction::Read,
+                        )),
+                        if_false: Some(Box::new(RapidRecastAction::LogicBasedAction(
+                            LogicBasedAction::ConditionBlock {
+                                condition: ConditionStatement::Not(RecastValue::Param(
+                                    Cow::Borrowed("banned")
+                                )),
+                                if_true: Box::new(grant(
+                                    RapidRecastRbacSubject::UserOrRole(
+                                        Cow::Borrowed("ns"),
+                                        Cow::Borrowed("other")
+                                    ),
+                                    RapidRecastRbacObject::Namespace(
+                                        NamespaceObject::ExistingNamespace(Cow::Borrowed("ns"))
+                                    ),
+                                    RapidRecastRbacAction::List,
+                                )),
+                                if_false: None,
+                            }
+                        ))),
+                    }
+                )],
+            })
+        )]
+    );
+}
+
+#[test]
+pub fn condition_operators() {
+    let cases = [
+        ("a != 1", ConditionStatement::NotEquals as fn(_, _) -> _),
+        ("a > 1", ConditionStatement::GreaterThan),
+        ("a < 1", ConditionStatement::LessThan),
+        ("a >= 1", ConditionStatement::GreaterThanOrEqual),
+        ("a <= 1", ConditionStatement::LessThanOrEqual),
+        ("a && 1", ConditionStatement::And),
+        ("a || 1", ConditionStatement::Or),
+    ];
+    for (condition, expected) in cases {
+        let input = format!(
+            r#"{}
+            protocol http 0 {{
+                actions {{
+                    if {} {{ create_user user("ns", "u"); }}
+                }}
+            }}"#,
+            HEADER, condition
+        );
+        let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+        let RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) = &res.ast[0]
+        else {
+            panic!("Expected an http statement");
+        };
+        let RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+            condition, ..
+        }) = &http.actions[0]
+        else {
+            panic!("Expected a condition block");
+        };
+        assert_eq!(
+            condition,
+            &expected(
+                RecastValue::Param(Cow::Borrowed("a")),
+                RecastValue::Number(1.0)
+            )
+        );
+    }
+}
+
+#[test]
+pub fn escaped_strings() {
+    let input = r#"
+    definition "id-with-\"quotes\"" {
+        language_version 1.2.3;
+        file_version 4.5.6;
+    }
+    "#;
+    let res = RrdlParser {}.parse_rrdl(input).unwrap();
+    assert_eq!(res.id, "id-with-\"quotes\"");
+    assert!(matches!(res.id, Cow::Owned(_)));
+}
+
+#[test]
+pub fn strings_are_borrowed_from_the_input() {
+    let input = format!(
+        "{}\nprotocol http 0 {{ paths \"/\"; methods GET; }}",
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert!(matches!(res.id, Cow::Borrowed(_)));
+    match &res.ast[..] {
+        [RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        ))] => assert!(matches!(http.paths[0], Cow::Borrowed("/"))),
+        other => panic!("Unexpected statements {:?}", other),
+    }
+}
+
+#[test]
+pub fn missing_semicolon_is_an_error() {
+    let input = r#"
+    definition "unique-schema-id-123" {
+        language_version 1.2.3
+        file_version 4.5.6;
+    }
+    "#;
+    let res = RrdlParser {}.parse_rrdl(input);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn sequence_out_of_range_is_an_error() {
+    let input = format!("{}\nprotocol http 256 {{}}", HEADER);
+    let res = RrdlParser {}.parse_rrdl(&input);
+    assert_eq!(
+        res.unwrap_err().message,
+        "Expected a sequence from 0 to 255, found 256"
+    );
+}
+
+#[test]
+pub fn errors_point_at_the_source() {
+    let input = "definition \"id\" {\n    language_version 1.2.3\n    file_version 4.5.6;\n}";
+    let error = RrdlParser {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unexpected 'file_version'");
+    assert_eq!(error.expected, vec!["';'".to_string()]);
+    assert_eq!(error.found, Some("'file_version'".to_string()));
+    assert_eq!(error.span, Some(SourceSpan::from_byte_range(input, 49..61)));
+    assert_eq!(
+        error.with_file("definition.rrdl").render(input),
+        r#"error: Unexpected 'file_version', expected one of: ';'
+ --> definition.rrdl:3:5
+  |
+3 |     file_version 4.5.6;
+  |     ^^^^^^^^^^^^
+"#
+    );
+}
+
+#[test]
+pub fn lexer_errors_point_at_the_source() {
+    let input = "definition \"id\" {\n    @\n}";
+    let error = RrdlParser {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unrecognised input");
+    assert_eq!(error.found, Some("@".to_string()));
+    assert_eq!(error.span, Some(SourceSpan::point(2, 5)));
+}
+
+#[test]
+pub fn recovers_at_statement_boundaries() {
+    let input = format!(
+        r#"{}
+        protocol http 0 {{
+            paths "/broken"
+            methods GET;
+        }}
+        model "ns" "Empty" {{}}
+        protocol http 1 {{
+            actions {{
+                grant user("ns", "u") {{
+                    admin fly topic new "t";
+                }}
+            }}
+        }}
+        protocol http 2 {{
+            paths "/";
+            methods GET;
+        }}
+        cron "ns" "Empty" {{
+            schedule "@daily";
+        }}"#,
+        HEADER
+    );
+    let (definition, diagnostics) = RrdlParser {}.parse_with_diagnostics(&input);
+    let mut expected = schema_with_proto_definition();
+    expected.ast = vec![
+        RapidAstStatement::ModelDefinition(empty_model()),
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            HttpStatement {
+                sequence: 2,
+                paths: vec![Cow::Borrowed("/")],
+                methods: vec![RapidRecastHttpMethod::GET],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![],
+            },
+        )),
+        RapidAstStatement::CronDefinition(RapidCronDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            schedule: Cow::Borrowed("@daily"),
+            timezone: None,
+            jitter_ms: 0,
+            overlap: CronOverlapPolicy::Allow,
+            actions: vec![],
+        }),
+    ];
+    assert_eq!(definition, Some(expected));
+    let messages = diagnostics
+        .iter()
+        .map(|d| (d.message.as_str(), d.span.unwrap().start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![("Unexpected 'methods'", 11), ("Unexpected 'fly'", 17)]
+    );
+    let res = RrdlParser {}.parse_rrdl(&input);
+    assert_eq!(res.unwrap_err(), diagnostics[0]);
+}
+
+#[test]
+pub fn broken_header_still_reports_statements() {
+    let input = r#"
+    definition "id" {
+        language_version 1.2;
+        file_version 4.5.6;
+    }
+    protocol http 300 {}
+    @
+    "#;
+    let (definition, diagnostics) = RrdlParser {}.parse_with_diagnostics(input);
+    assert_eq!(definition, None);
+    let messages = diagnostics
+        .iter()
+        .map(|d| d.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![
+            "Unexpected '1.2'",
+            "Expected a sequence from 0 to 255, found 300",
+            "Unrecognised input",
+        ]
+    );
+}
+
+#[test]
+pub fn parse_from_reader() {
+    let input = format!(
+        "{}\nprotocol http 0 {{ paths \"/\"; methods GET; }}",
+        HEADER
+    );
+    let res = RrdlParser {}.parse_reader(input.as_bytes()).unwrap();
+    assert_eq!(res, schema_with_proto_definition());
+}
+
+#[test]
+pub fn model_definition() {
+    let input = format!(
+        r#"{}
+        model "some-namespace" "User" {{
+            id: string regex "^[a-z0-9-]+$";
+            age: optional integer min 0 max 150;
+            score: float = 0.5;
+            active: bool = true;
+            created: timestamp;
+            avatar: optional bytes;
+            role: string = "user" one_of "user", "admin";
+            tags: list<string> = [] max 10;
+            "display-name": map<string>;
+            matrix: list<list<float>>;
+            address: optional model "some-namespace" "Address";
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_model());
+}
+
+#[test]
+pub fn unknown_field_type_is_an_error() {
+    let input = format!("{}\nmodel \"ns\" \"m\" {{\n    id: uuid;\n}}", HEADER);
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Unexpected 'uuid'");
+    assert_eq!(error.span.unwrap().start.line, 10);
+}
+
+#[test]
+pub fn field_type_errors_list_every_type() {
+    let input = format!("{}\nmodel \"ns\" \"m\" {{\n    id: uuid;\n}}", HEADER);
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.expected,
+        vec![
+            "'bool'",
+            "'bytes'",
+            "'float'",
+            "'integer'",
+            "'list'",
+            "'map'",
+            "'model'",
+            "'optional'",
+            "'string'",
+            "'timestamp'",
+        ]
+    );
+}
+
+#[test]
+pub fn model_fields_are_checked() {
+    let input = format!(
+        "{}\nmodel \"ns\" \"m\" {{\n    id: string;\n    age: integer = \"old\";\n}}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid default for field 'age', expected integer"
+    );
+    assert_eq!(
+        error.span.map(|span| span.start),
+        Some(LineColumn {
+            line: 11,
+            column: 5
+        })
+    );
+}
+
+#[test]
+pub fn topic_definition() {
+    let input = format!(
+        r#"{}
+        topic "some-namespace" "users" {{
+            partitions 12;
+            retention time 7d size 10GiB;
+            compacted;
+            key model "some-namespace" "UserKey";
+            value model "some-namespace" "User";
+            acl {{
+                anon read;
+                user("some-namespace", "some-username") write;
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_topic());
+}
+
+#[test]
+pub fn retention_units_are_checked() {
+    let input = format!("{}\ntopic \"ns\" \"t\" {{ retention time 7y; }}", HEADER);
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Unknown time unit 'y', expected one of ms, s, m, h, d, w"
+    );
+
+    let input = format!("{}\ntopic \"ns\" \"t\" {{ retention; }}", HEADER);
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Expected a retention time or size");
+}
+
+#[test]
+pub fn cron_definition() {
+    let input = format!(
+        r#"{}
+        cron "some-namespace" "nightly-cleanup" {{
+            schedule "30 2 * * mon-fri";
+            timezone "Europe/London";
+            jitter 5m;
+            overlap skip;
+            actions {{
+                create_user user("some-namespace", "cleanup-bot");
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_cron());
+}
+
+#[test]
+pub fn clauses_may_come_in_any_order() {
+    let input = r#"
+        definition "unique-schema-id-123" {
+            description "some description";
+            file_version 4.5.6;
+            name "some name";
+            language_version 1.2.3;
+        }
+        protocol http 0 {
+            actions {
+                create_user user("api", "v2-client");
+            }
+            body_size min 1B max 1MiB;
+            query "dry_run" = "true";
+            header "x-api-version" regex "^2\\.[0-9]+$";
+            content_type prefix "application/json";
+            host = "api.example.com";
+            header "authorization";
+            methods POST;
+            paths "/api/{*rest}";
+        }"#;
+    let res = RrdlParser {}.parse_rrdl(input).unwrap();
+    assert_eq!(res, schema_with_http_matchers());
+
+    let input = format!(
+        r#"{}
+        cron "some-namespace" "nightly-cleanup" {{
+            actions {{
+                create_user user("some-namespace", "cleanup-bot");
+            }}
+            overlap skip;
+            jitter 5m;
+            timezone "Europe/London";
+            schedule "30 2 * * mon-fri";
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_cron());
+}
+
+#[test]
+pub fn repeated_clauses_are_errors() {
+    let input = format!(
+        "{}\ncron \"ns\" \"c\" {{\n    timezone \"UTC\";\n    schedule \"0 0 * * *\";\n    timezone \"Europe/London\";\n}}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Duplicate 'timezone' clause");
+    let start = input.rfind("timezone").unwrap();
+    assert_eq!(
+        error.span,
+        Some(SourceSpan::from_byte_range(
+            &input,
+            start..input.rfind(';').unwrap() + 1
+        ))
+    );
+
+    let input = format!(
+        "{}\nprotocol http 0 {{ actions {{ forward {{ upstreams \"http://a\"; timeout 1s; timeout 2s; }} }} }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Duplicate 'timeout' clause");
+}
+
+#[test]
+pub fn cron_schedules_and_timezones_are_checked() {
+    let input = format!(
+        "{}\ncron \"ns\" \"c\" {{ schedule \"0 24 * * *\"; }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid hour '24' in cron schedule, expected 0 to 23"
+    );
+    assert_eq!(
+        error.span.map(|span| span.start),
+        Some(LineColumn {
+            line: 9,
+            column: 26
+        })
+    );
+
+    let input = format!(
+        "{}\ncron \"ns\" \"c\" {{ schedule \"@daily\"; timezone \"Europe/Nowhere\"; }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Unknown timezone 'Europe/Nowhere'");
+}
+
+#[test]
+pub fn websocket_statement() {
+    let input = format!(
+        r#"{}
+        protocol websocket 1 {{
+            path "/chat";
+            subprotocols "chat.v2", "chat.v1";
+            on connect {{
+                create_user user("some-namespace", "guest");
+            }}
+            on text if message == "ping" {{
+                add_metadata user("some-namespace", "guest") {{
+                    "last_message" = "ping";
+                }}
+            }}
+            on binary {{}}
+            on close {{
+                add_metadata user("some-namespace", "guest") {{
+                    "connected" = "false";
+                }}
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_websocket());
+}
+
+#[test]
+pub fn websocket_requires_a_path() {
+    let input = format!("{}\nprotocol websocket 0 {{ on connect {{}} }}", HEADER);
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Missing 'path' clause");
+    assert_eq!(
+        error.span,
+        Some(SourceSpan::from_byte_range(
+            &input,
+            input.len() - 1..input.len()
+        ))
+    );
+}
+
+#[test]
+pub fn kafka_statement() {
+    let input = format!(
+        r#"{}
+        protocol kafka 2 {{
+            operations produce, fetch;
+            topics "some-namespace" "orders-*", new "some-namespace";
+            consumer_groups "billing-*";
+            actions {{
+                grant user("some-namespace", "billing") {{
+                    user("some-namespace", "billing") read topic "some-namespace" "orders-*";
+                }}
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_kafka());
+}
+
+#[test]
+pub fn rabbitmq_statement() {
+    let input = format!(
+        r#"{}
+        protocol rabbitmq 3 {{
+            exchange "orders" topic durable;
+            exchange "audit" fanout;
+            queue "billing" durable;
+            queue "audit-log";
+            bind "billing" to "orders" key "order.*.created";
+            bind "audit-log" to "audit";
+            on publish "orders" key "order.#" {{
+                create_user user("some-namespace", "customer");
+            }}
+            on publish "audit" {{}}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_rabbitmq());
+}
+
+#[test]
+pub fn grpc_statement() {
+    let input = format!(
+        r#"{}
+        protocol grpc 4 {{
+            service "acme.users.v1.UserService";
+            methods "GetUser", "List*";
+            streaming server;
+            metadata "x-tenant" = "acme";
+            metadata "authorization";
+            actions {{
+                create_user user("acme", "grpc-client");
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_grpc());
+}
+
+#[test]
+pub fn mqtt_statement() {
+    let input = format!(
+        r#"{}
+        protocol mqtt 5 {{
+            on publish "sensors/+/temperature" qos 1 retain true {{
+                grant user("sensors", "dashboard") {{
+                    user("sensors", "dashboard") read mqtt topic "sensors/#";
+                }}
+            }}
+            on subscribe "sensors/#" qos 2 {{}}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_mqtt());
+}
+
+#[test]
+pub fn mqtt_topic_filters_are_checked() {
+    let input = format!(
+        "{}\nprotocol mqtt 5 {{ on subscribe \"sensors/temp+\" {{}} }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid MQTT topic filter 'sensors/temp+', '+' must fill a whole level"
+    );
+}
+
+#[test]
+pub fn http_matchers() {
+    let input = format!(
+        r#"{}
+        protocol http 0 {{
+            paths "/api/{{*rest}}";
+            methods POST;
+            host = "api.example.com";
+            content_type prefix "application/json";
+            header "x-api-version" regex "^2\\.[0-9]+$";
+            header "authorization";
+            query "dry_run" = "true";
+            body_size min 1B max 1MiB;
+            actions {{
+                create_user user("api", "v2-client");
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_http_matchers());
+}
+
+#[test]
+pub fn http_matchers_are_checked() {
+    let input = format!(
+        "{}\nprotocol http 0 {{ header \"x-api-version\" regex \"[0-9\"; }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid regex '[0-9', unclosed character class"
+    );
+
+    let input = format!(
+        "{}\nprotocol http 0 {{ body_size min 2MiB max 1MiB; }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "The body size min must not be larger than the max"
+    );
+}
+
+#[test]
+pub fn responses() {
+    let input = format!(
+        r#"{}
+        protocol http 1 {{
+            paths "/users/{{id:int}}";
+            methods GET;
+            actions {{
+                if id > 1000 {{
+                    redirect 308 "/archive/users/{{id}}";
+                }} else {{
+                    respond 200 {{
+                        header "content-type" = "application/json";
+                        body template "{{{{\"id\": {{id}}}}}}";
+                    }}
+                }}
+            }}
+        }}
+        protocol http 2 {{
+            paths "/static/{{*rest}}";
+            methods GET;
+            actions {{
+                serve_file "/var/www" "{{rest}}" content_type "text/css";
+            }}
+        }}
+        protocol http 3 {{
+            paths "/health";
+            methods GET;
+            actions {{
+                respond 200 {{
+                    body "ok";
+                }}
+            }}
+        }}
+        protocol http 4 {{
+            paths "/ready";
+            methods GET;
+            actions {{
+                respond 204;
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_responses());
+}
+
+#[test]
+pub fn response_actions_are_checked() {
+    let error = |action: &str| {
+        let input = format!("{}\nprotocol http 0 {{ actions {{ {} }} }}", HEADER, action);
+        RrdlParser {}.parse_rrdl(&input).unwrap_err().message
+    };
+    assert_eq!(
+        error("redirect 200 \"/login\";"),
+        "Invalid redirect status 200, expected one of 301, 302, 303, 307, 308"
+    );
+    assert_eq!(
+        error("respond 99;"),
+        "Invalid response status 99, expected 100 to 599"
+    );
+    assert_eq!(
+        error("respond 200 { body template \"{\\\"id\\\": {id}}\"; }"),
+        "Invalid template '{\"id\": {id}}', '\"id\": {id' is not a valid param name"
+    );
+}
+
+#[test]
+pub fn forward() {
+    let input = format!(
+        r#"{}
+        protocol http 1 {{
+            paths "/api/users/{{id}}";
+            actions {{
+                forward {{
+                    upstreams "http://users-a:8080", "http://users-b:8080";
+                    load_balancing least_connections;
+                    rewrite_path "/v2/users/{{id}}";
+                    timeout 5s;
+                    remove_header "cookie";
+                    add_header "x-forwarded-by" = "rapidrecast";
+                    retry 2 backoff 100ms on 502, 503;
+                }}
+            }}
+        }}
+        protocol http 2 {{
+            paths "/legacy/{{*rest}}";
+            actions {{
+                forward {{
+                    upstreams "https://legacy.example.com";
+                    load_balancing round_robin;
+                }}
+            }}
+        }}"#,
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    assert_eq!(res, schema_with_forward());
+}
+
+#[test]
+pub fn forward_actions_are_checked() {
+    let error = |forward: &str| {
+        let input = format!(
+            "{}\nprotocol http 0 {{ actions {{ forward {{ {} }} }} }}",
+            HEADER, forward
+        );
+        RrdlParser {}.parse_rrdl(&input).unwrap_err().message
+    };
+    assert_eq!(
+        error("upstreams \"users:8080\";"),
+        "Invalid upstream 'users:8080', expected an http:// or https:// URL with a host"
+    );
+    assert_eq!(
+        error("upstreams \"http://users\"; rewrite_path \"v2\";"),
+        "Invalid path rewrite 'v2', it must start with '/'"
+    );
+    assert_eq!(
+        error("upstreams \"http://users\"; timeout 0ms;"),
+        "A forward timeout must be longer than 0ms"
+    );
+    assert_eq!(
+        error("upstreams \"http://users\"; retry 1 on 600;"),
+        "Invalid retry status 600, expected 100 to 599"
+    );
+}
+
+#[test]
+pub fn forward_header_names_are_lowercased() {
+    let input = format!(
+        "{}\nprotocol http 0 {{ actions {{ forward {{ upstreams \"http://users\";\n\
+         remove_header \"Cookie\"; add_header \"X-Forwarded-By\" = \"rapidrecast\"; }} }} }}",
+        HEADER
+    );
+    let definition = RrdlParser {}.parse_rrdl(&input).unwrap();
+    match &definition.ast[0] {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            http,
+        )) => match &http.actions[0] {
+            RapidRecastAction::ForwardAction(forward) => {
+                assert_eq!(forward.remove_headers, vec!["cookie"]);
+                assert_eq!(
+                    forward
+                        .add_headers
+                        .get("x-forwarded-by")
+                        .map(|v| v.as_ref()),
+                    Some("rapidrecast")
+                );
+            }
+            other => panic!("Unexpected action {:?}", other),
+        },
+        other => panic!("Unexpected statement {:?}", other),
+    }
+
+    let input = format!(
+        "{}\nprotocol http 0 {{ actions {{ forward {{ upstreams \"http://users\";\n\
+         add_header \"x-team\" = \"a\"; add_header \"X-Team\" = \"b\"; }} }} }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(error.message, "Header 'x-team' is added more than once");
+    assert_eq!(
+        error.span.map(|span| span.start),
+        Some(LineColumn {
+            line: 10,
+            column: 28
+        })
+    );
+}
+
+#[test]
+pub fn path_templates_are_checked() {
+    let input = format!(
+        "{}\nprotocol http 0 {{ paths \"/users/{{id:int}}\", \"/static/{{*rest}}\"; }}",
+        HEADER
+    );
+    let res = RrdlParser {}.parse_rrdl(&input).unwrap();
+    let RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+        http,
+    )) = &res.ast[0]
+    else {
+        panic!("Expected an HTTP statement, got {:?}", res.ast);
+    };
+    let params = http
+        .parsed_paths()
+        .unwrap()
+        .iter()
+        .flat_map(|path| path.params().map(str::to_string).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(params, vec!["id", "rest"]);
+
+    let input = format!(
+        "{}\nprotocol http 0 {{ paths \"/\", \"/users/{{id:date}}\"; }}",
+        HEADER
+    );
+    let error = RrdlParser {}.parse_rrdl(&input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid path template '/users/{id:date}', unknown param type 'date', expected one of string, int, float, uuid"
+    );
+    assert_eq!(
+        error.span.map(|span| span.start),
+        Some(LineColumn {
+            line: 9,
+            column: 30
+        })
+    );
+}
@@ -0,0 +1,639 @@
+use crate::ast::action::{
+    AuthBasedAction, ClientObject, ConditionStatement, LogicBasedAction, NamespaceObject,
+    RapidRecastAction, RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy,
+    RapidRecastRbacSubject, RecastValue, TopicObject, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::RapidModelDefinition;
+use crate::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastProtocolType};
+use crate::ast::topic::{RapidTopicDefinition, TopicRetention};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::error::SourceSpan;
+use crate::rrdl::printer::format;
+use crate::rrdl::{RrdlParser, RrdlPrinter};
+use crate::test::{
+    bare_minimum_schema, schema_with_auth_actions, schema_with_cron, schema_with_forward,
+    schema_with_grpc, schema_with_http_matchers, schema_with_kafka, schema_with_model,
+    schema_with_mqtt, schema_with_proto_definition, schema_with_rabbitmq, schema_with_responses,
+    schema_with_topic, schema_with_websocket,
+};
+use crate::{ParseRRDL, SaveRRDL};
+use std::borrow::Cow;
+
+fn save(definition: &RapidRecastDefinition) -> String {
+    String::from_utf8(RrdlPrinter {}.save_rrdl(definition).unwrap()).unwrap()
+}
+
+fn assert_round_trip(definition: RapidRecastDefinition) {
+    let printed = save(&definition);
+    let parsed = RrdlParser {}.parse_rrdl(&printed).unwrap();
+    assert_eq!(parsed, definition, "Printed as:\n{}", printed);
+}
+
+#[test]
+pub fn test_basic_save() {
+    let res = save(&bare_minimum_schema());
+    assert_eq!(
+        res,
+        r#"definition "unique-schema-id-123" {
+    language_version 1.2.3;
+    file_version 4.5.6;
+    name "some name";
+    description "some description";
+}
+"#
+    );
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let res = save(&schema_with_auth_actions());
+    assert_eq!(
+        res,
+        r#"definition "unique-schema-id-123" {
+    language_version 1.2.3;
+    file_version 4.5.6;
+    name "some name";
+    description "some description";
+}
+
+protocol http 0 {
+    paths "/create-user";
+    methods PUT, POST, GET;
+    actions {
+        create_user user("some-namespace", "some-username") password "some-password";
+        add_metadata user("some-namespace", "some-username") {
+            "key1" = "value1";
+        }
+        grant user("some-namespace", "some-username") {
+            admin create topic new "some-namespace";
+        }
+    }
+}
+"#
+    );
+}
+
+#[test]
+pub fn fixtures_round_trip() {
+    assert_round_trip(bare_minimum_schema());
+    assert_round_trip(schema_with_proto_definition());
+    assert_round_trip(schema_with_auth_actions());
+    assert_round_trip(schema_with_model());
+    assert_round_trip(schema_with_topic());
+    assert_round_trip(schema_with_cron());
+    assert_round_trip(schema_with_websocket());
+    assert_round_trip(schema_with_kafka());
+    assert_round_trip(schema_with_rabbitmq());
+    assert_round_trip(schema_with_grpc());
+    assert_round_trip(schema_with_mqtt());
+    assert_round_trip(schema_with_http_matchers());
+    assert_round_trip(schema_with_responses());
+    assert_round_trip(schema_with_forward());
+}
+
+#[test]
+pub fn every_statement_round_trips() {
+    let mut definition = bare_minimum_schema();
+    definition.id = Cow::Borrowed("escaped \"id\"\twith\\slashes\n");
+    definition.description = None;
+    let user = UserIdentifier {
+        namespace: Cow::Borrowed("ns"),
+        username: Cow::Borrowed("u"),
+    };
+    let policy = |subject, object| RapidRecastRbacPolicy {
+        subject,
+        object,
+        action: RapidRecastRbacAction::Rename,
+    };
+    definition.ast = vec![
+        RapidAstStatement::ModelDefinition(RapidModelDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            fields: vec![],
+        }),
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            HttpStatement {
+                sequence: 255,
+                paths: vec![],
+                methods: vec![],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![
+                    RapidRecastAction::AuthBasedAction(AuthBasedAction::CreateUser {
+                        subject: user.clone(),
+                        password: None,
+                    }),
+                    RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+                        subject: user.clone(),
+                        policy: vec![
+                            policy(
+                                RapidRecastRbacSubject::Anon,
+                                RapidRecastRbacObject::Namespace(
+                                    NamespaceObject::NonExistingNamespace(Cow::Borrowed("a")),
+                                ),
+                            ),
+                            policy(
+                                RapidRecastRbacSubject::UserOrRole(
+                                    Cow::Borrowed("b"),
+                                    Cow::Borrowed("c"),
+                                ),
+                                RapidRecastRbacObject::Topic(TopicObject::NamespaceTopic(
+                                    Cow::Borrowed("d"),
+                                    Cow::Borrowed("e"),
+                                )),
+                            ),
+                            policy(
+                                RapidRecastRbacSubject::Admin,
+                                RapidRecastRbacObject::Protocol(RapidRecastProtocolType::Grpc),
+                            ),
+                            policy(
+                                RapidRecastRbacSubject::Admin,
+                                RapidRecastRbacObject::Client(ClientObject::Http3),
+                            ),
+                        ],
+                    }),
+                    RapidRecastAction::LogicBasedAction(LogicBasedAction::ConditionBlock {
+                        condition: ConditionStatement::LessThanOrEqual(
+                            RecastValue::Number(-1.5),
+                            RecastValue::String(Cow::Borrowed("x")),
+                        ),
+                        if_true: Box::new(RapidRecastAction::LogicBasedAction(
+                            LogicBasedAction::ConditionBlock {
+                                condition: ConditionStatement::Not(RecastValue::Param(
+                                    Cow::Borrowed("flag"),
+                                )),
+                                if_true: Box::new(RapidRecastAction::AuthBasedAction(
+                                    AuthBasedAction::CreateUser {
+                                        subject: user.clone(),
+                                        password: Some(Cow::Borrowed("pw")),
+                                    },
+                                )),
+                                if_false: None,
+                            },
+                        )),
+                        if_false: Some(Box::new(RapidRecastAction::AuthBasedAction(
+                            AuthBasedAction::AddMetadataToUser {
+                                subject: user,
+                                metadata: [
+                                    (Cow::Borrowed("b"), Cow::Borrowed("2")),
+                                    (Cow::Borrowed("a"), Cow::Borrowed("1")),
+                                ]
+                                .into_iter()
+                                .collect(),
+                            },
+                        ))),
+                    }),
+                ],
+            },
+        )),
+        RapidAstStatement::TopicDefinition(RapidTopicDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            partitions: None,
+            retention: TopicRetention::default(),
+            compacted: false,
+            key_schema: None,
+            value_schema: None,
+            acl: vec![],
+        }),
+        RapidAstStatement::CronDefinition(RapidCronDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            schedule: Cow::Borrowed("@daily"),
+            timezone: None,
+            jitter_ms: 0,
+            overlap: CronOverlapPolicy::Allow,
+            actions: vec![],
+        }),
+    ];
+    assert_round_trip(definition);
+}
+
+#[test]
+pub fn params_that_are_not_identifiers_are_quoted() {
+    let mut definition = bare_minimum_schema();
+    let condition = |param| LogicBasedAction::ConditionBlock {
+        condition: ConditionStatement::Equals(
+            RecastValue::Param(Cow::Borrowed(param)),
+            RecastValue::Number(1.0),
+        ),
+        if_true: Box::new(RapidRecastAction::AuthBasedAction(
+            AuthBasedAction::CreateUser {
+                subject: UserIdentifier {
+                    namespace: Cow::Borrowed("ns"),
+                    username: Cow::Borrowed("u"),
+                },
+                password: None,
+            },
+        )),
+        if_false: None,
+    };
+    definition.ast = vec![RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+            sequence: 0,
+            paths: vec![],
+            methods: vec![],
+            min_body_bytes: None,
+            max_body_bytes: None,
+            host: None,
+            content_type: None,
+            headers: vec![],
+            query: vec![],
+            actions: ["x-request-id", "if", "param", "user id", "\"quoted\""]
+                .into_iter()
+                .map(|param| RapidRecastAction::LogicBasedAction(condition(param)))
+                .collect(),
+        }),
+    )];
+    let printed = save(&definition);
+    assert!(
+        printed.contains("if param(\"x-request-id\") == 1 {"),
+        "{}",
+        printed
+    );
+    assert!(printed.contains("if param(\"if\") == 1 {"), "{}", printed);
+    assert!(printed.contains("if param == 1 {"), "{}", printed);
+    assert_round_trip(definition);
+}
+
+#[test]
+pub fn format_is_canonical() {
+    let input = r#"
+    // comments outside blocks are kept
+    definition "id" { language_version 1.0.0; file_version 2.0.0; }
+    protocol http 3 { methods GET,POST; actions { if a==1 { create_user user("n","u"); } else { create_user user("n","v"); } } }
+    "#;
+    let formatted = format(input).unwrap();
+    assert_eq!(
+        formatted,
+        r#"// comments outside blocks are kept
+definition "id" {
+    language_version 1.0.0;
+    file_version 2.0.0;
+}
+
+protocol http 3 {
+    methods GET, POST;
+    actions {
+        if a == 1 {
+            create_user user("n", "u");
+        } else {
+            create_user user("n", "v");
+        }
+    }
+}
+"#
+    );
+    assert_eq!(format(&formatted).unwrap(), formatted);
+}
+
+#[test]
+pub fn format_keeps_comments_between_blocks() {
+    let input = r#"definition "id" { language_version 1.0.0; file_version 2.0.0; } // the header
+
+/* Users
+   and their ages */
+model "n" "User" { age: integer; }
+// the end"#;
+    let formatted = format(input).unwrap();
+    assert_eq!(
+        formatted,
+        r#"definition "id" {
+    language_version 1.0.0;
+    file_version 2.0.0;
+}
+
+// the header
+/* Users
+   and their ages */
+model "n" "User" {
+    age: integer;
+}
+
+// the end
+"#
+    );
+    assert_eq!(format(&formatted).unwrap(), formatted);
+}
+
+#[test]
+pub fn format_refuses_comments_inside_blocks() {
+    let input = "definition \"id\" {\n    language_version 1.0.0; // which language\n    file_version 2.0.0;\n}";
+    let error = format(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Comments inside a block cannot be kept when formatting, move them before the block"
+    );
+    let start = input.find("//").unwrap();
+    assert_eq!(
+        error.span,
+        Some(SourceSpan::from_byte_range(
+            input,
+            start..input.find("\n    file").unwrap()
+        ))
+    );
+}
+
+#[test]
+pub fn model_definition() {
+    let res = save(&schema_with_model());
+    assert!(
+        res.ends_with(
+            r#"model "some-namespace" "User" {
+    id: string regex "^[a-z0-9-]+$";
+    age: optional integer min 0 max 150;
+    score: float = 0.5;
+    active: bool = true;
+    created: timestamp;
+    avatar: optional bytes;
+    role: string = "user" one_of "user", "admin";
+    tags: list<string> = [] max 10;
+    "display-name": map<string>;
+    matrix: list<list<float>>;
+    address: optional model "some-namespace" "Address";
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn topic_definition() {
+    let res = save(&schema_with_topic());
+    assert!(
+        res.ends_with(
+            r#"topic "some-namespace" "users" {
+    partitions 12;
+    retention time 1w size 10GiB;
+    compacted;
+    key model "some-namespace" "UserKey";
+    value model "some-namespace" "User";
+    acl {
+        anon read;
+        user("some-namespace", "some-username") write;
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn cron_definition() {
+    let res = save(&schema_with_cron());
+    assert!(
+        res.ends_with(
+            r#"cron "some-namespace" "nightly-cleanup" {
+    schedule "30 2 * * mon-fri";
+    timezone "Europe/London";
+    jitter 5m;
+    overlap skip;
+    actions {
+        create_user user("some-namespace", "cleanup-bot");
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn websocket_statement() {
+    let res = save(&schema_with_websocket());
+    assert!(
+        res.ends_with(
+            r#"protocol websocket 1 {
+    path "/chat";
+    subprotocols "chat.v2", "chat.v1";
+    on connect {
+        create_user user("some-namespace", "guest");
+    }
+    on text if message == "ping" {
+        add_metadata user("some-namespace", "guest") {
+            "last_message" = "ping";
+        }
+    }
+    on binary {}
+    on close {
+        add_metadata user("some-namespace", "guest") {
+            "connected" = "false";
+        }
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn kafka_statement() {
+    let res = save(&schema_with_kafka());
+    assert!(
+        res.ends_with(
+            r#"protocol kafka 2 {
+    operations produce, fetch;
+    topics "some-namespace" "orders-*", new "some-namespace";
+    consumer_groups "billing-*";
+    actions {
+        grant user("some-namespace", "billing") {
+            user("some-namespace", "billing") read topic "some-namespace" "orders-*";
+        }
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn rabbitmq_statement() {
+    let res = save(&schema_with_rabbitmq());
+    assert!(
+        res.ends_with(
+            r#"protocol rabbitmq 3 {
+    exchange "orders" topic durable;
+    exchange "audit" fanout;
+    queue "billing" durable;
+    queue "audit-log";
+    bind "billing" to "orders" key "order.*.created";
+    bind "audit-log" to "audit";
+    on publish "orders" key "order.#" {
+        create_user user("some-namespace", "customer");
+    }
+    on publish "audit" {}
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn grpc_statement() {
+    let res = save(&schema_with_grpc());
+    assert!(
+        res.ends_with(
+            r#"protocol grpc 4 {
+    service "acme.users.v1.UserService";
+    methods "GetUser", "List*";
+    streaming server;
+    metadata "x-tenant" = "acme";
+    metadata "authorization";
+    actions {
+        create_user user("acme", "grpc-client");
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn mqtt_statement() {
+    let res = save(&schema_with_mqtt());
+    assert!(
+        res.ends_with(
+            r#"protocol mqtt 5 {
+    on publish "sensors/+/temperature" qos 1 retain true {
+        grant user("sensors", "dashboard") {
+            user("sensors", "dashboard") read mqtt topic "sensors/#";
+        }
+    }
+    on subscribe "sensors/#" qos 2 {}
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn http_matchers() {
+    let res = save(&schema_with_http_matchers());
+    assert!(
+        res.ends_with(
+            r#"protocol http 0 {
+    paths "/api/{*rest}";
+    methods POST;
+    host = "api.example.com";
+    content_type prefix "application/json";
+    header "x-api-version" regex "^2\\.[0-9]+$";
+    header "authorization";
+    query "dry_run" = "true";
+    body_size min 1B max 1MiB;
+    actions {
+        create_user user("api", "v2-client");
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn responses() {
+    let res = save(&schema_with_responses());
+    assert!(
+        res.ends_with(
+            r#"protocol http 1 {
+    paths "/users/{id:int}";
+    methods GET;
+    actions {
+        if id > 1000 {
+            redirect 308 "/archive/users/{id}";
+        } else {
+            respond 200 {
+                header "content-type" = "application/json";
+                body template "{{\"id\": {id}}}";
+            }
+        }
+    }
+}
+
+protocol http 2 {
+    paths "/static/{*rest}";
+    methods GET;
+    actions {
+        serve_file "/var/www" "{rest}" content_type "text/css";
+    }
+}
+
+protocol http 3 {
+    paths "/health";
+    methods GET;
+    actions {
+        respond 200 {
+            body "ok";
+        }
+    }
+}
+
+protocol http 4 {
+    paths "/ready";
+    methods GET;
+    actions {
+        respond 204;
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
+
+#[test]
+pub fn forward() {
+    let res = save(&schema_with_forward());
+    assert!(
+        res.ends_with(
+            r#"protocol http 1 {
+    paths "/api/users/{id}";
+    actions {
+        forward {
+            upstreams "http://users-a:8080", "http://users-b:8080";
+            load_balancing least_connections;
+            rewrite_path "/v2/users/{id}";
+            timeout 5s;
+            remove_header "cookie";
+            add_header "x-forwarded-by" = "rapidrecast";
+            retry 2 backoff 100ms on 502, 503;
+        }
+    }
+}
+
+protocol http 2 {
+    paths "/legacy/{*rest}";
+    actions {
+        forward {
+            upstreams "https://legacy.example.com";
+        }
+    }
+}
+"#
+        ),
+        "{}",
+        res
+    );
+}
@@ -1,6 +1,25 @@
-use crate::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastHttpMethod};
+use crate::ast::action::{
+    AuthBasedAction, ConditionStatement, ForwardAction, LoadBalancingStrategy, LogicBasedAction,
+    RapidRecastAction, RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy,
+    RapidRecastRbacSubject, RecastValue, ResponseBasedAction, ResponseBody, ResponseHeader,
+    RetryPolicy, TopicObject, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::{
+    ModelConstraint, ModelField, ModelFieldType, ModelReference, ModelValue, RapidModelDefinition,
+    ScalarType,
+};
+use crate::ast::protocol::{
+    AmqpBinding, AmqpExchange, AmqpExchangeType, AmqpPublishHandler, AmqpQueue, AmqpStatement,
+    GrpcMetadataMatch, GrpcStatement, GrpcStreamingKind, HttpFieldMatch, HttpRegex, HttpStatement,
+    HttpValueMatch, KafkaOperation, KafkaStatement, MqttEvent, MqttHandler, MqttQos, MqttStatement,
+    RapidProtocolDefinition, RapidRecastHttpMethod, WebSocketMessageHandler, WebSocketMessageKind,
+    WebSocketStatement,
+};
+use crate::ast::topic::{RapidTopicDefinition, TopicAcl, TopicRetention};
 use crate::ast::{RapidAstStatement, RapidRecastDefinition, Version};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 /// An AST that contains only the bare minimum info
 pub fn bare_minimum_schema() -> RapidRecastDefinition<'static> {
@@ -30,8 +49,607 @@ pub fn schema_with_proto_definition() -> RapidRecastDefinition<'static> {
             sequence: 0,
             paths: vec![Cow::Borrowed("/")],
             methods: vec![RapidRecastHttpMethod::GET],
+            min_body_bytes: None,
+            max_body_bytes: None,
+            host: None,
+            content_type: None,
+            headers: vec![],
+            query: vec![],
             actions: vec![],
         }),
     ));
     schema
 }
+
+/// A fixture with an HTTP rule that creates a user and grants them permissions
+pub fn schema_with_auth_actions() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let mut metadata = BTreeMap::new();
+    metadata.insert(Cow::Borrowed("key1"), Cow::Borrowed("value1"));
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+            sequence: 0,
+            paths: vec![Cow::Borrowed("/create-user")],
+            methods: vec![
+                RapidRecastHttpMethod::PUT,
+                RapidRecastHttpMethod::POST,
+                RapidRecastHttpMethod::GET,
+            ],
+            min_body_bytes: None,
+            max_body_bytes: None,
+            host: None,
+            content_type: None,
+            headers: vec![],
+            query: vec![],
+            actions: vec![
+                RapidRecastAction::AuthBasedAction(AuthBasedAction::CreateUser {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        username: Cow::Borrowed("some-username"),
+                    },
+                    password: Some(Cow::Borrowed("some-password")),
+                }),
+                RapidRecastAction::AuthBasedAction(AuthBasedAction::AddMetadataToUser {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        username: Cow::Borrowed("some-username"),
+                    },
+                    metadata,
+                }),
+                RapidRecastAction::AuthBasedAction(AuthBasedAction::GrantPermissions {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        username: Cow::Borrowed("some-username"),
+                    },
+                    policy: vec![RapidRecastRbacPolicy {
+                        subject: RapidRecastRbacSubject::Admin,
+                        object: RapidRecastRbacObject::Topic(TopicObject::NonExistingTopic(
+                            Cow::Borrowed("some-namespace"),
+                        )),
+                        action: RapidRecastRbacAction::Create,
+                    }],
+                }),
+            ],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with a model using every field type, a default and each constraint
+pub fn schema_with_model() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let field = |name, field_type, required| ModelField {
+        name: Cow::Borrowed(name),
+        field_type,
+        required,
+        default: None,
+        constraints: vec![],
+    };
+    let scalar = ModelFieldType::Scalar;
+    let string = || Box::new(ModelFieldType::Scalar(ScalarType::String));
+    schema
+        .ast
+        .push(RapidAstStatement::ModelDefinition(RapidModelDefinition {
+            namespace: Cow::Borrowed("some-namespace"),
+            name: Cow::Borrowed("User"),
+            fields: vec![
+                ModelField {
+                    constraints: vec![ModelConstraint::Regex(Cow::Borrowed("^[a-z0-9-]+$"))],
+                    ..field("id", scalar(ScalarType::String), true)
+                },
+                ModelField {
+                    constraints: vec![ModelConstraint::Min(0.0), ModelConstraint::Max(150.0)],
+                    ..field("age", scalar(ScalarType::Integer), false)
+                },
+                ModelField {
+                    default: Some(ModelValue::Number(0.5)),
+                    ..field("score", scalar(ScalarType::Float), true)
+                },
+                ModelField {
+                    default: Some(ModelValue::Bool(true)),
+                    ..field("active", scalar(ScalarType::Bool), true)
+                },
+                field("created", scalar(ScalarType::Timestamp), true),
+                field("avatar", scalar(ScalarType::Bytes), false),
+                ModelField {
+                    default: Some(ModelValue::String(Cow::Borrowed("user"))),
+                    constraints: vec![ModelConstraint::OneOf(vec![
+                        ModelValue::String(Cow::Borrowed("user")),
+                        ModelValue::String(Cow::Borrowed("admin")),
+                    ])],
+                    ..field("role", scalar(ScalarType::String), true)
+                },
+                ModelField {
+                    default: Some(ModelValue::List(vec![])),
+                    constraints: vec![ModelConstraint::Max(10.0)],
+                    ..field("tags", ModelFieldType::List(string()), true)
+                },
+                field("display-name", ModelFieldType::Map(string()), true),
+                field(
+                    "matrix",
+                    ModelFieldType::List(Box::new(ModelFieldType::List(Box::new(
+                        ModelFieldType::Scalar(ScalarType::Float),
+                    )))),
+                    true,
+                ),
+                field(
+                    "address",
+                    ModelFieldType::Model(ModelReference {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        name: Cow::Borrowed("Address"),
+                    }),
+                    false,
+                ),
+            ],
+        }));
+    schema
+}
+
+/// A fixture with a compacted topic using every setting
+pub fn schema_with_topic() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let model = |name| ModelReference {
+        namespace: Cow::Borrowed("some-namespace"),
+        name: Cow::Borrowed(name),
+    };
+    schema
+        .ast
+        .push(RapidAstStatement::TopicDefinition(RapidTopicDefinition {
+            namespace: Cow::Borrowed("some-namespace"),
+            name: Cow::Borrowed("users"),
+            partitions: Some(12),
+            retention: TopicRetention {
+                time_ms: Some(7 * 24 * 60 * 60 * 1000),
+                size_bytes: Some(10 << 30),
+            },
+            compacted: true,
+            key_schema: Some(model("UserKey")),
+            value_schema: Some(model("User")),
+            acl: vec![
+                TopicAcl {
+                    subject: RapidRecastRbacSubject::Anon,
+                    action: RapidRecastRbacAction::Read,
+                },
+                TopicAcl {
+                    subject: RapidRecastRbacSubject::UserOrRole(
+                        Cow::Borrowed("some-namespace"),
+                        Cow::Borrowed("some-username"),
+                    ),
+                    action: RapidRecastRbacAction::Write,
+                },
+            ],
+        }));
+    schema
+}
+
+/// A fixture with a cron using every setting
+pub fn schema_with_cron() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema
+        .ast
+        .push(RapidAstStatement::CronDefinition(RapidCronDefinition {
+            namespace: Cow::Borrowed("some-namespace"),
+            name: Cow::Borrowed("nightly-cleanup"),
+            schedule: Cow::Borrowed("30 2 * * mon-fri"),
+            timezone: Some(Cow::Borrowed("Europe/London")),
+            jitter_ms: 5 * 60 * 1000,
+            overlap: CronOverlapPolicy::Skip,
+            actions: vec![RapidRecastAction::AuthBasedAction(
+                AuthBasedAction::CreateUser {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        username: Cow::Borrowed("cleanup-bot"),
+                    },
+                    password: None,
+                },
+            )],
+        }));
+    schema
+}
+
+/// A fixture with a WebSocket handling every event
+pub fn schema_with_websocket() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let guest = || UserIdentifier {
+        namespace: Cow::Borrowed("some-namespace"),
+        username: Cow::Borrowed("guest"),
+    };
+    let metadata = |key, value| {
+        RapidRecastAction::AuthBasedAction(AuthBasedAction::AddMetadataToUser {
+            subject: guest(),
+            metadata: BTreeMap::from([(Cow::Borrowed(key), Cow::Borrowed(value))]),
+        })
+    };
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::WebSocketProtocolDefinition(WebSocketStatement {
+            sequence: 1,
+            path: Cow::Borrowed("/chat"),
+            subprotocols: vec![Cow::Borrowed("chat.v2"), Cow::Borrowed("chat.v1")],
+            on_connect: vec![RapidRecastAction::AuthBasedAction(
+                AuthBasedAction::CreateUser {
+                    subject: guest(),
+                    password: None,
+                },
+            )],
+            on_message: vec![
+                WebSocketMessageHandler {
+                    kind: WebSocketMessageKind::Text,
+                    condition: Some(ConditionStatement::Equals(
+                        RecastValue::Param(Cow::Borrowed("message")),
+                        RecastValue::String(Cow::Borrowed("ping")),
+                    )),
+                    actions: vec![metadata("last_message", "ping")],
+                },
+                WebSocketMessageHandler {
+                    kind: WebSocketMessageKind::Binary,
+                    condition: None,
+                    actions: vec![],
+                },
+            ],
+            on_close: vec![metadata("connected", "false")],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with a Kafka statement matching topics and consumer groups
+pub fn schema_with_kafka() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::KafkaProtocolDefinition(KafkaStatement {
+            sequence: 2,
+            operations: vec![KafkaOperation::Produce, KafkaOperation::Fetch],
+            topics: vec![
+                TopicObject::NamespaceTopic(
+                    Cow::Borrowed("some-namespace"),
+                    Cow::Borrowed("orders-*"),
+                ),
+                TopicObject::NonExistingTopic(Cow::Borrowed("some-namespace")),
+            ],
+            consumer_groups: vec![Cow::Borrowed("billing-*")],
+            actions: vec![RapidRecastAction::AuthBasedAction(
+                AuthBasedAction::GrantPermissions {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("some-namespace"),
+                        username: Cow::Borrowed("billing"),
+                    },
+                    policy: vec![RapidRecastRbacPolicy {
+                        subject: RapidRecastRbacSubject::UserOrRole(
+                            Cow::Borrowed("some-namespace"),
+                            Cow::Borrowed("billing"),
+                        ),
+                        object: RapidRecastRbacObject::Topic(TopicObject::NamespaceTopic(
+                            Cow::Borrowed("some-namespace"),
+                            Cow::Borrowed("orders-*"),
+                        )),
+                        action: RapidRecastRbacAction::Read,
+                    }],
+                },
+            )],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with a RabbitMQ statement declaring and binding exchanges and queues
+pub fn schema_with_rabbitmq() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::RabbitMQProtocolDefinition(AmqpStatement {
+            sequence: 3,
+            exchanges: vec![
+                AmqpExchange {
+                    name: Cow::Borrowed("orders"),
+                    exchange_type: AmqpExchangeType::Topic,
+                    durable: true,
+                },
+                AmqpExchange {
+                    name: Cow::Borrowed("audit"),
+                    exchange_type: AmqpExchangeType::Fanout,
+                    durable: false,
+                },
+            ],
+            queues: vec![
+                AmqpQueue {
+                    name: Cow::Borrowed("billing"),
+                    durable: true,
+                },
+                AmqpQueue {
+                    name: Cow::Borrowed("audit-log"),
+                    durable: false,
+                },
+            ],
+            bindings: vec![
+                AmqpBinding {
+                    queue: Cow::Borrowed("billing"),
+                    exchange: Cow::Borrowed("orders"),
+                    routing_key: Cow::Borrowed("order.*.created"),
+                },
+                AmqpBinding {
+                    queue: Cow::Borrowed("audit-log"),
+                    exchange: Cow::Borrowed("audit"),
+                    routing_key: Cow::Borrowed(""),
+                },
+            ],
+            on_publish: vec![
+                AmqpPublishHandler {
+                    exchange: Cow::Borrowed("orders"),
+                    routing_key: Some(Cow::Borrowed("order.#")),
+                    actions: vec![RapidRecastAction::AuthBasedAction(
+                        AuthBasedAction::CreateUser {
+                            subject: UserIdentifier {
+                                namespace: Cow::Borrowed("some-namespace"),
+                                username: Cow::Borrowed("customer"),
+                            },
+                            password: None,
+                        },
+                    )],
+                },
+                AmqpPublishHandler {
+                    exchange: Cow::Borrowed("audit"),
+                    routing_key: None,
+                    actions: vec![],
+                },
+            ],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with a gRPC statement matching services, methods and metadata
+pub fn schema_with_grpc() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::GrpcProtocolDefinition(GrpcStatement {
+            sequence: 4,
+            service: Cow::Borrowed("acme.users.v1.UserService"),
+            methods: vec![Cow::Borrowed("GetUser"), Cow::Borrowed("List*")],
+            streaming: Some(GrpcStreamingKind::ServerStreaming),
+            metadata: vec![
+                GrpcMetadataMatch {
+                    name: Cow::Borrowed("x-tenant"),
+                    value: Some(Cow::Borrowed("acme")),
+                },
+                GrpcMetadataMatch {
+                    name: Cow::Borrowed("authorization"),
+                    value: None,
+                },
+            ],
+            actions: vec![RapidRecastAction::AuthBasedAction(
+                AuthBasedAction::CreateUser {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("acme"),
+                        username: Cow::Borrowed("grpc-client"),
+                    },
+                    password: None,
+                },
+            )],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with an MQTT statement handling publishes and subscriptions
+pub fn schema_with_mqtt() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::MqttProtocolDefinition(MqttStatement {
+            sequence: 5,
+            handlers: vec![
+                MqttHandler {
+                    event: MqttEvent::Publish,
+                    topic_filter: Cow::Borrowed("sensors/+/temperature"),
+                    qos: Some(MqttQos::AtLeastOnce),
+                    retain: Some(true),
+                    actions: vec![RapidRecastAction::AuthBasedAction(
+                        AuthBasedAction::GrantPermissions {
+                            subject: UserIdentifier {
+                                namespace: Cow::Borrowed("sensors"),
+                                username: Cow::Borrowed("dashboard"),
+                            },
+                            policy: vec![RapidRecastRbacPolicy {
+                                subject: RapidRecastRbacSubject::UserOrRole(
+                                    Cow::Borrowed("sensors"),
+                                    Cow::Borrowed("dashboard"),
+                                ),
+                                object: RapidRecastRbacObject::MqttTopic(Cow::Borrowed(
+                                    "sensors/#",
+                                )),
+                                action: RapidRecastRbacAction::Read,
+                            }],
+                        },
+                    )],
+                },
+                MqttHandler {
+                    event: MqttEvent::Subscribe,
+                    topic_filter: Cow::Borrowed("sensors/#"),
+                    qos: Some(MqttQos::ExactlyOnce),
+                    retain: None,
+                    actions: vec![],
+                },
+            ],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with an HTTP statement matching the host, content type, headers, query and body size
+pub fn schema_with_http_matchers() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    schema.ast.push(RapidAstStatement::ProtocolDefinition(
+        RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+            sequence: 0,
+            paths: vec![Cow::Borrowed("/api/{*rest}")],
+            methods: vec![RapidRecastHttpMethod::POST],
+            min_body_bytes: Some(1),
+            max_body_bytes: Some(1 << 20),
+            host: Some(HttpValueMatch::Exact(Cow::Borrowed("api.example.com"))),
+            content_type: Some(HttpValueMatch::Prefix(Cow::Borrowed("application/json"))),
+            headers: vec![
+                HttpFieldMatch {
+                    name: Cow::Borrowed("x-api-version"),
+                    value: HttpValueMatch::Regex(HttpRegex::new(Cow::Borrowed("^2\\.[0-9]+$"))),
+                },
+                HttpFieldMatch {
+                    name: Cow::Borrowed("authorization"),
+                    value: HttpValueMatch::Present,
+                },
+            ],
+            query: vec![HttpFieldMatch {
+                name: Cow::Borrowed("dry_run"),
+                value: HttpValueMatch::Exact(Cow::Borrowed("true")),
+            }],
+            actions: vec![RapidRecastAction::AuthBasedAction(
+                AuthBasedAction::CreateUser {
+                    subject: UserIdentifier {
+                        namespace: Cow::Borrowed("api"),
+                        username: Cow::Borrowed("v2-client"),
+                    },
+                    password: None,
+                },
+            )],
+        }),
+    ));
+    schema
+}
+
+/// A fixture with HTTP statements that respond, redirect and serve files
+pub fn schema_with_responses() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let http = |sequence, path, actions| {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            HttpStatement {
+                sequence,
+                paths: vec![Cow::Borrowed(path)],
+                methods: vec![RapidRecastHttpMethod::GET],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions,
+            },
+        ))
+    };
+    schema.ast.push(http(
+        1,
+        "/users/{id:int}",
+        vec![RapidRecastAction::LogicBasedAction(
+            LogicBasedAction::ConditionBlock {
+                condition: ConditionStatement::GreaterThan(
+                    RecastValue::Param(Cow::Borrowed("id")),
+                    RecastValue::Number(1000.0),
+                ),
+                if_true: Box::new(RapidRecastAction::ResponseBasedAction(
+                    ResponseBasedAction::Redirect {
+                        status: 308,
+                        location: Cow::Borrowed("/archive/users/{id}"),
+                    },
+                )),
+                if_false: Some(Box::new(RapidRecastAction::ResponseBasedAction(
+                    ResponseBasedAction::Respond {
+                        status: 200,
+                        headers: vec![ResponseHeader {
+                            name: Cow::Borrowed("content-type"),
+                            value: Cow::Borrowed("application/json"),
+                        }],
+                        body: Some(ResponseBody::Template(Cow::Borrowed("{{\"id\": {id}}}"))),
+                    },
+                ))),
+            },
+        )],
+    ));
+    schema.ast.push(http(
+        2,
+        "/static/{*rest}",
+        vec![RapidRecastAction::ResponseBasedAction(
+            ResponseBasedAction::ServeFile {
+                root: Cow::Borrowed("/var/www"),
+                path: Cow::Borrowed("{rest}"),
+                content_type: Some(Cow::Borrowed("text/css")),
+            },
+        )],
+    ));
+    schema.ast.push(http(
+        3,
+        "/health",
+        vec![RapidRecastAction::ResponseBasedAction(
+            ResponseBasedAction::Respond {
+                status: 200,
+                headers: vec![],
+                body: Some(ResponseBody::Text(Cow::Borrowed("ok"))),
+            },
+        )],
+    ));
+    schema.ast.push(http(
+        4,
+        "/ready",
+        vec![RapidRecastAction::ResponseBasedAction(
+            ResponseBasedAction::Respond {
+                status: 204,
+                headers: vec![],
+                body: None,
+            },
+        )],
+    ));
+    schema
+}
+
+/// A fixture with HTTP statements forwarding to upstreams, with and without the optional settings
+pub fn schema_with_forward() -> RapidRecastDefinition<'static> {
+    let mut schema = bare_minimum_schema();
+    let http = |sequence, path, forward| {
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            HttpStatement {
+                sequence,
+                paths: vec![Cow::Borrowed(path)],
+                methods: vec![],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![RapidRecastAction::ForwardAction(forward)],
+            },
+        ))
+    };
+    schema.ast.push(http(
+        1,
+        "/api/users/{id}",
+        ForwardAction {
+            upstreams: vec![
+                Cow::Borrowed("http://users-a:8080"),
+                Cow::Borrowed("http://users-b:8080"),
+            ],
+            load_balancing: LoadBalancingStrategy::LeastConnections,
+            rewrite_path: Some(Cow::Borrowed("/v2/users/{id}")),
+            timeout_ms: Some(5000),
+            remove_headers: vec![Cow::Borrowed("cookie")],
+            add_headers: BTreeMap::from([(
+                Cow::Borrowed("x-forwarded-by"),
+                Cow::Borrowed("rapidrecast"),
+            )]),
+            retry: RetryPolicy {
+                attempts: 2,
+                backoff_ms: 100,
+                statuses: vec![502, 503],
+            },
+        },
+    ));
+    schema.ast.push(http(
+        2,
+        "/legacy/{*rest}",
+        ForwardAction {
+            upstreams: vec![Cow::Borrowed("https://legacy.example.com")],
+            load_balancing: LoadBalancingStrategy::RoundRobin,
+            rewrite_path: None,
+            timeout_ms: None,
+            remove_headers: vec![],
+            add_headers: BTreeMap::new(),
+            retry: RetryPolicy::default(),
+        },
+    ));
+    schema
+}
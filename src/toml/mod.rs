@@ -0,0 +1,63 @@
+//! Toml parser and writer for RapidRecast Definition Language
+//!
+//! Enums are single-key tables, which the toml crate reads directly. It does not write
+//! enum variants that carry data, though, and TOML has no null, so definitions are
+//! written through `serde_json::Value`, leaving absent optional fields out.
+//! The layout matches the JSON one.
+#[cfg(test)]
+mod test;
+
+use crate::ast::RapidRecastDefinition;
+use crate::error::RrdlError;
+use crate::{ParseRRDL, SaveRRDL};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::Write;
+
+/// A parser for the RapidRecastDefinition Language in TOML
+pub struct TomlRRDL {}
+
+impl ParseRRDL for TomlRRDL {
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError> {
+        // The toml crate copies every string, so nothing is borrowed from the input.
+        let deserializer = toml::Deserializer::new(input);
+        let definition = RapidRecastDefinition::deserialize(deserializer)
+            .map_err(|e| RrdlError::from_toml(e, input))?;
+        definition.validate().map_err(|e| e.located_in(input))?;
+        Ok(definition)
+    }
+}
+
+impl SaveRRDL for TomlRRDL {
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        mut writer: W,
+    ) -> Result<(), RrdlError> {
+        // The toml crate only serializes to strings, so the document is written out at once.
+        let value = strip_nulls(serde_json::to_value(definition).map_err(RrdlError::from)?);
+        let value = toml::Value::try_from(value).map_err(RrdlError::from)?;
+        let document = toml::to_string(&value).map_err(RrdlError::from)?;
+        writer
+            .write_all(document.as_bytes())
+            .map_err(RrdlError::from)
+    }
+}
+
+/// Remove the members of every object that are null, which TOML cannot represent.
+fn strip_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, strip_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(strip_nulls).collect()),
+        value => value,
+    }
+}
@@ -0,0 +1,372 @@
+use crate::ast::RapidRecastDefinition;
+use crate::test::{
+    bare_minimum_schema, schema_with_auth_actions, schema_with_cron, schema_with_forward,
+    schema_with_grpc, schema_with_http_matchers, schema_with_kafka, schema_with_model,
+    schema_with_mqtt, schema_with_proto_definition, schema_with_rabbitmq, schema_with_responses,
+    schema_with_topic, schema_with_websocket,
+};
+use crate::toml::TomlRRDL;
+use crate::{ParseRRDL, SaveRRDL};
+use toml::Value;
+
+/// Save a definition, check it reads back the same, and return the saved layout.
+fn layout(definition: &RapidRecastDefinition) -> serde_json::Value {
+    let saved = String::from_utf8(TomlRRDL {}.save_rrdl(definition).unwrap()).unwrap();
+    assert_eq!(&TomlRRDL {}.parse_rrdl(&saved).unwrap(), definition);
+    toml::from_str(&saved).unwrap()
+}
+
+#[test]
+pub fn test_basic_save() {
+    let definition = bare_minimum_schema();
+    let res = TomlRRDL {}.save_rrdl(&definition);
+    let res_str = String::from_utf8(res.unwrap()).unwrap();
+    let res: Value = toml::from_str(&res_str).unwrap();
+    let expected_str = r#"
+    id = "unique-schema-id-123"
+    language_version = "1.2.3"
+    file_version = "4.5.6"
+    name = "some name"
+    description = "some description"
+    ast = []
+    "#;
+    let expected: Value = toml::from_str(expected_str).unwrap();
+    assert!(expected.is_table());
+    assert!(res.is_table());
+    assert_eq!(res, expected);
+}
+
+#[test]
+pub fn test_save_protocol_definition() {
+    let definition = schema_with_proto_definition();
+    let res = TomlRRDL {}.save_rrdl(&definition);
+    let res_str = String::from_utf8(res.unwrap()).unwrap();
+    let res: Value = toml::from_str(&res_str).unwrap();
+    let expected_str = r#"
+    id = "unique-schema-id-123"
+    language_version = "1.2.3"
+    file_version = "4.5.6"
+    name = "some name"
+    description = "some description"
+
+    [[ast]]
+    [ast.ProtocolDefinition.HttpProtocolDefinition]
+    sequence = 0
+    paths = ["/"]
+    methods = ["GET"]
+    actions = []
+    "#;
+    let expected: Value = toml::from_str(expected_str).unwrap();
+    assert_eq!(res, expected);
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let definition = schema_with_auth_actions();
+    let res = TomlRRDL {}.save_rrdl(&definition);
+    let res_str = String::from_utf8(res.unwrap()).unwrap();
+    let res: Value = toml::from_str(&res_str).unwrap();
+    let expected_str = r#"
+    id = "unique-schema-id-123"
+    language_version = "1.2.3"
+    file_version = "4.5.6"
+    name = "some name"
+    description = "some description"
+
+    [[ast]]
+    [ast.ProtocolDefinition.HttpProtocolDefinition]
+    sequence = 0
+    paths = ["/create-user"]
+    methods = ["PUT", "POST", "GET"]
+
+    [[ast.ProtocolDefinition.HttpProtocolDefinition.actions]]
+    AuthBasedAction.CreateUser.subject = { namespace = "some-namespace", username = "some-username" }
+    AuthBasedAction.CreateUser.password = "some-password"
+
+    [[ast.ProtocolDefinition.HttpProtocolDefinition.actions]]
+    AuthBasedAction.AddMetadataToUser.subject = { namespace = "some-namespace", username = "some-username" }
+    AuthBasedAction.AddMetadataToUser.metadata = { key1 = "value1" }
+
+    [[ast.ProtocolDefinition.HttpProtocolDefinition.actions]]
+    AuthBasedAction.GrantPermissions.subject = { namespace = "some-namespace", username = "some-username" }
+    AuthBasedAction.GrantPermissions.policy = [
+        { subject = "Admin", action = "Create", object = { Topic = { NonExistingTopic = "some-namespace" } } },
+    ]
+    "#;
+    let expected: Value = toml::from_str(expected_str).unwrap();
+    assert_eq!(res, expected);
+    assert_eq!(TomlRRDL {}.parse_rrdl(&res_str).unwrap(), definition);
+}
+
+#[test]
+pub fn model_layout() {
+    // TOML has no null, so the absent default is left out
+    let value = layout(&schema_with_model());
+    let fields = &value["ast"][0]["ModelDefinition"]["fields"];
+    assert_eq!(
+        fields[1],
+        serde_json::json!({
+            "name": "age",
+            "field_type": { "Scalar": "Integer" },
+            "required": false,
+            "constraints": [{ "Min": 0.0 }, { "Max": 150.0 }]
+        })
+    );
+    assert_eq!(fields[7]["default"], serde_json::json!({ "List": [] }));
+    assert_eq!(
+        fields[9]["field_type"],
+        serde_json::json!({ "List": { "List": { "Scalar": "Float" } } })
+    );
+}
+
+#[test]
+pub fn topic_layout() {
+    let value = layout(&schema_with_topic());
+    let topic = &value["ast"][0]["TopicDefinition"];
+    assert_eq!(
+        topic["retention"],
+        serde_json::json!({ "time_ms": 604800000, "size_bytes": 10737418240u64 })
+    );
+    assert_eq!(
+        topic["key_schema"],
+        serde_json::json!({ "namespace": "some-namespace", "name": "UserKey" })
+    );
+    assert_eq!(
+        topic["acl"],
+        serde_json::json!([
+            { "subject": "Anon", "action": "Read" },
+            {
+                "subject": { "UserOrRole": ["some-namespace", "some-username"] },
+                "action": "Write"
+            }
+        ])
+    );
+}
+
+#[test]
+pub fn cron_layout() {
+    // TOML has no null, so the absent password is left out
+    let value = layout(&schema_with_cron());
+    let cron = &value["ast"][0]["CronDefinition"];
+    assert_eq!(cron["schedule"], serde_json::json!("30 2 * * mon-fri"));
+    assert_eq!(cron["timezone"], serde_json::json!("Europe/London"));
+    assert_eq!(cron["jitter_ms"], serde_json::json!(300000));
+    assert_eq!(cron["overlap"], serde_json::json!("Skip"));
+    assert_eq!(
+        cron["actions"][0],
+        serde_json::json!({
+            "AuthBasedAction": {
+                "CreateUser": {
+                    "subject": { "namespace": "some-namespace", "username": "cleanup-bot" }
+                }
+            }
+        })
+    );
+}
+
+#[test]
+pub fn websocket_layout() {
+    // TOML has no null, so the binary handler's absent condition is left out
+    let value = layout(&schema_with_websocket());
+    let websocket = &value["ast"][0]["ProtocolDefinition"]["WebSocketProtocolDefinition"];
+    assert_eq!(
+        websocket["subprotocols"],
+        serde_json::json!(["chat.v2", "chat.v1"])
+    );
+    assert_eq!(
+        websocket["on_message"][0]["condition"],
+        serde_json::json!({ "Equals": [{ "Param": "message" }, { "String": "ping" }] })
+    );
+    assert_eq!(
+        websocket["on_message"][1],
+        serde_json::json!({ "kind": "Binary", "actions": [] })
+    );
+}
+
+#[test]
+pub fn kafka_layout() {
+    let value = layout(&schema_with_kafka());
+    let kafka = &value["ast"][0]["ProtocolDefinition"]["KafkaProtocolDefinition"];
+    assert_eq!(kafka["operations"], serde_json::json!(["Produce", "Fetch"]));
+    assert_eq!(
+        kafka["topics"],
+        serde_json::json!([
+            { "NamespaceTopic": ["some-namespace", "orders-*"] },
+            { "NonExistingTopic": "some-namespace" }
+        ])
+    );
+    assert_eq!(kafka["consumer_groups"], serde_json::json!(["billing-*"]));
+}
+
+#[test]
+pub fn rabbitmq_layout() {
+    // TOML has no null, so the audit handler's absent routing key is left out
+    let value = layout(&schema_with_rabbitmq());
+    let amqp = &value["ast"][0]["ProtocolDefinition"]["RabbitMQProtocolDefinition"];
+    assert_eq!(
+        amqp["exchanges"][0],
+        serde_json::json!({ "name": "orders", "exchange_type": "Topic", "durable": true })
+    );
+    assert_eq!(
+        amqp["queues"][1],
+        serde_json::json!({ "name": "audit-log", "durable": false })
+    );
+    assert_eq!(
+        amqp["bindings"][0],
+        serde_json::json!({
+            "queue": "billing",
+            "exchange": "orders",
+            "routing_key": "order.*.created"
+        })
+    );
+    assert_eq!(
+        amqp["on_publish"][1],
+        serde_json::json!({ "exchange": "audit", "actions": [] })
+    );
+}
+
+#[test]
+pub fn grpc_layout() {
+    // TOML has no null, so the authorization entry's absent value is left out
+    let value = layout(&schema_with_grpc());
+    let grpc = &value["ast"][0]["ProtocolDefinition"]["GrpcProtocolDefinition"];
+    assert_eq!(
+        grpc["service"],
+        serde_json::json!("acme.users.v1.UserService")
+    );
+    assert_eq!(grpc["methods"], serde_json::json!(["GetUser", "List*"]));
+    assert_eq!(grpc["streaming"], serde_json::json!("ServerStreaming"));
+    assert_eq!(
+        grpc["metadata"],
+        serde_json::json!([{ "name": "x-tenant", "value": "acme" }, { "name": "authorization" }])
+    );
+}
+
+#[test]
+pub fn mqtt_layout() {
+    // TOML has no null, so the subscribe handler's absent retain flag is left out
+    let value = layout(&schema_with_mqtt());
+    let handlers = &value["ast"][0]["ProtocolDefinition"]["MqttProtocolDefinition"]["handlers"];
+    assert_eq!(
+        handlers[0]["topic_filter"],
+        serde_json::json!("sensors/+/temperature")
+    );
+    assert_eq!(handlers[0]["qos"], serde_json::json!("AtLeastOnce"));
+    assert_eq!(handlers[0]["retain"], serde_json::json!(true));
+    assert_eq!(
+        handlers[0]["actions"][0]["AuthBasedAction"]["GrantPermissions"]["policy"][0]["object"],
+        serde_json::json!({ "MqttTopic": "sensors/#" })
+    );
+    assert_eq!(
+        handlers[1],
+        serde_json::json!({
+            "event": "Subscribe",
+            "topic_filter": "sensors/#",
+            "qos": "ExactlyOnce",
+            "actions": []
+        })
+    );
+}
+
+#[test]
+pub fn http_matchers_layout() {
+    let value = layout(&schema_with_http_matchers());
+    let http = &value["ast"][0]["ProtocolDefinition"]["HttpProtocolDefinition"];
+    assert_eq!(
+        http["host"],
+        serde_json::json!({ "Exact": "api.example.com" })
+    );
+    assert_eq!(
+        http["content_type"],
+        serde_json::json!({ "Prefix": "application/json" })
+    );
+    assert_eq!(
+        http["headers"],
+        serde_json::json!([
+            { "name": "x-api-version", "value": { "Regex": "^2\\.[0-9]+$" } },
+            { "name": "authorization", "value": "Present" }
+        ])
+    );
+    assert_eq!(
+        http["query"],
+        serde_json::json!([{ "name": "dry_run", "value": { "Exact": "true" } }])
+    );
+    assert_eq!(http["min_body_bytes"], serde_json::json!(1));
+    assert_eq!(http["max_body_bytes"], serde_json::json!(1048576));
+}
+
+#[test]
+pub fn responses_layout() {
+    // TOML has no null, so the empty response's absent body is left out
+    let value = layout(&schema_with_responses());
+    let action =
+        |i: usize| &value["ast"][i]["ProtocolDefinition"]["HttpProtocolDefinition"]["actions"][0];
+    assert_eq!(
+        action(0)["LogicBasedAction"]["ConditionBlock"]["if_true"],
+        serde_json::json!({
+            "ResponseBasedAction": { "Redirect": { "status": 308, "location": "/archive/users/{id}" } }
+        })
+    );
+    assert_eq!(
+        action(0)["LogicBasedAction"]["ConditionBlock"]["if_false"],
+        serde_json::json!({
+            "ResponseBasedAction": {
+                "Respond": {
+                    "status": 200,
+                    "headers": [{ "name": "content-type", "value": "application/json" }],
+                    "body": { "Template": "{{\"id\": {id}}}" }
+                }
+            }
+        })
+    );
+    assert_eq!(
+        *action(1),
+        serde_json::json!({
+            "ResponseBasedAction": {
+                "ServeFile": {
+                    "root": "/var/www",
+                    "path": "{rest}",
+                    "content_type": "text/css"
+                }
+            }
+        })
+    );
+    assert_eq!(
+        *action(3),
+        serde_json::json!({
+            "ResponseBasedAction": { "Respond": { "status": 204, "headers": [] } }
+        })
+    );
+}
+
+#[test]
+pub fn forward_layout() {
+    // TOML has no null, so the legacy upstream's absent rewrite and timeout are left out
+    let value = layout(&schema_with_forward());
+    let forward = |i: usize| {
+        &value["ast"][i]["ProtocolDefinition"]["HttpProtocolDefinition"]["actions"][0]
+            ["ForwardAction"]
+    };
+    assert_eq!(
+        *forward(0),
+        serde_json::json!({
+            "upstreams": ["http://users-a:8080", "http://users-b:8080"],
+            "load_balancing": "LeastConnections",
+            "rewrite_path": "/v2/users/{id}",
+            "timeout_ms": 5000,
+            "remove_headers": ["cookie"],
+            "add_headers": { "x-forwarded-by": "rapidrecast" },
+            "retry": { "attempts": 2, "backoff_ms": 100, "statuses": [502, 503] }
+        })
+    );
+    assert_eq!(
+        *forward(1),
+        serde_json::json!({
+            "upstreams": ["https://legacy.example.com"],
+            "load_balancing": "RoundRobin",
+            "remove_headers": [],
+            "add_headers": {},
+            "retry": { "attempts": 0, "backoff_ms": 0, "statuses": [] }
+        })
+    );
+}
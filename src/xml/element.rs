@@ -0,0 +1,255 @@
+//! A minimal element tree between quick-xml events and the AST.
+
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::cell::RefCell;
+use std::io::Write;
+
+/// An XML element with its attributes, child elements and text.
+///
+/// Text is the concatenation of the element's text and CDATA content; whitespace-only
+/// text between child elements is dropped.
+/// Attributes that are looked up are remembered, so those nothing asked for can be reported.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    /// Indices of the attributes that have been looked up
+    read: RefCell<Vec<usize>>,
+    pub(crate) children: Vec<Element>,
+    pub(crate) text: String,
+    /// Byte offset of the element's start tag in the source
+    pub(crate) offset: usize,
+}
+
+/// Something wrong with the document, at a byte offset into the source.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct XmlProblem {
+    pub(crate) offset: usize,
+    pub(crate) message: String,
+}
+
+impl Element {
+    pub(crate) fn new(name: &str) -> Self {
+        Element {
+            name: name.to_string(),
+            ..Element::default()
+        }
+    }
+
+    pub(crate) fn with_attribute(mut self, key: &str, value: impl ToString) -> Self {
+        self.attributes.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub(crate) fn with_child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub(crate) fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    pub(crate) fn problem(&self, message: impl Into<String>) -> XmlProblem {
+        XmlProblem {
+            offset: self.offset,
+            message: message.into(),
+        }
+    }
+
+    /// The problem to report when this element is not allowed where it is.
+    pub(crate) fn unexpected(&self, parent: &Element) -> XmlProblem {
+        self.problem(format!(
+            "Unexpected element <{}> in <{}>",
+            self.name, parent.name
+        ))
+    }
+
+    pub(crate) fn attribute(&self, key: &str) -> Option<&str> {
+        let index = self.attributes.iter().position(|(k, _)| k == key)?;
+        self.read.borrow_mut().push(index);
+        Some(self.attributes[index].1.as_str())
+    }
+
+    /// Fail on the first attribute, in document order, that was never looked up.
+    pub(crate) fn expect_attributes_read(&self) -> Result<(), XmlProblem> {
+        let read = self.read.borrow();
+        let unread = (0..self.attributes.len()).find(|index| !read.contains(index));
+        if let Some(index) = unread {
+            return Err(self.problem(format!(
+                "Unknown attribute '{}' on <{}>",
+                self.attributes[index].0, self.name
+            )));
+        }
+        self.children
+            .iter()
+            .try_for_each(Element::expect_attributes_read)
+    }
+
+    pub(crate) fn required_attribute(&self, key: &str) -> Result<&str, XmlProblem> {
+        self.attribute(key)
+            .ok_or_else(|| self.problem(format!("Missing attribute '{}' on <{}>", key, self.name)))
+    }
+
+    /// The single child element, for wrappers such as `<then>` that hold exactly one element.
+    pub(crate) fn only_child(&self) -> Result<&Element, XmlProblem> {
+        match self.children.as_slice() {
+            [child] => Ok(child),
+            _ => Err(self.problem(format!(
+                "Expected exactly one element in <{}>, found {}",
+                self.name,
+                self.children.len()
+            ))),
+        }
+    }
+}
+
+/// Read a document into its root element.
+pub(crate) fn read_document(input: &str) -> Result<Element, XmlProblem> {
+    let mut reader = Reader::from_str(input);
+    let problem = |reader: &Reader<&[u8]>, message: String| XmlProblem {
+        offset: reader.buffer_position(),
+        message,
+    };
+    let mut stack: Vec<Element> = vec![];
+    let mut root = None;
+    loop {
+        let offset = reader.buffer_position();
+        let event = reader
+            .read_event()
+            .map_err(|e| problem(&reader, e.to_string()))?;
+        match event {
+            Event::Start(start) => {
+                if root.is_some() && stack.is_empty() {
+                    return Err(second_root(offset));
+                }
+                stack.push(element(&start, offset, &reader)?);
+            }
+            Event::Empty(start) => {
+                if root.is_some() && stack.is_empty() {
+                    return Err(second_root(offset));
+                }
+                let element = element(&start, offset, &reader)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let mut element = stack.pop().expect("quick-xml checks end tags");
+                if !element.children.is_empty() && element.text.trim().is_empty() {
+                    element.text.clear();
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| problem(&reader, e.to_string()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.text.push_str(&text),
+                    None if text.trim().is_empty() => {}
+                    None => {
+                        return Err(XmlProblem {
+                            offset,
+                            message: "Text is not allowed outside of the root element".to_string(),
+                        })
+                    }
+                }
+            }
+            Event::CData(data) => {
+                let data = String::from_utf8_lossy(&data).to_string();
+                if let Some(parent) = stack.last_mut() {
+                    parent.text.push_str(&data);
+                }
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+    match (root, stack.pop()) {
+        (_, Some(unclosed)) => {
+            Err(unclosed.problem(format!("Element <{}> is never closed", unclosed.name)))
+        }
+        (Some(root), None) => Ok(root),
+        (None, None) => Err(XmlProblem {
+            offset: 0,
+            message: "The document has no root element".to_string(),
+        }),
+    }
+}
+
+fn second_root(offset: usize) -> XmlProblem {
+    XmlProblem {
+        offset,
+        message: "Only one root element is allowed".to_string(),
+    }
+}
+
+fn element(
+    start: &BytesStart,
+    offset: usize,
+    reader: &Reader<&[u8]>,
+) -> Result<Element, XmlProblem> {
+    let problem = |message: String| XmlProblem { offset, message };
+    let name =
+        String::from_utf8(start.name().as_ref().to_vec()).map_err(|e| problem(e.to_string()))?;
+    let mut attributes = vec![];
+    for attribute in start.attributes().with_checks(false) {
+        let attribute = attribute.map_err(|e| problem(e.to_string()))?;
+        let key = String::from_utf8(attribute.key.as_ref().to_vec())
+            .map_err(|e| problem(e.to_string()))?;
+        if attributes.iter().any(|(k, _)| *k == key) {
+            return Err(problem(format!(
+                "Duplicate attribute '{}' on <{}>",
+                key, name
+            )));
+        }
+        let value = attribute
+            .decode_and_unescape_value(reader)
+            .map_err(|e| problem(e.to_string()))?;
+        attributes.push((key, value.to_string()));
+    }
+    Ok(Element {
+        name,
+        attributes,
+        offset,
+        ..Element::default()
+    })
+}
+
+/// Write an element as an indented document.
+pub(crate) fn write_document<W: Write>(root: &Element, out: W) -> Result<(), quick_xml::Error> {
+    let mut writer = Writer::new_with_indent(out, b' ', 4);
+    write_element(&mut writer, root)?;
+    writer.inner().write_all(b"\n")?;
+    Ok(())
+}
+
+fn write_element<W: Write>(
+    writer: &mut Writer<W>,
+    element: &Element,
+) -> Result<(), quick_xml::Error> {
+    let start = BytesStart::new(element.name.as_str()).with_attributes(
+        element
+            .attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str())),
+    );
+    if element.children.is_empty() && element.text.is_empty() {
+        return writer.write_event(Event::Empty(start));
+    }
+    writer.write_event(Event::Start(start))?;
+    if !element.text.is_empty() {
+        writer.write_event(Event::Text(BytesText::new(&element.text)))?;
+    }
+    for child in &element.children {
+        write_element(writer, child)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new(element.name.as_str())))
+}
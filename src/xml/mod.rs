@@ -0,0 +1,196 @@
+//! Xml parser and writer for RapidRecast Definition Language
+//!
+//! The AST maps to elements and attributes as follows:
+//!
+//! ```xml
+//! <definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6">
+//!     <name>some name</name>
+//!     <description>some description</description>
+//!     <http sequence="0" max_body_bytes="1048576">
+//!         <path>/create-user</path>
+//!         <method>PUT</method>
+//!         <method>POST</method>
+//!         <host exact="api.example.com"/>
+//!         <content_type prefix="application/json"/>
+//!         <header name="x-api-version" regex="^2\.[0-9]+$"/>
+//!         <query name="dry_run"/>
+//!         <actions>
+//!             <create_user namespace="some-namespace" username="some-username" password="some-password"/>
+//!             <add_metadata namespace="some-namespace" username="some-username">
+//!                 <entry key="key1">value1</entry>
+//!             </add_metadata>
+//!             <grant namespace="some-namespace" username="some-username">
+//!                 <policy action="create">
+//!                     <admin/>
+//!                     <topic name="some-namespace"/>
+//!                 </policy>
+//!             </grant>
+//!             <if>
+//!                 <equals>
+//!                     <param>role</param>
+//!                     <string>admin</string>
+//!                 </equals>
+//!                 <then>...</then>
+//!                 <else>...</else>
+//!             </if>
+//!             <respond status="200">
+//!                 <header name="content-type">application/json</header>
+//!                 <body template="true">{{"id": {id}}}</body>
+//!             </respond>
+//!             <redirect status="308" location="/users/{id}"/>
+//!             <serve_file root="/var/www" path="{rest}" content_type="text/css"/>
+//!             <forward load_balancing="least_connections" rewrite_path="/v2/users/{id}" timeout_ms="5000">
+//!                 <upstream>http://users-a:8080</upstream>
+//!                 <upstream>http://users-b:8080</upstream>
+//!                 <remove_header name="cookie"/>
+//!                 <add_header name="x-forwarded-by">rapidrecast</add_header>
+//!                 <retry attempts="2" backoff_ms="100">
+//!                     <status code="502"/>
+//!                 </retry>
+//!             </forward>
+//!         </actions>
+//!     </http>
+//!     <websocket sequence="1" path="/chat">
+//!         <subprotocol>chat.v2</subprotocol>
+//!         <on_connect>...</on_connect>
+//!         <on_message kind="text">
+//!             <when><equals>...</equals></when>
+//!             ...
+//!         </on_message>
+//!         <on_close>...</on_close>
+//!     </websocket>
+//!     <kafka sequence="2">
+//!         <operation>produce</operation>
+//!         <topic namespace="some-namespace" name="orders-*"/>
+//!         <consumer_group>billing-*</consumer_group>
+//!         <actions>...</actions>
+//!     </kafka>
+//!     <rabbitmq sequence="3">
+//!         <exchange name="orders" type="topic" durable="true"/>
+//!         <queue name="billing" durable="true"/>
+//!         <binding queue="billing" exchange="orders" routing_key="order.*.created"/>
+//!         <on_publish exchange="orders" routing_key="order.#">...</on_publish>
+//!     </rabbitmq>
+//!     <grpc sequence="4" service="acme.users.v1.UserService" streaming="server">
+//!         <method>GetUser</method>
+//!         <metadata name="x-tenant" value="acme"/>
+//!         <actions>...</actions>
+//!     </grpc>
+//!     <mqtt sequence="5">
+//!         <on_publish filter="sensors/+/temperature" qos="1" retain="true">...</on_publish>
+//!         <on_subscribe filter="sensors/#">...</on_subscribe>
+//!     </mqtt>
+//!     <model namespace="some-namespace" name="User">
+//!         <field name="age" required="false">
+//!             <integer/>
+//!             <default>
+//!                 <number>18</number>
+//!             </default>
+//!             <min>0</min>
+//!         </field>
+//!         <field name="tags">
+//!             <list>
+//!                 <string/>
+//!             </list>
+//!             <one_of>...</one_of>
+//!         </field>
+//!     </model>
+//!     <topic namespace="some-namespace" name="users" partitions="12" compacted="true">
+//!         <retention time_ms="604800000" size_bytes="10737418240"/>
+//!         <key namespace="some-namespace" name="UserKey"/>
+//!         <value namespace="some-namespace" name="User"/>
+//!         <acl action="read">
+//!             <anon/>
+//!         </acl>
+//!     </topic>
+//!     <cron namespace="some-namespace" name="nightly-cleanup" schedule="30 2 * * mon-fri"
+//!           timezone="Europe/London" jitter_ms="300000" overlap="skip">
+//!         <actions>...</actions>
+//!     </cron>
+//! </definition>
+//! ```
+//!
+//! - Policy subjects are `<admin/>`, `<anon/>` or `<user namespace=".." username=".."/>`.
+//! - Policy objects are `<namespace name=".." exists="true|false"/>`, `<protocol type="http"/>`,
+//!   a topic, `<client type="http1"/>` and `<mqtt_topic filter="sensors/#"/>`. Topics, here and in `<kafka>`, are
+//!   `<topic name=".."/>` for a topic that does not exist yet, or `<topic namespace=".." name=".."/>`.
+//! - Conditions are `<equals>`, `<not_equals>`, `<greater_than>`, `<less_than>`,
+//!   `<greater_than_or_equal>`, `<less_than_or_equal>`, `<and>` and `<or>` holding two values,
+//!   or `<not>` holding one. Values are `<param>`, `<string>` or `<number>`.
+//! - A `<field>` starts with its type: `<string/>`, `<integer/>`, `<float/>`, `<bool/>`,
+//!   `<timestamp/>`, `<bytes/>`, `<list>` or `<map>` holding the item type, or
+//!   `<model namespace=".." name=".."/>`. Fields are required unless `required="false"`.
+//! - The type is followed by an optional `<default>` holding one model value, then constraints:
+//!   `<min>`, `<max>`, `<regex>` and `<one_of>` holding model values. Model values are
+//!   `<string>`, `<number>`, `<bool>` or `<list>` holding model values.
+//! - Topic attributes, `<retention>` and its attributes, `<key>` and `<value>` are optional.
+//!   Each `<acl>` holds one policy subject.
+//! - Cron `timezone`, `jitter_ms` and `overlap` (`allow`, `skip` or `queue`) are optional.
+//!   The schedule and timezone are checked when reading.
+//! - Exchange types are `direct`, `fanout`, `topic` or `headers`. Exchanges and queues are not
+//!   durable unless `durable="true"`, and routing keys are optional.
+//! - gRPC `streaming` is `unary`, `client`, `server` or `bidirectional`, and matches any kind if
+//!   absent. `<metadata>` without a `value` only requires the key to be present.
+//! - HTTP paths are templates such as `/users/{id:int}`, checked when reading.
+//! - `<host>`, `<content_type>`, `<header>` and `<query>` take at most one of `exact`, `prefix`
+//!   or `regex`, and only require the value to be present without one. Regexes are checked when
+//!   reading. `min_body_bytes` and `max_body_bytes` are optional.
+//! - MQTT `qos` is `0`, `1` or `2`, and `qos` and `retain` match anything if absent.
+//!   Topic filters are checked when reading.
+//! - `<on_connect>`, `<on_message>`, `<on_close>`, `<on_publish>` and `<on_subscribe>` hold
+//!   actions directly.
+//!   A message handler's kind is `text` or `binary`, and it may start with a `<when>` holding
+//!   one condition.
+//! - `<then>` and `<else>` hold exactly one action.
+//! - A `<respond>` holds any `<header>` elements, then an optional `<body>`. Bodies with
+//!   `template="true"`, redirect locations and served file paths fill `{name}` placeholders from
+//!   params, with `{{` and `}}` for literal braces. Statuses and templates are checked when reading.
+//! - A `<forward>` needs at least one `<upstream>` URL. `load_balancing` is `round_robin`, the
+//!   default, `random`, `least_connections` or `failover`. Without a `<retry>`, failed attempts
+//!   are not retried. Upstreams, the path rewrite and retried statuses are checked when reading.
+//! - Text content is taken as written, so surrounding whitespace is significant.
+#[cfg(test)]
+mod test;
+
+mod element;
+mod read;
+mod write;
+
+use crate::ast::RapidRecastDefinition;
+use crate::error::{RrdlError, SourceSpan};
+use crate::xml::element::XmlProblem;
+use crate::{ParseRRDL, SaveRRDL};
+use std::io::Write;
+
+/// A parser for the RapidRecastDefinition Language in XML
+pub struct XmlRRDL {}
+
+impl ParseRRDL for XmlRRDL {
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError> {
+        let to_error = |problem: XmlProblem| {
+            RrdlError::new(problem.message).with_span(SourceSpan::from_byte_range(
+                input,
+                problem.offset..problem.offset + 1,
+            ))
+        };
+        let root = element::read_document(input).map_err(to_error)?;
+        let definition = read::definition(&root).map_err(to_error)?;
+        root.expect_attributes_read().map_err(to_error)?;
+        definition.validate().map_err(|e| e.located_in(input))?;
+        Ok(definition)
+    }
+}
+
+impl SaveRRDL for XmlRRDL {
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        writer: W,
+    ) -> Result<(), RrdlError> {
+        let root = write::definition(definition);
+        element::write_document(&root, writer).map_err(RrdlError::from)
+    }
+}
@@ -0,0 +1,923 @@
+//! Conversion of XML elements into the AST.
+
+use crate::ast::action::{
+    add_header, header_name, AuthBasedAction, ClientObject, ConditionStatement, ForwardAction,
+    LoadBalancingStrategy, LogicBasedAction, NamespaceObject, RapidRecastAction,
+    RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy, RapidRecastRbacSubject,
+    RecastValue, ResponseBasedAction, ResponseBody, ResponseHeader, RetryPolicy, TopicObject,
+    UserIdentifier,
+};
+use crate::ast::cron::{parse_time_zone, CronOverlapPolicy, CronSchedule, RapidCronDefinition};
+use crate::ast::model::{
+    ModelConstraint, ModelField, ModelFieldType, ModelReference, ModelValue, RapidModelDefinition,
+    ScalarType,
+};
+use crate::ast::protocol::{
+    check_mqtt_topic_filter, AmqpBinding, AmqpExchange, AmqpExchangeType, AmqpPublishHandler,
+    AmqpQueue, AmqpStatement, GrpcMetadataMatch, GrpcStatement, GrpcStreamingKind, HttpFieldMatch,
+    HttpRegex, HttpStatement, HttpValueMatch, KafkaOperation, KafkaStatement, MqttEvent,
+    MqttHandler, MqttQos, MqttStatement, PathTemplate, RapidProtocolDefinition,
+    RapidRecastHttpMethod, RapidRecastProtocolType, WebSocketMessageHandler, WebSocketMessageKind,
+    WebSocketStatement,
+};
+use crate::ast::topic::{RapidTopicDefinition, TopicAcl, TopicRetention};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition, Version};
+use crate::xml::element::{Element, XmlProblem};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+fn owned<'a>(s: &str) -> Cow<'a, str> {
+    Cow::Owned(s.to_string())
+}
+
+fn parse_attribute<T: FromStr>(element: &Element, key: &str) -> Result<T, XmlProblem> {
+    let value = element.required_attribute(key)?;
+    value.parse().map_err(|_| {
+        element.problem(format!(
+            "Invalid value '{}' for attribute '{}' on <{}>",
+            value, key, element.name
+        ))
+    })
+}
+
+fn expect_empty(element: &Element) -> Result<(), XmlProblem> {
+    match element.children.first() {
+        Some(child) => Err(child.unexpected(element)),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn definition<'a>(element: &Element) -> Result<RapidRecastDefinition<'a>, XmlProblem> {
+    if element.name != "definition" {
+        return Err(element.problem(format!(
+            "Expected a <definition> root element, found <{}>",
+            element.name
+        )));
+    }
+    let version = |key: &str| -> Result<Version, XmlProblem> {
+        element
+            .required_attribute(key)?
+            .parse()
+            .map_err(|e: String| element.problem(format!("{} in '{}'", e, key)))
+    };
+    let mut definition = RapidRecastDefinition {
+        id: owned(element.required_attribute("id")?),
+        language_version: version("language_version")?,
+        file_version: version("file_version")?,
+        name: None,
+        description: None,
+        ast: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "name" if definition.name.is_none() => definition.name = Some(owned(&child.text)),
+            "description" if definition.description.is_none() => {
+                definition.description = Some(owned(&child.text))
+            }
+            "model" => definition
+                .ast
+                .push(RapidAstStatement::ModelDefinition(model_definition(child)?)),
+            "topic" => definition
+                .ast
+                .push(RapidAstStatement::TopicDefinition(topic_definition(child)?)),
+            "cron" => definition
+                .ast
+                .push(RapidAstStatement::CronDefinition(cron_definition(child)?)),
+            "http" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::HttpProtocolDefinition(http_statement(child)?),
+            )),
+            "websocket" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::WebSocketProtocolDefinition(websocket_statement(child)?),
+            )),
+            "kafka" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::KafkaProtocolDefinition(kafka_statement(child)?),
+            )),
+            "rabbitmq" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp_statement(child)?),
+            )),
+            "grpc" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::GrpcProtocolDefinition(grpc_statement(child)?),
+            )),
+            "mqtt" => definition.ast.push(RapidAstStatement::ProtocolDefinition(
+                RapidProtocolDefinition::MqttProtocolDefinition(mqtt_statement(child)?),
+            )),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(definition)
+}
+
+fn model_definition<'a>(element: &Element) -> Result<RapidModelDefinition<'a>, XmlProblem> {
+    Ok(RapidModelDefinition {
+        namespace: owned(element.required_attribute("namespace")?),
+        name: owned(element.required_attribute("name")?),
+        fields: element
+            .children
+            .iter()
+            .map(|field| match field.name.as_str() {
+                "field" => model_field(field),
+                _ => Err(field.unexpected(element)),
+            })
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn model_field<'a>(element: &Element) -> Result<ModelField<'a>, XmlProblem> {
+    let mut children = element.children.iter();
+    let field_type = children
+        .next()
+        .ok_or_else(|| element.problem("Missing type in <field>"))?;
+    let mut field = ModelField {
+        name: owned(element.required_attribute("name")?),
+        field_type: model_field_type(field_type)?,
+        required: match element.attribute("required") {
+            Some(_) => parse_attribute(element, "required")?,
+            None => true,
+        },
+        default: None,
+        constraints: vec![],
+    };
+    for child in children {
+        let number = || {
+            child
+                .text
+                .trim()
+                .parse()
+                .map_err(|_| child.problem(format!("Invalid number '{}'", child.text)))
+        };
+        match child.name.as_str() {
+            "default" if field.default.is_none() => {
+                field.default = Some(model_value(child.only_child()?)?)
+            }
+            "min" => field.constraints.push(ModelConstraint::Min(number()?)),
+            "max" => field.constraints.push(ModelConstraint::Max(number()?)),
+            "regex" => field
+                .constraints
+                .push(ModelConstraint::Regex(owned(&child.text))),
+            "one_of" => field.constraints.push(ModelConstraint::OneOf(
+                child
+                    .children
+                    .iter()
+                    .map(model_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    field.check().map_err(|e| element.problem(e.message))?;
+    Ok(field)
+}
+
+fn model_field_type<'a>(element: &Element) -> Result<ModelFieldType<'a>, XmlProblem> {
+    let scalar = |scalar| {
+        expect_empty(element)?;
+        Ok(ModelFieldType::Scalar(scalar))
+    };
+    match element.name.as_str() {
+        "string" => scalar(ScalarType::String),
+        "integer" => scalar(ScalarType::Integer),
+        "float" => scalar(ScalarType::Float),
+        "bool" => scalar(ScalarType::Bool),
+        "timestamp" => scalar(ScalarType::Timestamp),
+        "bytes" => scalar(ScalarType::Bytes),
+        "list" => Ok(ModelFieldType::List(Box::new(model_field_type(
+            element.only_child()?,
+        )?))),
+        "map" => Ok(ModelFieldType::Map(Box::new(model_field_type(
+            element.only_child()?,
+        )?))),
+        "model" => {
+            expect_empty(element)?;
+            Ok(ModelFieldType::Model(model_reference(element)?))
+        }
+        other => Err(element.problem(format!("Unknown field type <{}>", other))),
+    }
+}
+
+fn model_reference<'a>(element: &Element) -> Result<ModelReference<'a>, XmlProblem> {
+    Ok(ModelReference {
+        namespace: owned(element.required_attribute("namespace")?),
+        name: owned(element.required_attribute("name")?),
+    })
+}
+
+fn model_value<'a>(element: &Element) -> Result<ModelValue<'a>, XmlProblem> {
+    if element.name == "list" {
+        return Ok(ModelValue::List(
+            element
+                .children
+                .iter()
+                .map(model_value)
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+    expect_empty(element)?;
+    Ok(match element.name.as_str() {
+        "string" => ModelValue::String(owned(&element.text)),
+        "number" => ModelValue::Number(
+            element
+                .text
+                .trim()
+                .parse()
+                .map_err(|_| element.problem(format!("Invalid number '{}'", element.text)))?,
+        ),
+        "bool" => ModelValue::Bool(
+            element
+                .text
+                .trim()
+                .parse()
+                .map_err(|_| element.problem(format!("Invalid bool '{}'", element.text)))?,
+        ),
+        other => return Err(element.problem(format!("Unknown value <{}>", other))),
+    })
+}
+
+fn topic_definition<'a>(element: &Element) -> Result<RapidTopicDefinition<'a>, XmlProblem> {
+    let mut topic = RapidTopicDefinition {
+        namespace: owned(element.required_attribute("namespace")?),
+        name: owned(element.required_attribute("name")?),
+        partitions: match element.attribute("partitions") {
+            Some(_) => Some(parse_attribute(element, "partitions")?),
+            None => None,
+        },
+        retention: TopicRetention::default(),
+        compacted: match element.attribute("compacted") {
+            Some(_) => parse_attribute(element, "compacted")?,
+            None => false,
+        },
+        key_schema: None,
+        value_schema: None,
+        acl: vec![],
+    };
+    let mut retained = false;
+    for child in &element.children {
+        match child.name.as_str() {
+            "retention" if !retained => {
+                retained = true;
+                expect_empty(child)?;
+                if child.attribute("time_ms").is_some() {
+                    topic.retention.time_ms = Some(parse_attribute(child, "time_ms")?);
+                }
+                if child.attribute("size_bytes").is_some() {
+                    topic.retention.size_bytes = Some(parse_attribute(child, "size_bytes")?);
+                }
+            }
+            "key" if topic.key_schema.is_none() => {
+                expect_empty(child)?;
+                topic.key_schema = Some(model_reference(child)?)
+            }
+            "value" if topic.value_schema.is_none() => {
+                expect_empty(child)?;
+                topic.value_schema = Some(model_reference(child)?)
+            }
+            "acl" => topic.acl.push(TopicAcl {
+                subject: rbac_subject(child.only_child()?)?,
+                action: rbac_action(child)?,
+            }),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(topic)
+}
+
+fn cron_definition<'a>(element: &Element) -> Result<RapidCronDefinition<'a>, XmlProblem> {
+    let schedule = element.required_attribute("schedule")?;
+    CronSchedule::parse(schedule).map_err(|e| element.problem(e.message))?;
+    let timezone = element.attribute("timezone");
+    if let Some(timezone) = timezone {
+        parse_time_zone(timezone).map_err(|e| element.problem(e.message))?;
+    }
+    let mut cron = RapidCronDefinition {
+        namespace: owned(element.required_attribute("namespace")?),
+        name: owned(element.required_attribute("name")?),
+        schedule: owned(schedule),
+        timezone: timezone.map(owned),
+        jitter_ms: match element.attribute("jitter_ms") {
+            Some(_) => parse_attribute(element, "jitter_ms")?,
+            None => 0,
+        },
+        overlap: match element.attribute("overlap") {
+            None | Some("allow") => CronOverlapPolicy::Allow,
+            Some("skip") => CronOverlapPolicy::Skip,
+            Some("queue") => CronOverlapPolicy::Queue,
+            Some(other) => {
+                return Err(element.problem(format!("Unknown overlap policy '{}'", other)))
+            }
+        },
+        actions: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "actions" => cron.actions.extend(actions(child)?),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(cron)
+}
+
+fn http_statement<'a>(element: &Element) -> Result<HttpStatement<'a>, XmlProblem> {
+    let mut http = HttpStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        paths: vec![],
+        methods: vec![],
+        min_body_bytes: match element.attribute("min_body_bytes") {
+            Some(_) => Some(parse_attribute(element, "min_body_bytes")?),
+            None => None,
+        },
+        max_body_bytes: match element.attribute("max_body_bytes") {
+            Some(_) => Some(parse_attribute(element, "max_body_bytes")?),
+            None => None,
+        },
+        host: None,
+        content_type: None,
+        headers: vec![],
+        query: vec![],
+        actions: vec![],
+    };
+    http.check_body_size()
+        .map_err(|e| element.problem(e.message))?;
+    for child in &element.children {
+        match child.name.as_str() {
+            "host" if http.host.is_none() => http.host = Some(http_value_match(child)?),
+            "content_type" if http.content_type.is_none() => {
+                http.content_type = Some(http_value_match(child)?)
+            }
+            "header" => http.headers.push(http_field_match(child)?),
+            "query" => http.query.push(http_field_match(child)?),
+            "path" => {
+                PathTemplate::parse(&child.text).map_err(|e| child.problem(e.message))?;
+                http.paths.push(owned(&child.text))
+            }
+            "method" => http.methods.push(http_method(child)?),
+            "actions" => http.actions.extend(actions(child)?),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(http)
+}
+
+fn http_field_match<'a>(element: &Element) -> Result<HttpFieldMatch<'a>, XmlProblem> {
+    Ok(HttpFieldMatch {
+        name: owned(element.required_attribute("name")?),
+        value: http_value_match(element)?,
+    })
+}
+
+fn http_value_match<'a>(element: &Element) -> Result<HttpValueMatch<'a>, XmlProblem> {
+    expect_empty(element)?;
+    let exact = element
+        .attribute("exact")
+        .map(|v| HttpValueMatch::Exact(owned(v)));
+    let prefix = element
+        .attribute("prefix")
+        .map(|v| HttpValueMatch::Prefix(owned(v)));
+    let regex = element
+        .attribute("regex")
+        .map(|v| HttpValueMatch::Regex(HttpRegex::new(owned(v))));
+    let mut matchers = [exact, prefix, regex].into_iter().flatten();
+    let value = matchers.next().unwrap_or(HttpValueMatch::Present);
+    if matchers.next().is_some() {
+        return Err(element.problem(format!(
+            "Expected at most one of 'exact', 'prefix' or 'regex' on <{}>",
+            element.name
+        )));
+    }
+    value.check().map_err(|e| element.problem(e.message))?;
+    Ok(value)
+}
+
+fn websocket_statement<'a>(element: &Element) -> Result<WebSocketStatement<'a>, XmlProblem> {
+    let mut websocket = WebSocketStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        path: owned(element.required_attribute("path")?),
+        subprotocols: vec![],
+        on_connect: vec![],
+        on_message: vec![],
+        on_close: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "subprotocol" => websocket.subprotocols.push(owned(&child.text)),
+            "on_connect" => websocket.on_connect.extend(actions(child)?),
+            "on_message" => websocket.on_message.push(websocket_message_handler(child)?),
+            "on_close" => websocket.on_close.extend(actions(child)?),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(websocket)
+}
+
+fn kafka_statement<'a>(element: &Element) -> Result<KafkaStatement<'a>, XmlProblem> {
+    let mut kafka = KafkaStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        operations: vec![],
+        topics: vec![],
+        consumer_groups: vec![],
+        actions: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "operation" => kafka.operations.push(match child.text.as_str() {
+                "produce" => KafkaOperation::Produce,
+                "fetch" => KafkaOperation::Fetch,
+                other => return Err(child.problem(format!("Unknown Kafka operation '{}'", other))),
+            }),
+            "topic" => kafka.topics.push(topic_object(child)?),
+            "consumer_group" => kafka.consumer_groups.push(owned(&child.text)),
+            "actions" => kafka.actions.extend(actions(child)?),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(kafka)
+}
+
+fn mqtt_statement<'a>(element: &Element) -> Result<MqttStatement<'a>, XmlProblem> {
+    let mut mqtt = MqttStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        handlers: vec![],
+    };
+    for child in &element.children {
+        let event = match child.name.as_str() {
+            "on_publish" => MqttEvent::Publish,
+            "on_subscribe" => MqttEvent::Subscribe,
+            _ => return Err(child.unexpected(element)),
+        };
+        mqtt.handlers.push(MqttHandler {
+            event,
+            topic_filter: mqtt_topic_filter(child)?,
+            qos: match child.attribute("qos") {
+                None => None,
+                Some("0") => Some(MqttQos::AtMostOnce),
+                Some("1") => Some(MqttQos::AtLeastOnce),
+                Some("2") => Some(MqttQos::ExactlyOnce),
+                Some(other) => return Err(child.problem(format!("Unknown QoS level '{}'", other))),
+            },
+            retain: match child.attribute("retain") {
+                Some(_) => Some(parse_attribute(child, "retain")?),
+                None => None,
+            },
+            actions: actions(child)?,
+        });
+    }
+    Ok(mqtt)
+}
+
+fn mqtt_topic_filter<'a>(element: &Element) -> Result<Cow<'a, str>, XmlProblem> {
+    let filter = element.required_attribute("filter")?;
+    check_mqtt_topic_filter(filter).map_err(|e| element.problem(e.message))?;
+    Ok(owned(filter))
+}
+
+fn amqp_statement<'a>(element: &Element) -> Result<AmqpStatement<'a>, XmlProblem> {
+    let durable = |element: &Element| match element.attribute("durable") {
+        Some(_) => parse_attribute(element, "durable"),
+        None => Ok(false),
+    };
+    let mut amqp = AmqpStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        exchanges: vec![],
+        queues: vec![],
+        bindings: vec![],
+        on_publish: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "exchange" => {
+                expect_empty(child)?;
+                amqp.exchanges.push(AmqpExchange {
+                    name: owned(child.required_attribute("name")?),
+                    exchange_type: match child.required_attribute("type")? {
+                        "direct" => AmqpExchangeType::Direct,
+                        "fanout" => AmqpExchangeType::Fanout,
+                        "topic" => AmqpExchangeType::Topic,
+                        "headers" => AmqpExchangeType::Headers,
+                        other => {
+                            return Err(child.problem(format!("Unknown exchange type '{}'", other)))
+                        }
+                    },
+                    durable: durable(child)?,
+                })
+            }
+            "queue" => {
+                expect_empty(child)?;
+                amqp.queues.push(AmqpQueue {
+                    name: owned(child.required_attribute("name")?),
+                    durable: durable(child)?,
+                })
+            }
+            "binding" => {
+                expect_empty(child)?;
+                amqp.bindings.push(AmqpBinding {
+                    queue: owned(child.required_attribute("queue")?),
+                    exchange: owned(child.required_attribute("exchange")?),
+                    routing_key: owned(child.attribute("routing_key").unwrap_or_default()),
+                })
+            }
+            "on_publish" => amqp.on_publish.push(AmqpPublishHandler {
+                exchange: owned(child.required_attribute("exchange")?),
+                routing_key: child.attribute("routing_key").map(owned),
+                actions: actions(child)?,
+            }),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(amqp)
+}
+
+fn grpc_statement<'a>(element: &Element) -> Result<GrpcStatement<'a>, XmlProblem> {
+    let mut grpc = GrpcStatement {
+        sequence: parse_attribute(element, "sequence")?,
+        service: owned(element.required_attribute("service")?),
+        methods: vec![],
+        streaming: match element.attribute("streaming") {
+            None => None,
+            Some("unary") => Some(GrpcStreamingKind::Unary),
+            Some("client") => Some(GrpcStreamingKind::ClientStreaming),
+            Some("server") => Some(GrpcStreamingKind::ServerStreaming),
+            Some("bidirectional") => Some(GrpcStreamingKind::Bidirectional),
+            Some(other) => {
+                return Err(element.problem(format!("Unknown gRPC streaming kind '{}'", other)))
+            }
+        },
+        metadata: vec![],
+        actions: vec![],
+    };
+    for child in &element.children {
+        match child.name.as_str() {
+            "method" => grpc.methods.push(owned(&child.text)),
+            "metadata" => {
+                expect_empty(child)?;
+                grpc.metadata.push(GrpcMetadataMatch {
+                    name: owned(child.required_attribute("name")?),
+                    value: child.attribute("value").map(owned),
+                })
+            }
+            "actions" => grpc.actions.extend(actions(child)?),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(grpc)
+}
+
+fn websocket_message_handler<'a>(
+    element: &Element,
+) -> Result<WebSocketMessageHandler<'a>, XmlProblem> {
+    let kind = match element.required_attribute("kind")? {
+        "text" => WebSocketMessageKind::Text,
+        "binary" => WebSocketMessageKind::Binary,
+        other => return Err(element.problem(format!("Unknown WebSocket message kind '{}'", other))),
+    };
+    let mut children = element.children.iter().peekable();
+    let condition = match children.next_if(|child| child.name == "when") {
+        Some(when) => Some(condition(when.only_child()?)?),
+        None => None,
+    };
+    Ok(WebSocketMessageHandler {
+        kind,
+        condition,
+        actions: children.map(action).collect::<Result<_, _>>()?,
+    })
+}
+
+fn http_method(element: &Element) -> Result<RapidRecastHttpMethod, XmlProblem> {
+    Ok(match element.text.as_str() {
+        "GET" => RapidRecastHttpMethod::GET,
+        "POST" => RapidRecastHttpMethod::POST,
+        "DELETE" => RapidRecastHttpMethod::DELETE,
+        "UPDATE" => RapidRecastHttpMethod::UPDATE,
+        "PATCH" => RapidRecastHttpMethod::PATCH,
+        "PUT" => RapidRecastHttpMethod::PUT,
+        "OPTIONS" => RapidRecastHttpMethod::OPTIONS,
+        "HEAD" => RapidRecastHttpMethod::HEAD,
+        "CONNECT" => RapidRecastHttpMethod::CONNECT,
+        "TRACE" => RapidRecastHttpMethod::TRACE,
+        other => return Err(element.problem(format!("Unknown HTTP method '{}'", other))),
+    })
+}
+
+fn actions<'a>(element: &Element) -> Result<Vec<RapidRecastAction<'a>>, XmlProblem> {
+    element.children.iter().map(action).collect()
+}
+
+fn action<'a>(element: &Element) -> Result<RapidRecastAction<'a>, XmlProblem> {
+    Ok(match element.name.as_str() {
+        "create_user" | "add_metadata" | "grant" => {
+            RapidRecastAction::AuthBasedAction(auth_action(element)?)
+        }
+        "if" => RapidRecastAction::LogicBasedAction(logic_action(element)?),
+        "respond" | "redirect" | "serve_file" => {
+            RapidRecastAction::ResponseBasedAction(response_action(element)?)
+        }
+        "forward" => RapidRecastAction::ForwardAction(forward_action(element)?),
+        other => return Err(element.problem(format!("Unknown action <{}>", other))),
+    })
+}
+
+fn response_action<'a>(element: &Element) -> Result<ResponseBasedAction<'a>, XmlProblem> {
+    let response = match element.name.as_str() {
+        "respond" => {
+            let mut headers = vec![];
+            let mut body = None;
+            for child in &element.children {
+                match child.name.as_str() {
+                    "header" => headers.push(ResponseHeader {
+                        name: owned(child.required_attribute("name")?),
+                        value: owned(&child.text),
+                    }),
+                    "body" if body.is_none() => {
+                        let template = match child.attribute("template") {
+                            Some(_) => parse_attribute(child, "template")?,
+                            None => false,
+                        };
+                        body = Some(match template {
+                            true => ResponseBody::Template(owned(&child.text)),
+                            false => ResponseBody::Text(owned(&child.text)),
+                        });
+                    }
+                    _ => return Err(child.unexpected(element)),
+                }
+            }
+            ResponseBasedAction::Respond {
+                status: parse_attribute(element, "status")?,
+                headers,
+                body,
+            }
+        }
+        "redirect" => {
+            expect_empty(element)?;
+            ResponseBasedAction::Redirect {
+                status: parse_attribute(element, "status")?,
+                location: owned(element.required_attribute("location")?),
+            }
+        }
+        _ => {
+            expect_empty(element)?;
+            ResponseBasedAction::ServeFile {
+                root: owned(element.required_attribute("root")?),
+                path: owned(element.required_attribute("path")?),
+                content_type: element.attribute("content_type").map(owned),
+            }
+        }
+    };
+    response.check().map_err(|e| element.problem(e.message))?;
+    Ok(response)
+}
+
+fn forward_action<'a>(element: &Element) -> Result<ForwardAction<'a>, XmlProblem> {
+    let mut forward = ForwardAction {
+        upstreams: vec![],
+        load_balancing: match element.attribute("load_balancing") {
+            None | Some("round_robin") => LoadBalancingStrategy::RoundRobin,
+            Some("random") => LoadBalancingStrategy::Random,
+            Some("least_connections") => LoadBalancingStrategy::LeastConnections,
+            Some("failover") => LoadBalancingStrategy::Failover,
+            Some(other) => {
+                return Err(element.problem(format!("Unknown load balancing strategy '{}'", other)))
+            }
+        },
+        rewrite_path: element.attribute("rewrite_path").map(owned),
+        timeout_ms: match element.attribute("timeout_ms") {
+            Some(_) => Some(parse_attribute(element, "timeout_ms")?),
+            None => None,
+        },
+        remove_headers: vec![],
+        add_headers: BTreeMap::new(),
+        retry: RetryPolicy::default(),
+    };
+    let mut retried = false;
+    for child in &element.children {
+        match child.name.as_str() {
+            "upstream" => forward.upstreams.push(owned(&child.text)),
+            "remove_header" => {
+                expect_empty(child)?;
+                let name = owned(child.required_attribute("name")?);
+                forward.remove_headers.push(header_name(name));
+            }
+            "add_header" => {
+                let name = owned(child.required_attribute("name")?);
+                add_header(&mut forward.add_headers, name, owned(&child.text))
+                    .map_err(|e| child.problem(e.message))?;
+            }
+            "retry" if !retried => {
+                retried = true;
+                forward.retry.attempts = parse_attribute(child, "attempts")?;
+                if child.attribute("backoff_ms").is_some() {
+                    forward.retry.backoff_ms = parse_attribute(child, "backoff_ms")?;
+                }
+                for status in &child.children {
+                    match status.name.as_str() {
+                        "status" => {
+                            expect_empty(status)?;
+                            forward
+                                .retry
+                                .statuses
+                                .push(parse_attribute(status, "code")?);
+                        }
+                        _ => return Err(status.unexpected(child)),
+                    }
+                }
+            }
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    forward.check().map_err(|e| element.problem(e.message))?;
+    Ok(forward)
+}
+
+fn user<'a>(element: &Element) -> Result<UserIdentifier<'a>, XmlProblem> {
+    Ok(UserIdentifier {
+        namespace: owned(element.required_attribute("namespace")?),
+        username: owned(element.required_attribute("username")?),
+    })
+}
+
+fn auth_action<'a>(element: &Element) -> Result<AuthBasedAction<'a>, XmlProblem> {
+    let subject = user(element)?;
+    Ok(match element.name.as_str() {
+        "create_user" => {
+            expect_empty(element)?;
+            AuthBasedAction::CreateUser {
+                subject,
+                password: element.attribute("password").map(owned),
+            }
+        }
+        "add_metadata" => {
+            let mut metadata = BTreeMap::new();
+            for entry in &element.children {
+                if entry.name != "entry" {
+                    return Err(entry.unexpected(element));
+                }
+                metadata.insert(owned(entry.required_attribute("key")?), owned(&entry.text));
+            }
+            AuthBasedAction::AddMetadataToUser { subject, metadata }
+        }
+        _ => AuthBasedAction::GrantPermissions {
+            subject,
+            policy: element
+                .children
+                .iter()
+                .map(|policy| match policy.name.as_str() {
+                    "policy" => rbac_policy(policy),
+                    _ => Err(policy.unexpected(element)),
+                })
+                .collect::<Result<_, _>>()?,
+        },
+    })
+}
+
+/// The RBAC action in the `action` attribute.
+fn rbac_action(element: &Element) -> Result<RapidRecastRbacAction, XmlProblem> {
+    Ok(match element.required_attribute("action")? {
+        "create" => RapidRecastRbacAction::Create,
+        "read" => RapidRecastRbacAction::Read,
+        "update" => RapidRecastRbacAction::Update,
+        "delete" => RapidRecastRbacAction::Delete,
+        "write" => RapidRecastRbacAction::Write,
+        "list" => RapidRecastRbacAction::List,
+        "rename" => RapidRecastRbacAction::Rename,
+        other => return Err(element.problem(format!("Unknown RBAC action '{}'", other))),
+    })
+}
+
+fn rbac_subject<'a>(element: &Element) -> Result<RapidRecastRbacSubject<'a>, XmlProblem> {
+    expect_empty(element)?;
+    Ok(match element.name.as_str() {
+        "admin" => RapidRecastRbacSubject::Admin,
+        "anon" => RapidRecastRbacSubject::Anon,
+        "user" => {
+            let user = user(element)?;
+            RapidRecastRbacSubject::UserOrRole(user.namespace, user.username)
+        }
+        other => return Err(element.problem(format!("Unknown RBAC subject <{}>", other))),
+    })
+}
+
+fn rbac_policy<'a>(element: &Element) -> Result<RapidRecastRbacPolicy<'a>, XmlProblem> {
+    let action = rbac_action(element)?;
+    let mut subject = None;
+    let mut object = None;
+    for child in &element.children {
+        match child.name.as_str() {
+            "admin" | "anon" | "user" if subject.is_none() => subject = Some(rbac_subject(child)?),
+            "namespace" | "protocol" | "topic" | "client" | "mqtt_topic" if object.is_none() => {
+                object = Some(rbac_object(child)?)
+            }
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(RapidRecastRbacPolicy {
+        subject: subject.ok_or_else(|| element.problem("Missing subject in <policy>"))?,
+        object: object.ok_or_else(|| element.problem("Missing object in <policy>"))?,
+        action,
+    })
+}
+
+fn rbac_object<'a>(element: &Element) -> Result<RapidRecastRbacObject<'a>, XmlProblem> {
+    Ok(match element.name.as_str() {
+        "namespace" => {
+            let name = owned(element.required_attribute("name")?);
+            match parse_attribute(element, "exists")? {
+                true => RapidRecastRbacObject::Namespace(NamespaceObject::ExistingNamespace(name)),
+                false => {
+                    RapidRecastRbacObject::Namespace(NamespaceObject::NonExistingNamespace(name))
+                }
+            }
+        }
+        "protocol" => {
+            RapidRecastRbacObject::Protocol(match element.required_attribute("type")? {
+                "http" => RapidRecastProtocolType::HTTP,
+                "websocket" => RapidRecastProtocolType::WebSocket,
+                "kafka" => RapidRecastProtocolType::Kafka,
+                "rabbitmq" => RapidRecastProtocolType::RabbitMQ,
+                "grpc" => RapidRecastProtocolType::Grpc,
+                "mqtt" => RapidRecastProtocolType::Mqtt,
+                other => return Err(element.problem(format!("Unknown protocol '{}'", other))),
+            })
+        }
+        "topic" => RapidRecastRbacObject::Topic(topic_object(element)?),
+        "mqtt_topic" => {
+            expect_empty(element)?;
+            RapidRecastRbacObject::MqttTopic(mqtt_topic_filter(element)?)
+        }
+        _ => RapidRecastRbacObject::Client(match element.required_attribute("type")? {
+            "http1" => ClientObject::Http1,
+            "http2" => ClientObject::Http2,
+            "http3" => ClientObject::Http3,
+            "kafka" => ClientObject::Kafka,
+            "mqtt" => ClientObject::Mqtt,
+            other => return Err(element.problem(format!("Unknown client '{}'", other))),
+        }),
+    })
+}
+
+fn topic_object<'a>(element: &Element) -> Result<TopicObject<'a>, XmlProblem> {
+    let name = owned(element.required_attribute("name")?);
+    Ok(match element.attribute("namespace") {
+        Some(namespace) => TopicObject::NamespaceTopic(owned(namespace), name),
+        None => TopicObject::NonExistingTopic(name),
+    })
+}
+
+fn logic_action<'a>(element: &Element) -> Result<LogicBasedAction<'a>, XmlProblem> {
+    let mut children = element.children.iter();
+    let statement = children
+        .next()
+        .ok_or_else(|| element.problem("Missing condition in <if>"))?;
+    let mut if_true = None;
+    let mut if_false = None;
+    for child in children {
+        match child.name.as_str() {
+            "then" if if_true.is_none() => if_true = Some(Box::new(action(child.only_child()?)?)),
+            "else" if if_false.is_none() => if_false = Some(Box::new(action(child.only_child()?)?)),
+            _ => return Err(child.unexpected(element)),
+        }
+    }
+    Ok(LogicBasedAction::ConditionBlock {
+        condition: condition(statement)?,
+        if_true: if_true.ok_or_else(|| element.problem("Missing <then> in <if>"))?,
+        if_false,
+    })
+}
+
+fn condition<'a>(element: &Element) -> Result<ConditionStatement<'a>, XmlProblem> {
+    if element.name == "not" {
+        return Ok(ConditionStatement::Not(value(element.only_child()?)?));
+    }
+    let operator: fn(RecastValue<'a>, RecastValue<'a>) -> ConditionStatement<'a> =
+        match element.name.as_str() {
+            "equals" => ConditionStatement::Equals,
+            "not_equals" => ConditionStatement::NotEquals,
+            "greater_than" => ConditionStatement::GreaterThan,
+            "less_than" => ConditionStatement::LessThan,
+            "greater_than_or_equal" => ConditionStatement::GreaterThanOrEqual,
+            "less_than_or_equal" => ConditionStatement::LessThanOrEqual,
+            "and" => ConditionStatement::And,
+            "or" => ConditionStatement::Or,
+            other => return Err(element.problem(format!("Unknown condition <{}>", other))),
+        };
+    match element.children.as_slice() {
+        [left, right] => Ok(operator(value(left)?, value(right)?)),
+        _ => Err(element.problem(format!(
+            "Expected two values in <{}>, found {}",
+            element.name,
+            element.children.len()
+        ))),
+    }
+}
+
+fn value<'a>(element: &Element) -> Result<RecastValue<'a>, XmlProblem> {
+    expect_empty(element)?;
+    Ok(match element.name.as_str() {
+        "param" => RecastValue::Param(owned(&element.text)),
+        "string" => RecastValue::String(owned(&element.text)),
+        "number" => RecastValue::Number(
+            element
+                .text
+                .trim()
+                .parse()
+                .map_err(|_| element.problem(format!("Invalid number '{}'", element.text)))?,
+        ),
+        other => return Err(element.problem(format!("Unknown value <{}>", other))),
+    })
+}
@@ -0,0 +1,333 @@
+use crate::ast::action::{
+    AuthBasedAction, ClientObject, ConditionStatement, LogicBasedAction, NamespaceObject,
+    RapidRecastAction, RapidRecastRbacAction, RapidRecastRbacObject, RapidRecastRbacPolicy,
+    RapidRecastRbacSubject, RecastValue, TopicObject, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::RapidModelDefinition;
+use crate::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastProtocolType};
+use crate::ast::topic::{RapidTopicDefinition, TopicRetention};
+use crate::ast::{RapidAstStatement, RapidRecastDefinition, Version};
+use crate::error::SourceSpan;
+use crate::test::schema_with_auth_actions;
+use crate::xml::XmlRRDL;
+use crate::{ParseRRDL, SaveRRDL};
+use std::borrow::Cow;
+
+#[test]
+pub fn bare_minimum() {
+    let input = r#"<?xml version="1.0"?>
+    <definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6"/>
+    "#;
+
+    let parser = XmlRRDL {};
+
+    let res = parser.parse_rrdl(input).unwrap();
+    assert_eq!(
+        res,
+        RapidRecastDefinition {
+            id: Cow::Owned("unique-schema-id-123".to_string()),
+            language_version: Version {
+                major: 1,
+                minor: 2,
+                patch: 3,
+            },
+            file_version: Version {
+                major: 4,
+                minor: 5,
+                patch: 6,
+            },
+            name: None,
+            description: None,
+            ast: vec![],
+        }
+    )
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let input = r#"
+    <definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6">
+        <!-- comments are ignored -->
+        <name>some name</name>
+        <description><![CDATA[some description]]></description>
+        <http sequence="0">
+            <path>/create-user</path>
+            <method>PUT</method>
+            <method>POST</method>
+            <method>GET</method>
+            <actions>
+                <create_user namespace="some-namespace" username="some-username" password="some-password"/>
+                <add_metadata namespace="some-namespace" username="some-username">
+                    <entry key="key1">value1</entry>
+                </add_metadata>
+                <grant namespace="some-namespace" username="some-username">
+                    <policy action="create">
+                        <admin/>
+                        <topic name="some-namespace"/>
+                    </policy>
+                </grant>
+            </actions>
+        </http>
+    </definition>
+    "#;
+    let res = XmlRRDL {}.parse_rrdl(input).unwrap();
+    assert_eq!(res, schema_with_auth_actions());
+}
+
+#[test]
+pub fn every_statement_round_trips() {
+    let mut definition = schema_with_auth_actions();
+    let user = UserIdentifier {
+        namespace: Cow::Borrowed("ns"),
+        username: Cow::Borrowed("u"),
+    };
+    let policy = |subject, object| RapidRecastRbacPolicy {
+        subject,
+        object,
+        action: RapidRecastRbacAction::Update,
+    };
+    definition.ast.extend([
+        RapidAstStatement::ModelDefinition(RapidModelDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            fields: vec![],
+        }),
+        RapidAstStatement::TopicDefinition(RapidTopicDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            partitions: None,
+            retention: TopicRetention::default(),
+            compacted: false,
+            key_schema: None,
+            value_schema: None,
+            acl: vec![],
+        }),
+        RapidAstStatement::CronDefinition(RapidCronDefinition {
+            namespace: Cow::Borrowed("ns"),
+            name: Cow::Borrowed("Empty"),
+            schedule: Cow::Borrowed("@daily"),
+            timezone: None,
+            jitter_ms: 0,
+            overlap: CronOverlapPolicy::Allow,
+            actions: vec![],
+        }),
+        RapidAstStatement::ProtocolDefinition(RapidProtocolDefinition::HttpProtocolDefinition(
+            HttpStatement {
+                sequence: 7,
+                paths: vec![],
+                methods: vec![],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![RapidRecastAction::LogicBasedAction(
+                    LogicBasedAction::ConditionBlock {
+                        condition: ConditionStatement::GreaterThanOrEqual(
+                            RecastValue::Param(Cow::Borrowed("count")),
+                            RecastValue::Number(2.5),
+                        ),
+                        if_true: Box::new(RapidRecastAction::LogicBasedAction(
+                            LogicBasedAction::ConditionBlock {
+                                condition: ConditionStatement::Not(RecastValue::String(
+                                    Cow::Borrowed(""),
+                                )),
+                                if_true: Box::new(RapidRecastAction::AuthBasedAction(
+                                    AuthBasedAction::CreateUser {
+                                        subject: user.clone(),
+                                        password: None,
+                                    },
+                                )),
+                                if_false: None,
+                            },
+                        )),
+                        if_false: Some(Box::new(RapidRecastAction::AuthBasedAction(
+                            AuthBasedAction::GrantPermissions {
+                                subject: user,
+                                policy: vec![
+                                    policy(
+                                        RapidRecastRbacSubject::Anon,
+                                        RapidRecastRbacObject::Namespace(
+                                            NamespaceObject::ExistingNamespace(Cow::Borrowed("a")),
+                                        ),
+                                    ),
+                                    policy(
+                                        RapidRecastRbacSubject::UserOrRole(
+                                            Cow::Borrowed("b"),
+                                            Cow::Borrowed("c"),
+                                        ),
+                                        RapidRecastRbacObject::Topic(TopicObject::NamespaceTopic(
+                                            Cow::Borrowed("d"),
+                                            Cow::Borrowed("e"),
+                                        )),
+                                    ),
+                                    policy(
+                                        RapidRecastRbacSubject::Admin,
+                                        RapidRecastRbacObject::Protocol(
+                                            RapidRecastProtocolType::Kafka,
+                                        ),
+                                    ),
+                                    policy(
+                                        RapidRecastRbacSubject::Admin,
+                                        RapidRecastRbacObject::Client(ClientObject::Mqtt),
+                                    ),
+                                ],
+                            },
+                        ))),
+                    },
+                )],
+            },
+        )),
+    ]);
+    let saved = String::from_utf8(XmlRRDL {}.save_rrdl(&definition).unwrap()).unwrap();
+    assert_eq!(
+        XmlRRDL {}.parse_rrdl(&saved).unwrap(),
+        definition,
+        "Saved as:\n{}",
+        saved
+    );
+}
+
+#[test]
+pub fn unknown_elements_are_errors() {
+    let input = "<definition id=\"id\" language_version=\"1.2.3\" file_version=\"4.5.6\">\n    <smtp/>\n</definition>";
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unexpected element <smtp> in <definition>");
+    assert_eq!(error.span, Some(SourceSpan::point(2, 5)));
+}
+
+#[test]
+pub fn unknown_attributes_are_errors() {
+    let input = "<definition id=\"id\" language_version=\"1.2.3\" file_version=\"4.5.6\">\n    <http sequence=\"0\" secure=\"true\"/>\n</definition>";
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unknown attribute 'secure' on <http>");
+    assert_eq!(error.span, Some(SourceSpan::point(2, 5)));
+}
+
+#[test]
+pub fn duplicate_attributes_are_errors() {
+    let input = "<definition id=\"id\" language_version=\"1.2.3\" file_version=\"4.5.6\">\n    <http sequence=\"0\" sequence=\"1\"/>\n</definition>";
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Duplicate attribute 'sequence' on <http>");
+    assert_eq!(error.span, Some(SourceSpan::point(2, 5)));
+}
+
+#[test]
+pub fn repeated_elements_are_errors() {
+    let input = "<definition id=\"id\" language_version=\"1.2.3\" file_version=\"4.5.6\">\n    <name>one</name>\n    <name>two</name>\n</definition>";
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unexpected element <name> in <definition>");
+    assert_eq!(error.span, Some(SourceSpan::point(3, 5)));
+}
+
+#[test]
+pub fn http_matchers_are_checked() {
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <header name="x-api-version" exact="2" prefix="2."/>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Expected at most one of 'exact', 'prefix' or 'regex' on <header>"
+    );
+
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <host regex="(api"/>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Invalid regex '(api', unclosed group");
+}
+
+#[test]
+pub fn response_actions_are_checked() {
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <actions>
+            <redirect status="302" location="/users/{id"/>
+        </actions>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid template '/users/{id', '{' is never closed, write '{{' for a literal brace"
+    );
+}
+
+#[test]
+pub fn forward_actions_are_checked() {
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <actions>
+            <forward load_balancing="sticky">
+                <upstream>http://users</upstream>
+            </forward>
+        </actions>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Unknown load balancing strategy 'sticky'");
+
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <actions>
+            <forward timeout_ms="1000"/>
+        </actions>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "A forward action needs at least one upstream"
+    );
+}
+
+#[test]
+pub fn path_templates_are_checked() {
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <http sequence="0">
+        <path>/files/{name}.json</path>
+    </http>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid path template '/files/{name}.json', a capture must fill a whole segment"
+    );
+}
+
+#[test]
+pub fn mqtt_topic_filters_are_checked() {
+    let input = r#"<definition id="id" language_version="1.2.3" file_version="4.5.6">
+    <mqtt sequence="5">
+        <on_publish filter="sensors/#/temperature"/>
+    </mqtt>
+</definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid MQTT topic filter 'sensors/#/temperature', '#' must fill the last level"
+    );
+}
+
+#[test]
+pub fn missing_attributes_are_errors() {
+    let input =
+        r#"<definition id="id" language_version="1.2.3" file_version="4.5.6"><http/></definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.message, "Missing attribute 'sequence' on <http>");
+}
+
+#[test]
+pub fn malformed_documents_are_errors() {
+    let input =
+        r#"<definition id="id" language_version="1.2.3" file_version="4.5.6"><http></definition>"#;
+    let error = XmlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert!(error.span.is_some());
+}
@@ -0,0 +1,442 @@
+use crate::test::{
+    bare_minimum_schema, schema_with_auth_actions, schema_with_cron, schema_with_forward,
+    schema_with_grpc, schema_with_http_matchers, schema_with_kafka, schema_with_model,
+    schema_with_mqtt, schema_with_proto_definition, schema_with_rabbitmq, schema_with_responses,
+    schema_with_topic, schema_with_websocket,
+};
+use crate::xml::XmlRRDL;
+use crate::{ParseRRDL, SaveRRDL};
+
+fn save(definition: &crate::ast::RapidRecastDefinition) -> String {
+    String::from_utf8(XmlRRDL {}.save_rrdl(definition).unwrap()).unwrap()
+}
+
+#[test]
+pub fn test_basic_save() {
+    let res = save(&bare_minimum_schema());
+    assert_eq!(
+        res,
+        r#"<definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6">
+    <name>some name</name>
+    <description>some description</description>
+</definition>
+"#
+    );
+}
+
+#[test]
+pub fn test_save_protocol_definition() {
+    let res = save(&schema_with_proto_definition());
+    assert_eq!(
+        res,
+        r#"<definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6">
+    <name>some name</name>
+    <description>some description</description>
+    <http sequence="0">
+        <path>/</path>
+        <method>GET</method>
+    </http>
+</definition>
+"#
+    );
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let definition = schema_with_auth_actions();
+    let res = save(&definition);
+    assert_eq!(
+        res,
+        r#"<definition id="unique-schema-id-123" language_version="1.2.3" file_version="4.5.6">
+    <name>some name</name>
+    <description>some description</description>
+    <http sequence="0">
+        <path>/create-user</path>
+        <method>PUT</method>
+        <method>POST</method>
+        <method>GET</method>
+        <actions>
+            <create_user namespace="some-namespace" username="some-username" password="some-password"/>
+            <add_metadata namespace="some-namespace" username="some-username">
+                <entry key="key1">value1</entry>
+            </add_metadata>
+            <grant namespace="some-namespace" username="some-username">
+                <policy action="create">
+                    <admin/>
+                    <topic name="some-namespace"/>
+                </policy>
+            </grant>
+        </actions>
+    </http>
+</definition>
+"#
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn text_is_escaped() {
+    let mut definition = bare_minimum_schema();
+    definition.id = "a\"b".into();
+    definition.name = Some(" <tag> & ".into());
+    let res = save(&definition);
+    assert!(res.contains("id=\"a&quot;b\""), "{}", res);
+    assert!(res.contains("<name> &lt;tag&gt; &amp; </name>"), "{}", res);
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn model_definition() {
+    let definition = schema_with_model();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <model namespace="some-namespace" name="User">
+        <field name="id">
+            <string/>
+            <regex>^[a-z0-9-]+$</regex>
+        </field>
+        <field name="age" required="false">
+            <integer/>
+            <min>0</min>
+            <max>150</max>
+        </field>
+        <field name="score">
+            <float/>
+            <default>
+                <number>0.5</number>
+            </default>
+        </field>
+        <field name="active">
+            <bool/>
+            <default>
+                <bool>true</bool>
+            </default>
+        </field>
+        <field name="created">
+            <timestamp/>
+        </field>
+        <field name="avatar" required="false">
+            <bytes/>
+        </field>
+        <field name="role">
+            <string/>
+            <default>
+                <string>user</string>
+            </default>
+            <one_of>
+                <string>user</string>
+                <string>admin</string>
+            </one_of>
+        </field>
+        <field name="tags">
+            <list>
+                <string/>
+            </list>
+            <default>
+                <list/>
+            </default>
+            <max>10</max>
+        </field>
+        <field name="display-name">
+            <map>
+                <string/>
+            </map>
+        </field>
+        <field name="matrix">
+            <list>
+                <list>
+                    <float/>
+                </list>
+            </list>
+        </field>
+        <field name="address" required="false">
+            <model namespace="some-namespace" name="Address"/>
+        </field>
+    </model>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn topic_definition() {
+    let definition = schema_with_topic();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <topic namespace="some-namespace" name="users" partitions="12" compacted="true">
+        <retention time_ms="604800000" size_bytes="10737418240"/>
+        <key namespace="some-namespace" name="UserKey"/>
+        <value namespace="some-namespace" name="User"/>
+        <acl action="read">
+            <anon/>
+        </acl>
+        <acl action="write">
+            <user namespace="some-namespace" username="some-username"/>
+        </acl>
+    </topic>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn cron_definition() {
+    let definition = schema_with_cron();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <cron namespace="some-namespace" name="nightly-cleanup" schedule="30 2 * * mon-fri" timezone="Europe/London" jitter_ms="300000" overlap="skip">
+        <actions>
+            <create_user namespace="some-namespace" username="cleanup-bot"/>
+        </actions>
+    </cron>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+
+    let invalid = res.replace("30 2 * * mon-fri", "30 2 * * funday");
+    let error = XmlRRDL {}.parse_rrdl(&invalid).unwrap_err();
+    assert!(
+        error
+            .message
+            .starts_with("Invalid day of week 'funday' in cron schedule"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+pub fn websocket_statement() {
+    let definition = schema_with_websocket();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <websocket sequence="1" path="/chat">
+        <subprotocol>chat.v2</subprotocol>
+        <subprotocol>chat.v1</subprotocol>
+        <on_connect>
+            <create_user namespace="some-namespace" username="guest"/>
+        </on_connect>
+        <on_message kind="text">
+            <when>
+                <equals>
+                    <param>message</param>
+                    <string>ping</string>
+                </equals>
+            </when>
+            <add_metadata namespace="some-namespace" username="guest">
+                <entry key="last_message">ping</entry>
+            </add_metadata>
+        </on_message>
+        <on_message kind="binary"/>
+        <on_close>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn kafka_statement() {
+    let definition = schema_with_kafka();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <kafka sequence="2">
+        <operation>produce</operation>
+        <operation>fetch</operation>
+        <topic namespace="some-namespace" name="orders-*"/>
+        <topic name="some-namespace"/>
+        <consumer_group>billing-*</consumer_group>
+        <actions>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn rabbitmq_statement() {
+    let definition = schema_with_rabbitmq();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <rabbitmq sequence="3">
+        <exchange name="orders" type="topic" durable="true"/>
+        <exchange name="audit" type="fanout"/>
+        <queue name="billing" durable="true"/>
+        <queue name="audit-log"/>
+        <binding queue="billing" exchange="orders" routing_key="order.*.created"/>
+        <binding queue="audit-log" exchange="audit"/>
+        <on_publish exchange="orders" routing_key="order.#">
+            <create_user namespace="some-namespace" username="customer"/>
+        </on_publish>
+        <on_publish exchange="audit"/>
+    </rabbitmq>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn grpc_statement() {
+    let definition = schema_with_grpc();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <grpc sequence="4" service="acme.users.v1.UserService" streaming="server">
+        <method>GetUser</method>
+        <method>List*</method>
+        <metadata name="x-tenant" value="acme"/>
+        <metadata name="authorization"/>
+        <actions>
+            <create_user namespace="acme" username="grpc-client"/>
+        </actions>
+    </grpc>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn mqtt_statement() {
+    let definition = schema_with_mqtt();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <mqtt sequence="5">
+        <on_publish filter="sensors/+/temperature" qos="1" retain="true">
+            <grant namespace="sensors" username="dashboard">
+                <policy action="read">
+                    <user namespace="sensors" username="dashboard"/>
+                    <mqtt_topic filter="sensors/#"/>
+                </policy>
+            </grant>
+        </on_publish>
+        <on_subscribe filter="sensors/#" qos="2"/>
+    </mqtt>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn http_matchers() {
+    let definition = schema_with_http_matchers();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"    <http sequence="0" min_body_bytes="1" max_body_bytes="1048576">
+        <path>/api/{*rest}</path>
+        <method>POST</method>
+        <host exact="api.example.com"/>
+        <content_type prefix="application/json"/>
+        <header name="x-api-version" regex="^2\.[0-9]+$"/>
+        <header name="authorization"/>
+        <query name="dry_run" exact="true"/>
+        <actions>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn responses() {
+    let definition = schema_with_responses();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"        <actions>
+            <if>
+                <greater_than>
+                    <param>id</param>
+                    <number>1000</number>
+                </greater_than>
+                <then>
+                    <redirect status="308" location="/archive/users/{id}"/>
+                </then>
+                <else>
+                    <respond status="200">
+                        <header name="content-type">application/json</header>
+                        <body template="true">{{&quot;id&quot;: {id}}}</body>
+                    </respond>
+                </else>
+            </if>
+        </actions>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert!(
+        res.contains(r#"<serve_file root="/var/www" path="{rest}" content_type="text/css"/>"#),
+        "{}",
+        res
+    );
+    assert!(res.contains(r#"<body>ok</body>"#), "{}", res);
+    assert!(res.contains(r#"<respond status="204"/>"#), "{}", res);
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
+
+#[test]
+pub fn forward() {
+    let definition = schema_with_forward();
+    let res = save(&definition);
+    assert!(
+        res.contains(
+            r#"        <actions>
+            <forward load_balancing="least_connections" rewrite_path="/v2/users/{id}" timeout_ms="5000">
+                <upstream>http://users-a:8080</upstream>
+                <upstream>http://users-b:8080</upstream>
+                <remove_header name="cookie"/>
+                <add_header name="x-forwarded-by">rapidrecast</add_header>
+                <retry attempts="2" backoff_ms="100">
+                    <status code="502"/>
+                    <status code="503"/>
+                </retry>
+            </forward>
+        </actions>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert!(
+        res.contains(
+            r#"            <forward>
+                <upstream>https://legacy.example.com</upstream>
+            </forward>
+"#
+        ),
+        "{}",
+        res
+    );
+    assert_eq!(XmlRRDL {}.parse_rrdl(&res).unwrap(), definition);
+}
@@ -0,0 +1,689 @@
+//! Conversion of the AST into XML elements.
+
+use crate::ast::action::{
+    AuthBasedAction, ClientObject, ConditionStatement, ForwardAction, LoadBalancingStrategy,
+    LogicBasedAction, NamespaceObject, RapidRecastAction, RapidRecastRbacAction,
+    RapidRecastRbacObject, RapidRecastRbacPolicy, RapidRecastRbacSubject, RecastValue,
+    ResponseBasedAction, ResponseBody, RetryPolicy, TopicObject, UserIdentifier,
+};
+use crate::ast::cron::{CronOverlapPolicy, RapidCronDefinition};
+use crate::ast::model::{
+    ModelConstraint, ModelField, ModelFieldType, ModelReference, ModelValue, RapidModelDefinition,
+    ScalarType,
+};
+use crate::ast::protocol::{
+    AmqpExchangeType, AmqpStatement, GrpcStatement, GrpcStreamingKind, HttpStatement,
+    HttpValueMatch, KafkaOperation, KafkaStatement, MqttEvent, MqttQos, MqttStatement,
+    RapidProtocolDefinition, RapidRecastHttpMethod, RapidRecastProtocolType, WebSocketMessageKind,
+    WebSocketStatement,
+};
+use crate::ast::topic::RapidTopicDefinition;
+use crate::ast::{RapidAstStatement, RapidRecastDefinition};
+use crate::xml::element::Element;
+
+pub(crate) fn definition(definition: &RapidRecastDefinition) -> Element {
+    let mut element = Element::new("definition")
+        .with_attribute("id", &definition.id)
+        .with_attribute("language_version", &definition.language_version)
+        .with_attribute("file_version", &definition.file_version);
+    if let Some(name) = &definition.name {
+        element = element.with_child(Element::new("name").with_text(name));
+    }
+    if let Some(description) = &definition.description {
+        element = element.with_child(Element::new("description").with_text(description));
+    }
+    for statement in &definition.ast {
+        element = element.with_child(match statement {
+            RapidAstStatement::ModelDefinition(model) => model_definition(model),
+            RapidAstStatement::ProtocolDefinition(protocol) => protocol_definition(protocol),
+            RapidAstStatement::TopicDefinition(topic) => topic_definition(topic),
+            RapidAstStatement::CronDefinition(cron) => cron_definition(cron),
+        });
+    }
+    element
+}
+
+fn model_definition(model: &RapidModelDefinition) -> Element {
+    model.fields.iter().fold(
+        Element::new("model")
+            .with_attribute("namespace", &model.namespace)
+            .with_attribute("name", &model.name),
+        |element, field| element.with_child(model_field(field)),
+    )
+}
+
+fn model_field(field: &ModelField) -> Element {
+    let mut element = Element::new("field").with_attribute("name", &field.name);
+    if !field.required {
+        element = element.with_attribute("required", false);
+    }
+    element = element.with_child(model_field_type(&field.field_type));
+    if let Some(default) = &field.default {
+        element = element.with_child(Element::new("default").with_child(model_value(default)));
+    }
+    for constraint in &field.constraints {
+        element = element.with_child(match constraint {
+            ModelConstraint::Min(min) => Element::new("min").with_text(&min.to_string()),
+            ModelConstraint::Max(max) => Element::new("max").with_text(&max.to_string()),
+            ModelConstraint::Regex(regex) => Element::new("regex").with_text(regex),
+            ModelConstraint::OneOf(values) => values
+                .iter()
+                .fold(Element::new("one_of"), |element, value| {
+                    element.with_child(model_value(value))
+                }),
+        });
+    }
+    element
+}
+
+fn model_field_type(field_type: &ModelFieldType) -> Element {
+    match field_type {
+        ModelFieldType::Scalar(scalar) => Element::new(match scalar {
+            ScalarType::String => "string",
+            ScalarType::Integer => "integer",
+            ScalarType::Float => "float",
+            ScalarType::Bool => "bool",
+            ScalarType::Timestamp => "timestamp",
+            ScalarType::Bytes => "bytes",
+        }),
+        ModelFieldType::List(item) => Element::new("list").with_child(model_field_type(item)),
+        ModelFieldType::Map(value) => Element::new("map").with_child(model_field_type(value)),
+        ModelFieldType::Model(model) => model_reference(model),
+    }
+}
+
+fn model_reference(model: &ModelReference) -> Element {
+    Element::new("model")
+        .with_attribute("namespace", &model.namespace)
+        .with_attribute("name", &model.name)
+}
+
+fn model_value(value: &ModelValue) -> Element {
+    match value {
+        ModelValue::String(s) => Element::new("string").with_text(s),
+        ModelValue::Number(n) => Element::new("number").with_text(&n.to_string()),
+        ModelValue::Bool(b) => Element::new("bool").with_text(&b.to_string()),
+        ModelValue::List(values) => values.iter().fold(Element::new("list"), |element, value| {
+            element.with_child(model_value(value))
+        }),
+    }
+}
+
+fn topic_definition(topic: &RapidTopicDefinition) -> Element {
+    let mut element = Element::new("topic")
+        .with_attribute("namespace", &topic.namespace)
+        .with_attribute("name", &topic.name);
+    if let Some(partitions) = topic.partitions {
+        element = element.with_attribute("partitions", partitions);
+    }
+    if topic.compacted {
+        element = element.with_attribute("compacted", true);
+    }
+    let retention = &topic.retention;
+    if retention.time_ms.is_some() || retention.size_bytes.is_some() {
+        let mut child = Element::new("retention");
+        if let Some(time_ms) = retention.time_ms {
+            child = child.with_attribute("time_ms", time_ms);
+        }
+        if let Some(size_bytes) = retention.size_bytes {
+            child = child.with_attribute("size_bytes", size_bytes);
+        }
+        element = element.with_child(child);
+    }
+    if let Some(key) = &topic.key_schema {
+        element = element.with_child(
+            Element::new("key")
+                .with_attribute("namespace", &key.namespace)
+                .with_attribute("name", &key.name),
+        );
+    }
+    if let Some(value) = &topic.value_schema {
+        element = element.with_child(
+            Element::new("value")
+                .with_attribute("namespace", &value.namespace)
+                .with_attribute("name", &value.name),
+        );
+    }
+    topic.acl.iter().fold(element, |element, acl| {
+        element.with_child(
+            Element::new("acl")
+                .with_attribute("action", rbac_action(&acl.action))
+                .with_child(rbac_subject(&acl.subject)),
+        )
+    })
+}
+
+fn cron_definition(cron: &RapidCronDefinition) -> Element {
+    let mut element = Element::new("cron")
+        .with_attribute("namespace", &cron.namespace)
+        .with_attribute("name", &cron.name)
+        .with_attribute("schedule", &cron.schedule);
+    if let Some(timezone) = &cron.timezone {
+        element = element.with_attribute("timezone", timezone);
+    }
+    if cron.jitter_ms != 0 {
+        element = element.with_attribute("jitter_ms", cron.jitter_ms);
+    }
+    match cron.overlap {
+        CronOverlapPolicy::Allow => {}
+        CronOverlapPolicy::Skip => element = element.with_attribute("overlap", "skip"),
+        CronOverlapPolicy::Queue => element = element.with_attribute("overlap", "queue"),
+    }
+    if !cron.actions.is_empty() {
+        element = element.with_child(actions(&cron.actions));
+    }
+    element
+}
+
+fn protocol_definition(protocol: &RapidProtocolDefinition) -> Element {
+    match protocol {
+        RapidProtocolDefinition::HttpProtocolDefinition(http) => http_statement(http),
+        RapidProtocolDefinition::WebSocketProtocolDefinition(websocket) => {
+            websocket_statement(websocket)
+        }
+        RapidProtocolDefinition::KafkaProtocolDefinition(kafka) => kafka_statement(kafka),
+        RapidProtocolDefinition::RabbitMQProtocolDefinition(amqp) => amqp_statement(amqp),
+        RapidProtocolDefinition::GrpcProtocolDefinition(grpc) => grpc_statement(grpc),
+        RapidProtocolDefinition::MqttProtocolDefinition(mqtt) => mqtt_statement(mqtt),
+    }
+}
+
+fn http_statement(http: &HttpStatement) -> Element {
+    let mut element = Element::new("http").with_attribute("sequence", http.sequence);
+    if let Some(min) = http.min_body_bytes {
+        element = element.with_attribute("min_body_bytes", min);
+    }
+    if let Some(max) = http.max_body_bytes {
+        element = element.with_attribute("max_body_bytes", max);
+    }
+    for path in &http.paths {
+        element = element.with_child(Element::new("path").with_text(path));
+    }
+    for method in &http.methods {
+        element = element.with_child(Element::new("method").with_text(http_method(method)));
+    }
+    if let Some(host) = &http.host {
+        element = element.with_child(http_value_match(Element::new("host"), host));
+    }
+    if let Some(content_type) = &http.content_type {
+        element = element.with_child(http_value_match(Element::new("content_type"), content_type));
+    }
+    for (name, fields) in [("header", &http.headers), ("query", &http.query)] {
+        for field in fields {
+            element = element.with_child(http_value_match(
+                Element::new(name).with_attribute("name", &field.name),
+                &field.value,
+            ));
+        }
+    }
+    if !http.actions.is_empty() {
+        element = element.with_child(actions(&http.actions));
+    }
+    element
+}
+
+fn http_value_match(element: Element, value: &HttpValueMatch) -> Element {
+    match value {
+        HttpValueMatch::Exact(value) => element.with_attribute("exact", value),
+        HttpValueMatch::Prefix(prefix) => element.with_attribute("prefix", prefix),
+        HttpValueMatch::Regex(regex) => element.with_attribute("regex", regex),
+        HttpValueMatch::Present => element,
+    }
+}
+
+fn websocket_statement(websocket: &WebSocketStatement) -> Element {
+    let mut element = Element::new("websocket")
+        .with_attribute("sequence", websocket.sequence)
+        .with_attribute("path", &websocket.path);
+    for subprotocol in &websocket.subprotocols {
+        element = element.with_child(Element::new("subprotocol").with_text(subprotocol));
+    }
+    if !websocket.on_connect.is_empty() {
+        element = element.with_child(event("on_connect", &websocket.on_connect));
+    }
+    for handler in &websocket.on_message {
+        let mut child = Element::new("on_message").with_attribute(
+            "kind",
+            match handler.kind {
+                WebSocketMessageKind::Text => "text",
+                WebSocketMessageKind::Binary => "binary",
+            },
+        );
+        if let Some(statement) = &handler.condition {
+            child = child.with_child(Element::new("when").with_child(condition(statement)));
+        }
+        element = element.with_child(
+            handler
+                .actions
+                .iter()
+                .fold(child, |element, a| element.with_child(action(a))),
+        );
+    }
+    if !websocket.on_close.is_empty() {
+        element = element.with_child(event("on_close", &websocket.on_close));
+    }
+    element
+}
+
+fn kafka_statement(kafka: &KafkaStatement) -> Element {
+    let mut element = Element::new("kafka").with_attribute("sequence", kafka.sequence);
+    for operation in &kafka.operations {
+        element = element.with_child(Element::new("operation").with_text(match operation {
+            KafkaOperation::Produce => "produce",
+            KafkaOperation::Fetch => "fetch",
+        }));
+    }
+    for topic in &kafka.topics {
+        element = element.with_child(topic_object(topic));
+    }
+    for group in &kafka.consumer_groups {
+        element = element.with_child(Element::new("consumer_group").with_text(group));
+    }
+    if !kafka.actions.is_empty() {
+        element = element.with_child(actions(&kafka.actions));
+    }
+    element
+}
+
+fn amqp_statement(amqp: &AmqpStatement) -> Element {
+    let durable = |element: Element, durable: bool| match durable {
+        true => element.with_attribute("durable", true),
+        false => element,
+    };
+    let mut element = Element::new("rabbitmq").with_attribute("sequence", amqp.sequence);
+    for exchange in &amqp.exchanges {
+        let exchange_type = match exchange.exchange_type {
+            AmqpExchangeType::Direct => "direct",
+            AmqpExchangeType::Fanout => "fanout",
+            AmqpExchangeType::Topic => "topic",
+            AmqpExchangeType::Headers => "headers",
+        };
+        element = element.with_child(durable(
+            Element::new("exchange")
+                .with_attribute("name", &exchange.name)
+                .with_attribute("type", exchange_type),
+            exchange.durable,
+        ));
+    }
+    for queue in &amqp.queues {
+        element = element.with_child(durable(
+            Element::new("queue").with_attribute("name", &queue.name),
+            queue.durable,
+        ));
+    }
+    for binding in &amqp.bindings {
+        let mut child = Element::new("binding")
+            .with_attribute("queue", &binding.queue)
+            .with_attribute("exchange", &binding.exchange);
+        if !binding.routing_key.is_empty() {
+            child = child.with_attribute("routing_key", &binding.routing_key);
+        }
+        element = element.with_child(child);
+    }
+    for handler in &amqp.on_publish {
+        let mut child =
+            event("on_publish", &handler.actions).with_attribute("exchange", &handler.exchange);
+        if let Some(routing_key) = &handler.routing_key {
+            child = child.with_attribute("routing_key", routing_key);
+        }
+        element = element.with_child(child);
+    }
+    element
+}
+
+fn grpc_statement(grpc: &GrpcStatement) -> Element {
+    let mut element = Element::new("grpc")
+        .with_attribute("sequence", grpc.sequence)
+        .with_attribute("service", &grpc.service);
+    if let Some(streaming) = &grpc.streaming {
+        element = element.with_attribute(
+            "streaming",
+            match streaming {
+                GrpcStreamingKind::Unary => "unary",
+                GrpcStreamingKind::ClientStreaming => "client",
+                GrpcStreamingKind::ServerStreaming => "server",
+                GrpcStreamingKind::Bidirectional => "bidirectional",
+            },
+        );
+    }
+    for method in &grpc.methods {
+        element = element.with_child(Element::new("method").with_text(method));
+    }
+    for metadata in &grpc.metadata {
+        let mut child = Element::new("metadata").with_attribute("name", &metadata.name);
+        if let Some(value) = &metadata.value {
+            child = child.with_attribute("value", value);
+        }
+        element = element.with_child(child);
+    }
+    if !grpc.actions.is_empty() {
+        element = element.with_child(actions(&grpc.actions));
+    }
+    element
+}
+
+fn mqtt_statement(mqtt: &MqttStatement) -> Element {
+    let mut element = Element::new("mqtt").with_attribute("sequence", mqtt.sequence);
+    for handler in &mqtt.handlers {
+        let event_name = match handler.event {
+            MqttEvent::Publish => "on_publish",
+            MqttEvent::Subscribe => "on_subscribe",
+        };
+        let mut child =
+            event(event_name, &handler.actions).with_attribute("filter", &handler.topic_filter);
+        if let Some(qos) = &handler.qos {
+            child = child.with_attribute(
+                "qos",
+                match qos {
+                    MqttQos::AtMostOnce => 0,
+                    MqttQos::AtLeastOnce => 1,
+                    MqttQos::ExactlyOnce => 2,
+                },
+            );
+        }
+        if let Some(retain) = handler.retain {
+            child = child.with_attribute("retain", retain);
+        }
+        element = element.with_child(child);
+    }
+    element
+}
+
+/// An element holding the actions to take on an event.
+fn event(name: &str, actions: &[RapidRecastAction]) -> Element {
+    actions.iter().fold(Element::new(name), |element, a| {
+        element.with_child(action(a))
+    })
+}
+
+fn http_method(method: &RapidRecastHttpMethod) -> &'static str {
+    match method {
+        RapidRecastHttpMethod::GET => "GET",
+        RapidRecastHttpMethod::POST => "POST",
+        RapidRecastHttpMethod::DELETE => "DELETE",
+        RapidRecastHttpMethod::UPDATE => "UPDATE",
+        RapidRecastHttpMethod::PATCH => "PATCH",
+        RapidRecastHttpMethod::PUT => "PUT",
+        RapidRecastHttpMethod::OPTIONS => "OPTIONS",
+        RapidRecastHttpMethod::HEAD => "HEAD",
+        RapidRecastHttpMethod::CONNECT => "CONNECT",
+        RapidRecastHttpMethod::TRACE => "TRACE",
+    }
+}
+
+fn actions(actions: &[RapidRecastAction]) -> Element {
+    actions.iter().fold(Element::new("actions"), |element, a| {
+        element.with_child(action(a))
+    })
+}
+
+fn action(action: &RapidRecastAction) -> Element {
+    match action {
+        RapidRecastAction::AuthBasedAction(auth) => auth_action(auth),
+        RapidRecastAction::LogicBasedAction(logic) => logic_action(logic),
+        RapidRecastAction::ResponseBasedAction(response) => response_action(response),
+        RapidRecastAction::ForwardAction(forward) => forward_action(forward),
+    }
+}
+
+fn forward_action(forward: &ForwardAction) -> Element {
+    let mut element = Element::new("forward");
+    match forward.load_balancing {
+        LoadBalancingStrategy::RoundRobin => {}
+        LoadBalancingStrategy::Random => {
+            element = element.with_attribute("load_balancing", "random")
+        }
+        LoadBalancingStrategy::LeastConnections => {
+            element = element.with_attribute("load_balancing", "least_connections")
+        }
+        LoadBalancingStrategy::Failover => {
+            element = element.with_attribute("load_balancing", "failover")
+        }
+    }
+    if let Some(rewrite) = &forward.rewrite_path {
+        element = element.with_attribute("rewrite_path", rewrite);
+    }
+    if let Some(timeout) = forward.timeout_ms {
+        element = element.with_attribute("timeout_ms", timeout);
+    }
+    for upstream in &forward.upstreams {
+        element = element.with_child(Element::new("upstream").with_text(upstream));
+    }
+    for name in &forward.remove_headers {
+        element = element.with_child(Element::new("remove_header").with_attribute("name", name));
+    }
+    for (name, value) in &forward.add_headers {
+        element = element.with_child(
+            Element::new("add_header")
+                .with_attribute("name", name)
+                .with_text(value),
+        );
+    }
+    let retry = &forward.retry;
+    if *retry != RetryPolicy::default() {
+        let mut child = Element::new("retry").with_attribute("attempts", retry.attempts);
+        if retry.backoff_ms != 0 {
+            child = child.with_attribute("backoff_ms", retry.backoff_ms);
+        }
+        for status in &retry.statuses {
+            child = child.with_child(Element::new("status").with_attribute("code", status));
+        }
+        element = element.with_child(child);
+    }
+    element
+}
+
+fn response_action(response: &ResponseBasedAction) -> Element {
+    match response {
+        ResponseBasedAction::Respond {
+            status,
+            headers,
+            body,
+        } => {
+            let mut element = Element::new("respond").with_attribute("status", status);
+            for header in headers {
+                element = element.with_child(
+                    Element::new("header")
+                        .with_attribute("name", &header.name)
+                        .with_text(&header.value),
+                );
+            }
+            match body {
+                Some(ResponseBody::Text(text)) => {
+                    element.with_child(Element::new("body").with_text(text))
+                }
+                Some(ResponseBody::Template(template)) => element.with_child(
+                    Element::new("body")
+                        .with_attribute("template", true)
+                        .with_text(template),
+                ),
+                None => element,
+            }
+        }
+        ResponseBasedAction::Redirect { status, location } => Element::new("redirect")
+            .with_attribute("status", status)
+            .with_attribute("location", location),
+        ResponseBasedAction::ServeFile {
+            root,
+            path,
+            content_type,
+        } => {
+            let element = Element::new("serve_file")
+                .with_attribute("root", root)
+                .with_attribute("path", path);
+            match content_type {
+                Some(content_type) => element.with_attribute("content_type", content_type),
+                None => element,
+            }
+        }
+    }
+}
+
+fn user(element: Element, user: &UserIdentifier) -> Element {
+    element
+        .with_attribute("namespace", &user.namespace)
+        .with_attribute("username", &user.username)
+}
+
+fn auth_action(auth: &AuthBasedAction) -> Element {
+    match auth {
+        AuthBasedAction::CreateUser { subject, password } => {
+            let element = user(Element::new("create_user"), subject);
+            match password {
+                Some(password) => element.with_attribute("password", password),
+                None => element,
+            }
+        }
+        AuthBasedAction::AddMetadataToUser { subject, metadata } => metadata.iter().fold(
+            user(Element::new("add_metadata"), subject),
+            |element, (key, value)| {
+                element.with_child(
+                    Element::new("entry")
+                        .with_attribute("key", key)
+                        .with_text(value),
+                )
+            },
+        ),
+        AuthBasedAction::GrantPermissions { subject, policy } => policy
+            .iter()
+            .fold(user(Element::new("grant"), subject), |element, policy| {
+                element.with_child(rbac_policy(policy))
+            }),
+    }
+}
+
+fn rbac_subject(subject: &RapidRecastRbacSubject) -> Element {
+    match subject {
+        RapidRecastRbacSubject::Admin => Element::new("admin"),
+        RapidRecastRbacSubject::Anon => Element::new("anon"),
+        RapidRecastRbacSubject::UserOrRole(namespace, username) => Element::new("user")
+            .with_attribute("namespace", namespace)
+            .with_attribute("username", username),
+    }
+}
+
+fn rbac_policy(policy: &RapidRecastRbacPolicy) -> Element {
+    Element::new("policy")
+        .with_attribute("action", rbac_action(&policy.action))
+        .with_child(rbac_subject(&policy.subject))
+        .with_child(rbac_object(&policy.object))
+}
+
+fn rbac_action(action: &RapidRecastRbacAction) -> &'static str {
+    match action {
+        RapidRecastRbacAction::Create => "create",
+        RapidRecastRbacAction::Read => "read",
+        RapidRecastRbacAction::Update => "update",
+        RapidRecastRbacAction::Delete => "delete",
+        RapidRecastRbacAction::Write => "write",
+        RapidRecastRbacAction::List => "list",
+        RapidRecastRbacAction::Rename => "rename",
+    }
+}
+
+fn rbac_object(object: &RapidRecastRbacObject) -> Element {
+    match object {
+        RapidRecastRbacObject::Namespace(NamespaceObject::ExistingNamespace(namespace)) => {
+            Element::new("namespace")
+                .with_attribute("name", namespace)
+                .with_attribute("exists", true)
+        }
+        RapidRecastRbacObject::Namespace(NamespaceObject::NonExistingNamespace(namespace)) => {
+            Element::new("namespace")
+                .with_attribute("name", namespace)
+                .with_attribute("exists", false)
+        }
+        RapidRecastRbacObject::Protocol(protocol) => {
+            Element::new("protocol").with_attribute("type", protocol_type(protocol))
+        }
+        RapidRecastRbacObject::Topic(topic) => topic_object(topic),
+        RapidRecastRbacObject::Client(client) => {
+            Element::new("client").with_attribute("type", client_type(client))
+        }
+        RapidRecastRbacObject::MqttTopic(filter) => {
+            Element::new("mqtt_topic").with_attribute("filter", filter)
+        }
+        RapidRecastRbacObject::Model(model) => match *model {},
+    }
+}
+
+fn topic_object(topic: &TopicObject) -> Element {
+    match topic {
+        TopicObject::NonExistingTopic(topic) => Element::new("topic").with_attribute("name", topic),
+        TopicObject::NamespaceTopic(namespace, topic) => Element::new("topic")
+            .with_attribute("namespace", namespace)
+            .with_attribute("name", topic),
+    }
+}
+
+fn protocol_type(protocol: &RapidRecastProtocolType) -> &'static str {
+    match protocol {
+        RapidRecastProtocolType::HTTP => "http",
+        RapidRecastProtocolType::WebSocket => "websocket",
+        RapidRecastProtocolType::Kafka => "kafka",
+        RapidRecastProtocolType::RabbitMQ => "rabbitmq",
+        RapidRecastProtocolType::Grpc => "grpc",
+        RapidRecastProtocolType::Mqtt => "mqtt",
+    }
+}
+
+fn client_type(client: &ClientObject) -> &'static str {
+    match client {
+        ClientObject::Http1 => "http1",
+        ClientObject::Http2 => "http2",
+        ClientObject::Http3 => "http3",
+        ClientObject::Kafka => "kafka",
+        ClientObject::Mqtt => "mqtt",
+    }
+}
+
+fn logic_action(logic: &LogicBasedAction) -> Element {
+    match logic {
+        LogicBasedAction::ConditionBlock {
+            condition: statement,
+            if_true,
+            if_false,
+        } => {
+            let element = Element::new("if")
+                .with_child(condition(statement))
+                .with_child(Element::new("then").with_child(action(if_true)));
+            match if_false {
+                Some(if_false) => {
+                    element.with_child(Element::new("else").with_child(action(if_false)))
+                }
+                None => element,
+            }
+        }
+    }
+}
+
+fn condition(condition: &ConditionStatement) -> Element {
+    let binary = |name: &str, left: &RecastValue, right: &RecastValue| {
+        Element::new(name)
+            .with_child(value(left))
+            .with_child(value(right))
+    };
+    match condition {
+        ConditionStatement::Equals(left, right) => binary("equals", left, right),
+        ConditionStatement::NotEquals(left, right) => binary("not_equals", left, right),
+        ConditionStatement::GreaterThan(left, right) => binary("greater_than", left, right),
+        ConditionStatement::LessThan(left, right) => binary("less_than", left, right),
+        ConditionStatement::GreaterThanOrEqual(left, right) => {
+            binary("greater_than_or_equal", left, right)
+        }
+        ConditionStatement::LessThanOrEqual(left, right) => {
+            binary("less_than_or_equal", left, right)
+        }
+        ConditionStatement::And(left, right) => binary("and", left, right),
+        ConditionStatement::Or(left, right) => binary("or", left, right),
+        ConditionStatement::Not(v) => Element::new("not").with_child(value(v)),
+    }
+}
+
+fn value(value: &RecastValue) -> Element {
+    match value {
+        RecastValue::Param(param) => Element::new("param").with_text(param),
+        RecastValue::String(s) => Element::new("string").with_text(s),
+        RecastValue::Number(n) => Element::new("number").with_text(&n.to_string()),
+    }
+}
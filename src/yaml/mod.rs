@@ -0,0 +1,64 @@
+//! Yaml parser and writer for RapidRecast Definition Language
+//!
+//! YAML cannot put two tags on one node, and the AST nests enums (a `ProtocolDefinition`
+//! holding an `HttpProtocolDefinition`), so enums are written as single-key mappings
+//! instead of tags. The layout is the same as the JSON one.
+#[cfg(test)]
+mod test;
+
+use crate::ast::RapidRecastDefinition;
+use crate::error::RrdlError;
+use crate::{ParseRRDL, SaveRRDL};
+use serde_yaml::with::singleton_map_recursive;
+use serde_yaml::{Mapping, Value};
+use std::io::Write;
+
+/// A parser for the RapidRecastDefinition Language in YAML
+pub struct YamlRRDL {}
+
+impl ParseRRDL for YamlRRDL {
+    fn parse_rrdl<'input>(
+        &self,
+        input: &'input str,
+    ) -> Result<RapidRecastDefinition<'input>, RrdlError> {
+        // serde_yaml only reads enums from tags, unless told to read single-key mappings.
+        let deserializer = serde_yaml::Deserializer::from_str(input);
+        let definition: RapidRecastDefinition = singleton_map_recursive::deserialize(deserializer)?;
+        definition.validate().map_err(|e| e.located_in(input))?;
+        Ok(definition)
+    }
+}
+
+impl SaveRRDL for YamlRRDL {
+    fn save_to<W: Write>(
+        &self,
+        definition: &RapidRecastDefinition,
+        writer: W,
+    ) -> Result<(), RrdlError> {
+        let value = untag(serde_yaml::to_value(definition).map_err(RrdlError::from)?);
+        serde_yaml::to_writer(writer, &value).map_err(RrdlError::from)
+    }
+}
+
+/// Replace every tagged value with a single-key mapping from the tag to the value.
+fn untag(value: Value) -> Value {
+    match value {
+        Value::Tagged(tagged) => {
+            let mut mapping = Mapping::new();
+            let tag = tagged.tag.to_string();
+            mapping.insert(
+                Value::String(tag.trim_start_matches('!').to_string()),
+                untag(tagged.value),
+            );
+            Value::Mapping(mapping)
+        }
+        Value::Sequence(sequence) => Value::Sequence(sequence.into_iter().map(untag).collect()),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (untag(key), untag(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
@@ -0,0 +1,203 @@
+use crate::ast::protocol::{HttpStatement, RapidProtocolDefinition, RapidRecastHttpMethod};
+use crate::ast::topic::{RapidTopicDefinition, TopicRetention};
+use crate::ast::RapidAstStatement::{ProtocolDefinition, TopicDefinition};
+use crate::ast::{RapidRecastDefinition, Version};
+use crate::error::SourceSpan;
+use crate::test::schema_with_auth_actions;
+use crate::yaml::YamlRRDL;
+use crate::ParseRRDL;
+use std::borrow::Cow;
+
+#[test]
+pub fn bare_minimum() {
+    let input = r#"
+id: unique-schema-id-123
+language_version: 1.2.3
+file_version: 4.5.6
+ast: []
+"#;
+
+    let parser = YamlRRDL {};
+
+    let res = parser.parse_rrdl(input).unwrap();
+    assert_eq!(
+        res,
+        RapidRecastDefinition {
+            id: Cow::Owned("unique-schema-id-123".to_string()),
+            language_version: Version {
+                major: 1,
+                minor: 2,
+                patch: 3,
+            },
+            file_version: Version {
+                major: 4,
+                minor: 5,
+                patch: 6,
+            },
+            name: None,
+            description: None,
+            ast: vec![],
+        }
+    )
+}
+
+#[test]
+pub fn protocol_definition() {
+    let input = r#"
+id: unique-schema-id-123
+language_version: 1.2.3
+file_version: 4.5.6
+ast:
+  - ProtocolDefinition:
+      HttpProtocolDefinition:
+        sequence: 0
+        paths: ["/user"]
+        methods: [POST]
+        actions: []
+"#;
+
+    let parser = YamlRRDL {};
+
+    let res = parser.parse_rrdl(input).unwrap();
+    assert_eq!(
+        res.ast,
+        vec![ProtocolDefinition(
+            RapidProtocolDefinition::HttpProtocolDefinition(HttpStatement {
+                sequence: 0,
+                paths: vec![Cow::Borrowed("/user")],
+                methods: vec![RapidRecastHttpMethod::POST],
+                min_body_bytes: None,
+                max_body_bytes: None,
+                host: None,
+                content_type: None,
+                headers: vec![],
+                query: vec![],
+                actions: vec![],
+            })
+        )]
+    );
+}
+
+#[test]
+pub fn gh_1_http_to_create_user_and_permissions() {
+    let input = r#"
+id: unique-schema-id-123
+language_version: 1.2.3
+file_version: 4.5.6
+name: some name
+description: some description
+ast:
+  - ProtocolDefinition:
+      HttpProtocolDefinition:
+        sequence: 0
+        paths: [/create-user]
+        methods: [PUT, POST, GET]
+        actions:
+          - AuthBasedAction:
+              CreateUser:
+                subject: { namespace: some-namespace, username: some-username }
+                password: some-password
+          - AuthBasedAction:
+              AddMetadataToUser:
+                subject: { namespace: some-namespace, username: some-username }
+                metadata:
+                  key1: value1
+          - AuthBasedAction:
+              GrantPermissions:
+                subject: { namespace: some-namespace, username: some-username }
+                policy:
+                  - subject: Admin
+                    object:
+                      Topic:
+                        NonExistingTopic: some-namespace
+                    action: Create
+"#;
+
+    let res = YamlRRDL {}.parse_rrdl(input).unwrap();
+    assert_eq!(res, schema_with_auth_actions());
+}
+
+#[test]
+pub fn syntax_errors_have_positions() {
+    let input = "id: x\nlanguage_version: 1.2.3\n: : :\n";
+    let error = YamlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.span, Some(SourceSpan::point(3, 1)));
+    assert!(!error.message.contains("line 3"), "{}", error.message);
+}
+
+#[test]
+pub fn type_errors_have_positions() {
+    let input = "id: x\nlanguage_version: 1.2.3\nfile_version: [4, 5, 6]\n";
+    let error = YamlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(error.span, Some(SourceSpan::point(3, 15)));
+    assert!(
+        error
+            .message
+            .starts_with("file_version: invalid type: sequence"),
+        "{}",
+        error.message
+    );
+}
+
+#[test]
+pub fn errors_render_with_the_source() {
+    let input = "id: x\nlanguage_version: 1.2.3\nfile_version: 4.5.6\nast:\n  - Nope: {}\n";
+    let error = YamlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.with_file("definition.yaml").render(input),
+        r#"error: ast[0]: unknown variant `Nope`, expected one of `ModelDefinition`, `ProtocolDefinition`, `TopicDefinition`, `CronDefinition`
+ --> definition.yaml:5:5
+  |
+5 |   - Nope: {}
+  |     ^
+"#
+    );
+}
+
+#[test]
+pub fn path_templates_are_checked() {
+    let input = r#"
+id: unique-schema-id-123
+language_version: 1.2.3
+file_version: 4.5.6
+ast:
+  - ProtocolDefinition:
+      HttpProtocolDefinition:
+        sequence: 0
+        paths: ["/users/{id:bogus}"]
+        methods: [GET]
+        actions: []
+"#;
+    let error = YamlRRDL {}.parse_rrdl(input).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Invalid path template '/users/{id:bogus}', unknown param type 'bogus', expected one of string, int, float, uuid"
+    );
+}
+
+#[test]
+pub fn topic_defaults_may_be_left_out() {
+    let input = r#"
+id: unique-schema-id-123
+language_version: 1.2.3
+file_version: 4.5.6
+ast:
+  - TopicDefinition:
+      namespace: orders
+      name: created
+"#;
+    let res = YamlRRDL {}.parse_rrdl(input).unwrap();
+    assert_eq!(
+        res.ast,
+        vec![TopicDefinition(RapidTopicDefinition {
+            namespace: Cow::Borrowed("orders"),
+            name: Cow::Borrowed("created"),
+            partitions: None,
+            retention: TopicRetention::default(),
+            compacted: false,
+            key_schema: None,
+            value_schema: None,
+            acl: vec![],
+        })]
+    );
+}
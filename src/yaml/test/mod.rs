@@ -0,0 +1,2 @@
+mod deserialisation;
+mod serialisation;